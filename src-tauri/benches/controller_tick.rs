@@ -0,0 +1,109 @@
+//! Measures heap allocations of the mouse controller thread in steady state.
+//!
+//! The controller runs on a `ManualClock` that this thread advances by the tick
+//! duration as soon as an iteration is finished, so the loop runs as fast as possible.
+//!
+//! Run with `cargo bench --bench controller_tick`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use app_lib::mouse_controller::{ManualClock, MouseController};
+use app_lib::patterns::{ActivationRule, Sensitivity, Step};
+
+/// Duration of a single controller iteration
+const TICK: Duration = Duration::from_millis(24);
+/// Number of iterations before measuring, so that the pattern reaches its last step
+const WARMUP_TICKS: u32 = 1_000;
+/// Number of measured iterations
+const MEASURED_TICKS: u32 = 100_000;
+
+/// Number of heap allocations performed by the whole process
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// System allocator that counts every allocation
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Creates a step that does not move the mouse, so the benchmark leaves the cursor alone
+fn step(duration: u64) -> Step {
+    Step {
+        duration,
+        dx: 0,
+        dy: 0,
+        adjusted_dx: 0,
+        adjusted_dy: 0,
+    }
+}
+
+/// Lets the controller thread run the given iterations, one tick of virtual time each
+///
+/// # Arguments
+/// * `clock` - Clock the controller thread sleeps on
+/// * `from` - Number of iterations already run
+/// * `to` - Number of iterations after which to stop
+fn run_ticks(clock: &ManualClock, from: u32, to: u32) {
+    for tick in from + 1..=to {
+        clock.wait_for_sleeps(u64::from(tick));
+        clock.advance(TICK);
+    }
+}
+
+fn main() {
+    let clock = Arc::new(ManualClock::new());
+
+    // Always active: `any` of no rules is never satisfied, so its negation always is
+    let activation = ActivationRule::Not(Box::new(ActivationRule::Any(vec![])));
+
+    let mut controller = MouseController::with_clock(
+        Sensitivity { x: 1.0, y: 1.0 },
+        activation,
+        Arc::clone(&clock) as _,
+    );
+    controller.update_steps(vec![step(300), step(1000), step(0)]);
+
+    run_ticks(&clock, 0, WARMUP_TICKS);
+
+    let allocations_before = ALLOCATIONS.load(Ordering::SeqCst);
+    let elapsed_before = clock.elapsed();
+    let started = Instant::now();
+
+    run_ticks(&clock, WARMUP_TICKS, WARMUP_TICKS + MEASURED_TICKS);
+
+    let allocations = ALLOCATIONS.load(Ordering::SeqCst) - allocations_before;
+    let ticks = ((clock.elapsed() - elapsed_before).as_nanos() / TICK.as_nanos()) as usize;
+    let duration = started.elapsed();
+
+    controller.shutdown();
+
+    println!(
+        "controller_tick: {} ticks in {:?} ({:?} per tick), {} heap allocations ({:.4} per tick)",
+        ticks,
+        duration,
+        duration / ticks.max(1) as u32,
+        allocations,
+        allocations as f64 / ticks.max(1) as f64,
+    );
+
+    assert_eq!(allocations, 0, "the controller thread allocated in steady state");
+}
@@ -0,0 +1,6 @@
+fn main() {
+    if let Err(e) = app_lib::cli::run(std::env::args().skip(1)) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
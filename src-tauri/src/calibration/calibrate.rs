@@ -0,0 +1,175 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::calibration::capture::FrameCapture;
+use crate::calibration::error::CalibrationError;
+use crate::extraction::MotionTracker;
+use crate::mouse_controller::{Clock, InputBackend};
+use crate::patterns::Sensitivity;
+
+/// Settings of a calibration run
+#[derive(Debug, Clone)]
+pub struct CalibrationSettings {
+    /// Number of moves measured along each axis
+    pub moves: u32,
+    /// Counts sent by each move
+    pub step: i32,
+    /// Time the game is given to render a move before the frame is captured
+    pub settle: Duration,
+    /// Fraction of the frame size around the crosshair that is tracked
+    pub region: f64,
+    /// Smallest phase correlation response that is trusted
+    pub min_response: f64,
+}
+
+impl Default for CalibrationSettings {
+    fn default() -> Self {
+        Self {
+            moves: 5,
+            step: 20,
+            settle: Duration::from_millis(100),
+            region: 0.5,
+            min_response: 0.05,
+        }
+    }
+}
+
+/// Displacement measured for a single calibration move
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct CalibrationSample {
+    /// Horizontal counts that were sent
+    pub dx: i32,
+    /// Vertical counts that were sent
+    pub dy: i32,
+    /// Horizontal scene displacement in pixels
+    pub shift_x: f64,
+    /// Vertical scene displacement in pixels
+    pub shift_y: f64,
+    /// Response of the phase correlation
+    pub response: f64,
+}
+
+/// Result of a calibration run
+#[derive(Debug, Clone, Serialize)]
+pub struct Calibration {
+    /// Horizontal pixels the view turns per count
+    pub pixels_per_count_x: f64,
+    /// Vertical pixels the view turns per count
+    pub pixels_per_count_y: f64,
+    /// Sensitivity that makes one unit of a step move the view by one pixel
+    pub sensitivity: Sensitivity,
+    /// Every measured move, including the ones that were not trusted
+    pub samples: Vec<CalibrationSample>,
+}
+
+/// Measures how far the view turns per mouse count
+///
+/// Each move is sent through the backend between two captured frames and the
+/// scene displacement is measured by phase correlation. The pixels per count
+/// of each axis are fitted to the trusted moves by least squares, and the view
+/// is turned back to where it started afterwards.
+///
+/// # Arguments
+/// * `backend` - Destination of the calibration moves
+/// * `camera` - Source of the frames before and after each move
+/// * `clock` - Time source used to wait for each move to be rendered
+/// * `settings` - Number and size of the moves and tracking settings
+///
+/// # Returns
+/// The fitted factors, or an error if a move or frame failed or an axis did
+/// not produce any trusted movement
+pub fn calibrate(
+    backend: &mut dyn InputBackend,
+    camera: &mut dyn FrameCapture,
+    clock: &dyn Clock,
+    settings: &CalibrationSettings,
+) -> Result<Calibration, CalibrationError> {
+    let mut samples = Vec::new();
+
+    for (dx, dy) in [(settings.step, 0), (0, settings.step)] {
+        let result = measure_axis(backend, camera, clock, settings, dx, dy, &mut samples);
+
+        // Turn back even when a measurement failed
+        let moves = settings.moves as i32;
+        backend
+            .send_move(-dx * moves, -dy * moves)
+            .map_err(|e| CalibrationError::InputFailed(e.to_string()))?;
+        clock.sleep(settings.settle);
+
+        result?;
+    }
+
+    let trusted = || {
+        samples
+            .iter()
+            .filter(|sample| sample.response >= settings.min_response)
+    };
+    let pixels_per_count_x = fit(trusted().map(|sample| (sample.dx, sample.shift_x)))
+        .ok_or(CalibrationError::NoMotion("horizontal"))?;
+    let pixels_per_count_y = fit(trusted().map(|sample| (sample.dy, sample.shift_y)))
+        .ok_or(CalibrationError::NoMotion("vertical"))?;
+
+    Ok(Calibration {
+        pixels_per_count_x,
+        pixels_per_count_y,
+        sensitivity: Sensitivity {
+            x: pixels_per_count_x as f32,
+            y: pixels_per_count_y as f32,
+        },
+        samples,
+    })
+}
+
+/// Sends the moves of one axis and records the displacement of each
+fn measure_axis(
+    backend: &mut dyn InputBackend,
+    camera: &mut dyn FrameCapture,
+    clock: &dyn Clock,
+    settings: &CalibrationSettings,
+    dx: i32,
+    dy: i32,
+    samples: &mut Vec<CalibrationSample>,
+) -> Result<(), CalibrationError> {
+    let mut tracker = MotionTracker::new(settings.region);
+    let track = |tracker: &mut MotionTracker, frame| {
+        tracker
+            .track(&frame)
+            .map_err(|e| CalibrationError::TrackingFailed(e.to_string()))
+    };
+
+    track(&mut tracker, camera.capture()?)?;
+    for _ in 0..settings.moves {
+        backend
+            .send_move(dx, dy)
+            .map_err(|e| CalibrationError::InputFailed(e.to_string()))?;
+        clock.sleep(settings.settle);
+
+        if let Some((shift_x, shift_y, response)) = track(&mut tracker, camera.capture()?)? {
+            samples.push(CalibrationSample {
+                dx,
+                dy,
+                shift_x,
+                shift_y,
+                response,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Fits the pixels per count to pairs of sent counts and measured scene displacements
+///
+/// Turning the view moves the scene the other way, so the displacement is
+/// negated. Axes without any sent counts or with a non-positive fit give `None`.
+fn fit(pairs: impl Iterator<Item = (i32, f64)>) -> Option<f64> {
+    let (mut product, mut square) = (0.0, 0.0);
+    for (counts, shift) in pairs {
+        product += -shift * counts as f64;
+        square += (counts as f64).powi(2);
+    }
+
+    let factor = product / square;
+    (square > 0.0 && factor.is_finite() && factor > 0.0).then_some(factor)
+}
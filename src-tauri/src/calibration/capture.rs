@@ -0,0 +1,142 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use opencv::core::{AlgorithmHint, Mat, Rect};
+use opencv::imgproc;
+use opencv::prelude::MatTraitConst;
+use screenshots::Screen;
+
+use crate::calibration::error::CalibrationError;
+use crate::mouse_controller::{InputBackend, MouseInputError};
+use crate::screen_reader::utils::rgba_image_to_mat;
+
+/// Source of frames captured on demand
+pub trait FrameCapture {
+    /// Captures the current frame
+    ///
+    /// # Returns
+    /// The frame in BGR order, or an error if it could not be captured
+    fn capture(&mut self) -> Result<Mat, CalibrationError>;
+}
+
+/// Captures the primary screen
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ScreenCapture;
+
+impl FrameCapture for ScreenCapture {
+    fn capture(&mut self) -> Result<Mat, CalibrationError> {
+        let screens = Screen::all().map_err(|e| CalibrationError::CaptureFailed(e.to_string()))?;
+        let screen = screens
+            .first()
+            .ok_or_else(|| CalibrationError::CaptureFailed("No screen found".to_string()))?;
+        let image = screen
+            .capture()
+            .map_err(|e| CalibrationError::CaptureFailed(e.to_string()))?;
+
+        let bgra = rgba_image_to_mat(&image)
+            .map_err(|e| CalibrationError::CaptureFailed(e.to_string()))?;
+        let mut bgr = Mat::default();
+        imgproc::cvt_color(
+            &bgra,
+            &mut bgr,
+            imgproc::COLOR_BGRA2BGR,
+            0,
+            AlgorithmHint::ALGO_HINT_DEFAULT,
+        )
+        .map_err(|e| CalibrationError::CaptureFailed(e.to_string()))?;
+
+        Ok(bgr)
+    }
+}
+
+/// View into a larger image that pans by a known amount per mouse count
+///
+/// Movement sent to the backend of the scene turns the view, so the frames
+/// show the image shifted by exactly `pixels_per_count` times the counts.
+/// Turning right moves the scene to the left, like in a game.
+pub struct SyntheticScene {
+    /// Image the view pans over
+    image: Mat,
+    /// Width of a captured frame
+    width: i32,
+    /// Height of a captured frame
+    height: i32,
+    /// Horizontal pixels the view turns per count
+    pixels_per_count_x: f64,
+    /// Vertical pixels the view turns per count
+    pixels_per_count_y: f64,
+    /// Counts received by the backend so far
+    counts: Rc<Cell<(i32, i32)>>,
+}
+
+impl SyntheticScene {
+    /// Creates a scene that starts with the view at the center of the image
+    ///
+    /// # Arguments
+    /// * `image` - Image the view pans over; must be larger than the frames
+    /// * `width` - Width of a captured frame
+    /// * `height` - Height of a captured frame
+    /// * `pixels_per_count_x` - Horizontal pixels the view turns per count
+    /// * `pixels_per_count_y` - Vertical pixels the view turns per count
+    pub fn new(
+        image: Mat,
+        width: i32,
+        height: i32,
+        pixels_per_count_x: f64,
+        pixels_per_count_y: f64,
+    ) -> Self {
+        Self {
+            image,
+            width,
+            height,
+            pixels_per_count_x,
+            pixels_per_count_y,
+            counts: Rc::new(Cell::new((0, 0))),
+        }
+    }
+
+    /// Returns a backend whose movement turns the view of this scene
+    pub fn backend(&self) -> SyntheticBackend {
+        SyntheticBackend {
+            counts: Rc::clone(&self.counts),
+        }
+    }
+}
+
+impl FrameCapture for SyntheticScene {
+    fn capture(&mut self) -> Result<Mat, CalibrationError> {
+        let (counts_x, counts_y) = self.counts.get();
+        let x = (self.image.cols() - self.width) / 2
+            + (counts_x as f64 * self.pixels_per_count_x).round() as i32;
+        let y = (self.image.rows() - self.height) / 2
+            + (counts_y as f64 * self.pixels_per_count_y).round() as i32;
+
+        if x < 0
+            || y < 0
+            || x + self.width > self.image.cols()
+            || y + self.height > self.image.rows()
+        {
+            return Err(CalibrationError::CaptureFailed(
+                "The view left the synthetic image".to_string(),
+            ));
+        }
+
+        Mat::roi(&self.image, Rect::new(x, y, self.width, self.height))
+            .and_then(|frame| frame.try_clone())
+            .map_err(|e| CalibrationError::CaptureFailed(e.to_string()))
+    }
+}
+
+/// Backend that turns the view of a `SyntheticScene`
+pub struct SyntheticBackend {
+    /// Counts received so far, shared with the scene
+    counts: Rc<Cell<(i32, i32)>>,
+}
+
+impl InputBackend for SyntheticBackend {
+    fn send_move(&mut self, dx: i32, dy: i32) -> Result<(), MouseInputError> {
+        let (x, y) = self.counts.get();
+        self.counts.set((x + dx, y + dy));
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+use std::fmt;
+
+/// Error type for sensitivity calibration operations
+#[derive(Debug)]
+pub enum CalibrationError {
+    /// A frame could not be captured
+    CaptureFailed(String),
+    /// A calibration move could not be sent
+    InputFailed(String),
+    /// The displacement between two frames could not be measured
+    TrackingFailed(String),
+    /// No trusted displacement was measured along an axis
+    NoMotion(&'static str),
+}
+
+impl fmt::Display for CalibrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CalibrationError::CaptureFailed(reason) => {
+                write!(f, "Failed to capture frame: {}", reason)
+            }
+            CalibrationError::InputFailed(reason) => {
+                write!(f, "Failed to send calibration move: {}", reason)
+            }
+            CalibrationError::TrackingFailed(reason) => {
+                write!(f, "Failed to measure displacement: {}", reason)
+            }
+            CalibrationError::NoMotion(axis) => {
+                write!(
+                    f,
+                    "No trusted movement was measured along the {} axis",
+                    axis
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for CalibrationError {}
@@ -0,0 +1,31 @@
+/// Calibration module for measuring the sensitivity of the player's setup.
+///
+/// This module sends known mouse moves, measures how far the view turns on the
+/// screen and solves for the sensitivity factors of each axis.
+///
+/// # Structure
+///
+/// The module is organized into several components:
+/// - `calibrate`: Sends the moves and fits the factors to the measurements
+/// - `capture`: Screen capture and a synthetic scene that pans by a known amount
+/// - `error`: Contains error types for calibration operations
+///
+/// # Usage
+///
+/// The main entry point is the `calibrate` function, which works with any
+/// `InputBackend` and `FrameCapture`.
+mod calibrate;
+mod capture;
+mod error;
+
+/// Public exports from the calibration module
+///
+/// These are the primary types that users of this module will interact with:
+/// - `calibrate`, `CalibrationSettings`: Measures the pixels per count of each axis
+/// - `Calibration`, `CalibrationSample`: Fitted factors and the measurements they are based on
+/// - `FrameCapture`, `ScreenCapture`: On-demand frame sources
+/// - `SyntheticScene`, `SyntheticBackend`: Scene that pans by a known amount per count
+/// - `CalibrationError`: Errors of a calibration
+pub use calibrate::{calibrate, Calibration, CalibrationSample, CalibrationSettings};
+pub use capture::{FrameCapture, ScreenCapture, SyntheticBackend, SyntheticScene};
+pub use error::CalibrationError;
@@ -0,0 +1,67 @@
+use crate::cli::Options;
+use crate::patterns::{GameSettings, PatternCollection};
+
+/// Rescales all patterns of a configuration to the target game settings
+///
+/// The converted configuration is written to `--output` or printed to stdout,
+/// and the steps that lost precision to rounding and the scripts that were not
+/// converted are reported on stderr.
+pub fn run(options: &Options) -> Result<(), String> {
+    let collection = PatternCollection::load_from_path(options.config_path())?;
+    let reference = collection.game.as_ref().map(|game| &game.reference);
+
+    let target = GameSettings {
+        dpi: match options.parse_value("dpi")? {
+            Some(dpi) => dpi,
+            None => reference
+                .map(|reference| reference.dpi)
+                .ok_or_else(|| "Missing required option --dpi".to_string())?,
+        },
+        sensitivity: options.require("sensitivity")?,
+        ads_multiplier: options.parse_value("ads")?.unwrap_or(1.0),
+        fov: options.parse_value("fov")?,
+    };
+
+    let (converted, reports) = collection.convert(&target)?;
+
+    for (name, report) in &reports {
+        eprintln!("{}: factor {:.4}", name, report.factor);
+        for loss in &report.steps {
+            eprintln!(
+                "  step {}: ({:.2}, {:.2}) rounded to ({}, {})",
+                loss.index, loss.exact_dx, loss.exact_dy, loss.dx, loss.dy
+            );
+        }
+        for loss in &report.offsets {
+            eprintln!(
+                "  bullet {}: ({:.2}, {:.2}) rounded to ({}, {})",
+                loss.index, loss.exact_dx, loss.exact_dy, loss.dx, loss.dy
+            );
+        }
+        for loss in &report.shots {
+            eprintln!(
+                "  shot {}: ({:.2}, {:.2}) rounded to ({}, {})",
+                loss.index, loss.exact_dx, loss.exact_dy, loss.dx, loss.dy
+            );
+        }
+        for (variant, losses) in &report.variants {
+            for loss in losses {
+                eprintln!(
+                    "  {} step {}: ({:.2}, {:.2}) rounded to ({}, {})",
+                    variant, loss.index, loss.exact_dx, loss.exact_dy, loss.dx, loss.dy
+                );
+            }
+        }
+        if report.unconverted_script {
+            eprintln!("  script: not converted, its movement must be rescaled by hand");
+        }
+    }
+
+    match options.get("output") {
+        Some(path) => converted.save_to_path(path),
+        None => {
+            println!("{}", converted.to_json()?);
+            Ok(())
+        }
+    }
+}
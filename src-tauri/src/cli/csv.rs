@@ -0,0 +1,59 @@
+use std::fs;
+
+use crate::cli::Options;
+use crate::patterns::{parse_csv, PatternCollection};
+
+/// Writes the steps and images of all patterns as CSV
+///
+/// The CSV is written to `--output` or printed to stdout.
+pub fn export(options: &Options) -> Result<(), String> {
+    let collection = PatternCollection::load_from_path(options.config_path())?;
+    let csv = collection.to_csv();
+
+    match options.get("output") {
+        Some(path) => fs::write(path, csv).map_err(|e| format!("Failed to write file: {}", e)),
+        None => {
+            print!("{}", csv);
+            Ok(())
+        }
+    }
+}
+
+/// Merges the patterns of a CSV file into a configuration
+///
+/// Patterns that already exist with different steps or images are reported on
+/// stderr and only replaced with `--overwrite true`. The merged configuration
+/// is written to `--output` or printed to stdout.
+pub fn import(options: &Options) -> Result<(), String> {
+    let mut collection = PatternCollection::load_from_path(options.config_path())?;
+    let input: String = options.require("input")?;
+    let overwrite = options.parse_value("overwrite")?.unwrap_or(false);
+
+    let csv = fs::read_to_string(&input).map_err(|e| format!("Failed to read file: {}", e))?;
+    let imported = parse_csv(&csv).map_err(|e| e.to_string())?;
+    let report = collection.merge_csv(imported, overwrite);
+
+    for name in &report.added {
+        eprintln!("{}: added", name);
+    }
+    for name in &report.skipped {
+        eprintln!("{}: no steps, skipped", name);
+    }
+    for conflict in &report.conflicts {
+        eprintln!(
+            "{}: {} differ, {}",
+            conflict.pattern,
+            conflict.fields.join(" and "),
+            if overwrite { "replaced" } else { "kept" }
+        );
+    }
+    eprintln!("{} unchanged", report.unchanged.len());
+
+    match options.get("output") {
+        Some(path) => collection.save_to_path(path),
+        None => {
+            println!("{}", collection.to_json()?);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+use crate::cli::Options;
+use crate::extraction::{extract, open_source, ExtractionSettings};
+use crate::mouse_controller::DEFAULT_THREAD_SLEEP_DURATION_MS;
+use crate::patterns::PatternCollection;
+use crate::recorder::DEFAULT_BUCKET_MS;
+
+/// Measures the recoil in a video or frame directory and saves it as a pattern
+///
+/// The configuration with the new or updated pattern is written to `--output`
+/// or printed to stdout, and a summary of the tracking is reported on stderr.
+pub fn run(options: &Options) -> Result<(), String> {
+    let mut collection = PatternCollection::load_from_path(options.config_path())?;
+    let input: String = options.require("input")?;
+    let name: String = options.require("pattern")?;
+    let defaults = ExtractionSettings::default();
+    let settings = ExtractionSettings {
+        region: options.parse_value("region")?.unwrap_or(defaults.region),
+        counts_per_pixel: options
+            .parse_value("counts-per-pixel")?
+            .unwrap_or(defaults.counts_per_pixel),
+        min_response: options
+            .parse_value("min-response")?
+            .unwrap_or(defaults.min_response),
+        max_lost_fraction: options
+            .parse_value("max-lost")?
+            .unwrap_or(defaults.max_lost_fraction),
+    };
+    let tick = Duration::from_millis(
+        options
+            .parse_value("tick")?
+            .unwrap_or(DEFAULT_THREAD_SLEEP_DURATION_MS),
+    );
+    let bucket = Duration::from_millis(options.parse_value("bucket")?.unwrap_or(DEFAULT_BUCKET_MS));
+
+    let mut source = open_source(&input, options.parse_value("fps")?).map_err(|e| e.to_string())?;
+    let extraction = extract(source.as_mut(), &settings).map_err(|e| e.to_string())?;
+    let steps = extraction.to_steps(tick, bucket, &collection.sensitivity);
+
+    eprintln!(
+        "{}: {} frames over {} ms, {} interpolated, {} steps",
+        name,
+        extraction.frames,
+        extraction.duration().as_millis(),
+        extraction.lost_frames,
+        steps.len()
+    );
+
+    let baseline = collection.game.as_ref().map(|game| game.current.clone());
+    collection.set_steps(&name, steps, baseline);
+
+    match options.get("output") {
+        Some(path) => collection.save_to_path(path),
+        None => {
+            println!("{}", collection.to_json()?);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! Command line tools for working with pattern configurations
+//!
+//! Available commands:
+//! - `convert`: rescales all patterns to different game settings
+//! - `simulate`: prints every movement the controller would send for a pattern
+//! - `preview`: renders the simulated path of patterns as an SVG or PNG image
+//! - `stats`: prints the metrics of every pattern
+//! - `simplify`: reduces the steps of every pattern within a pixel tolerance
+//! - `extract`: measures the recoil in recorded gameplay and saves it as a pattern
+//! - `steps`: converts step lists between the step notation and JSON
+//! - `export`, `import`: exchange patterns with spreadsheets as CSV
+
+mod convert;
+mod csv;
+mod extract;
+mod preview;
+mod simplify;
+mod simulate;
+mod stats;
+mod steps;
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Default path of the configuration file
+const DEFAULT_CONFIG: &str = "config/config.json";
+
+/// Usage printed when the command line is invalid
+const USAGE: &str = "\
+Usage: recoil-cli <command> [options]
+
+Commands:
+  convert   Rescale all patterns to different game settings
+            --sensitivity <value>  Target in-game sensitivity (required)
+            --dpi <value>          Target mouse DPI (default: reference DPI)
+            --ads <value>          Target ADS multiplier (default: 1.0)
+            --fov <value>          Target field of view in degrees
+            --config <path>        Configuration to convert (default: config/config.json)
+            --output <path>        File to write the converted configuration to (default: stdout)
+  simulate  Print every movement the controller would send for a pattern
+            --pattern <name>       Pattern to simulate (required)
+            --hold <ms>            How long the fire buttons are held (required)
+            --tick <ms>            Time between two iterations (default: 24)
+            --format <text|json>   Output format (default: text)
+            --config <path>        Configuration to read (default: config/config.json)
+  preview   Render the simulated path of patterns as an SVG or PNG image
+            --pattern <a,b>        Patterns to draw on top of each other (required)
+            --sensitivity <a,b>    Sensitivities to compare (default: configured one)
+            --hold <ms>            How long the fire buttons are held (required)
+            --output <path>        Image file to write, .svg or .png (required)
+            --format <svg|png>     Image format (default: from the output extension)
+            --width <px>           Width of the image (default: 640, at most 4096)
+            --height <px>          Height of the image (default: 640, at most 4096)
+            --tick <ms>            Time between two iterations (default: 24)
+            --config <path>        Configuration to read (default: config/config.json)
+  stats     Print the metrics of every pattern
+            --pattern <name>       Only print the metrics of this pattern
+            --format <text|json>   Output format (default: text)
+            --tick <ms>            Time between two iterations (default: 24)
+            --config <path>        Configuration to read (default: config/config.json)
+  simplify  Reduce the steps of every pattern within a pixel tolerance
+            --tolerance <px>       Largest allowed distance from the original path (required)
+            --tick <ms>            Time between two iterations (default: 24)
+            --config <path>        Configuration to simplify (default: config/config.json)
+            --output <path>        File to write the simplified configuration to (default: stdout)
+  extract   Measure the recoil in a video or frame directory and save it as a pattern
+            --input <path>         Video file or directory of frame images (required)
+            --pattern <name>       Pattern to create or update (required)
+            --fps <value>          Frame rate (required for a directory, default: from the video)
+            --counts-per-pixel <v> Mouse counts that turn the view by one video pixel (default: 1.0)
+            --region <fraction>    Part of the frame around the crosshair to track (default: 0.5)
+            --min-response <v>     Weakest phase correlation that is trusted (default: 0.05)
+            --max-lost <fraction>  Largest part of the frames that may be untrusted (default: 0.25)
+            --bucket <ms>          Length of the time slices turned into steps (default: 100)
+            --tick <ms>            Time between two iterations (default: 24)
+            --config <path>        Configuration to update (default: config/config.json)
+            --output <path>        File to write the updated configuration to (default: stdout)
+  steps     Convert step lists between the step notation and JSON
+            --parse <text>         Steps such as \"1000ms: -5,122; 700ms x3: 0,200\" to print as JSON
+            --pattern <name>       Pattern whose steps are printed in the notation
+            --variant <name>       Print the steps of this variant of the pattern instead
+            --config <path>        Configuration to read (default: config/config.json)
+  export    Write the steps and images of all patterns as CSV
+            --config <path>        Configuration to read (default: config/config.json)
+            --output <path>        CSV file to write (default: stdout)
+  import    Merge the patterns of a CSV file into a configuration
+            --input <path>         CSV file with pattern, step, duration, dx, dy and images columns (required)
+            --overwrite <bool>     Replace patterns whose steps or images differ (default: false)
+            --config <path>        Configuration to merge into (default: config/config.json)
+            --output <path>        File to write the merged configuration to (default: stdout)";
+
+/// Parsed command line options in the form `--name value`
+pub struct Options {
+    /// Option values by name, without the leading dashes
+    values: HashMap<String, String>,
+}
+
+impl Options {
+    /// Parses options from the remaining command line arguments
+    ///
+    /// # Arguments
+    /// * `args` - Arguments following the command name
+    ///
+    /// # Returns
+    /// The parsed options, or an error if an argument is not a valid option
+    pub fn parse(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut values = HashMap::new();
+        let mut args = args;
+
+        while let Some(arg) = args.next() {
+            let name = arg
+                .strip_prefix("--")
+                .ok_or_else(|| format!("Unexpected argument: {}", arg))?;
+            let value = args
+                .next()
+                .ok_or_else(|| format!("Missing value for option --{}", name))?;
+            values.insert(name.to_string(), value);
+        }
+
+        Ok(Self { values })
+    }
+
+    /// Returns the raw value of an option
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    /// Returns the parsed value of an option, or `None` when it was not given
+    pub fn parse_value<T: FromStr>(&self, name: &str) -> Result<Option<T>, String> {
+        self.get(name)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| format!("Invalid value for option --{}: {}", name, value))
+            })
+            .transpose()
+    }
+
+    /// Returns the parsed value of an option that must be given
+    pub fn require<T: FromStr>(&self, name: &str) -> Result<T, String> {
+        self.parse_value(name)?
+            .ok_or_else(|| format!("Missing required option --{}", name))
+    }
+
+    /// Returns the configuration path, falling back to the default one
+    pub fn config_path(&self) -> &str {
+        self.get("config").unwrap_or(DEFAULT_CONFIG)
+    }
+}
+
+/// Runs the command line tool
+///
+/// # Arguments
+/// * `args` - Command line arguments without the program name
+///
+/// # Returns
+/// `Ok(())` on success, or an error message to print
+pub fn run(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let command = args.next().ok_or_else(|| USAGE.to_string())?;
+    let options = Options::parse(args)?;
+
+    match command.as_str() {
+        "convert" => convert::run(&options),
+        "simulate" => simulate::run(&options),
+        "preview" => preview::run(&options),
+        "stats" => stats::run(&options),
+        "simplify" => simplify::run(&options),
+        "extract" => extract::run(&options),
+        "steps" => steps::run(&options),
+        "export" => csv::export(&options),
+        "import" => csv::import(&options),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(format!("Unknown command: {}\n\n{}", command, USAGE)),
+    }
+}
@@ -0,0 +1,65 @@
+use std::fs;
+use std::time::Duration;
+
+use crate::cli::Options;
+use crate::mouse_controller::{simulate, DEFAULT_THREAD_SLEEP_DURATION_MS};
+use crate::patterns::{PatternCollection, Sensitivity};
+use crate::preview::{collect_series, render, PreviewFormat, PreviewOptions};
+
+/// Renders the simulated path of one or more patterns to an image file
+///
+/// Patterns and sensitivities are comma-separated lists; every combination is
+/// drawn on top of each other. The format is taken from the output extension.
+pub fn run(options: &Options) -> Result<(), String> {
+    let collection = PatternCollection::load_from_path(options.config_path())?;
+    let names: Vec<String> = list(options.get("pattern").unwrap_or_default());
+    if names.is_empty() {
+        return Err("Missing required option --pattern".to_string());
+    }
+
+    let sensitivities = list(options.get("sensitivity").unwrap_or_default())
+        .iter()
+        .map(|value| {
+            value
+                .parse()
+                .map(|value| Sensitivity { x: value, y: value })
+                .map_err(|_| format!("Invalid value for option --sensitivity: {}", value))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let output: String = options.require("output")?;
+    let format = match options.get("format") {
+        Some(format) => format.parse()?,
+        None => PreviewFormat::from_path(&output)
+            .ok_or_else(|| format!("Cannot tell the format of {}, use --format", output))?,
+    };
+
+    let tick = Duration::from_millis(
+        options
+            .parse_value("tick")?
+            .unwrap_or(DEFAULT_THREAD_SLEEP_DURATION_MS),
+    );
+    let hold = Duration::from_millis(options.require("hold")?);
+    let preview_options = PreviewOptions {
+        width: options.parse_value("width")?.unwrap_or(640),
+        height: options.parse_value("height")?.unwrap_or(640),
+        ..PreviewOptions::default()
+    };
+
+    let series = collect_series(&collection.patterns, &names, &sensitivities, |pattern| {
+        simulate(pattern, &collection, tick, hold)
+    })?;
+    let image = render(&series, &preview_options, format)?;
+
+    fs::write(&output, image).map_err(|e| format!("Failed to write file: {}", e))
+}
+
+/// Splits a comma-separated option value
+fn list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
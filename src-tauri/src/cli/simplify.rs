@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use crate::cli::Options;
+use crate::mouse_controller::DEFAULT_THREAD_SLEEP_DURATION_MS;
+use crate::patterns::PatternCollection;
+
+/// Simplifies the steps of all patterns of a configuration
+///
+/// The simplified configuration is written to `--output` or printed to stdout,
+/// and the error introduced for every pattern is reported on stderr.
+pub fn run(options: &Options) -> Result<(), String> {
+    let collection = PatternCollection::load_from_path(options.config_path())?;
+    let tolerance: f32 = options.require("tolerance")?;
+    let tick = Duration::from_millis(
+        options
+            .parse_value("tick")?
+            .unwrap_or(DEFAULT_THREAD_SLEEP_DURATION_MS),
+    );
+
+    let (simplified, reports) = collection.simplify(tick, tolerance);
+
+    for (name, report) in &reports {
+        eprintln!(
+            "{}: {} -> {} steps, max error {:.2} px, end error {:.2} px",
+            name, report.original_steps, report.steps, report.max_error, report.end_error
+        );
+    }
+
+    match options.get("output") {
+        Some(path) => simplified.save_to_path(path),
+        None => {
+            println!("{}", simplified.to_json()?);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use std::time::Duration;
+
+use crate::cli::Options;
+use crate::mouse_controller::{simulate, DEFAULT_THREAD_SLEEP_DURATION_MS};
+use crate::patterns::PatternCollection;
+
+/// Prints every movement the controller would send for a pattern
+///
+/// The movements are printed as a table, or as JSON with `--format json`.
+pub fn run(options: &Options) -> Result<(), String> {
+    let collection = PatternCollection::load_from_path(options.config_path())?;
+    let name: String = options.require("pattern")?;
+    let pattern = collection
+        .patterns
+        .get(&name)
+        .ok_or_else(|| format!("Pattern not found: {}", name))?;
+
+    let tick = options
+        .parse_value("tick")?
+        .unwrap_or(DEFAULT_THREAD_SLEEP_DURATION_MS);
+    let hold = options.require("hold")?;
+
+    let trajectory = simulate(
+        pattern,
+        &collection,
+        Duration::from_millis(tick),
+        Duration::from_millis(hold),
+    );
+
+    match options.get("format").unwrap_or("text") {
+        "json" => {
+            let json = serde_json::to_string_pretty(&trajectory)
+                .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+            println!("{}", json);
+        }
+        "text" => {
+            println!("{:>8} {:>6} {:>6} {:>6} {:>6}", "time_ms", "dx", "dy", "x", "y");
+            for step in &trajectory.moves {
+                println!(
+                    "{:>8} {:>6} {:>6} {:>6} {:>6}",
+                    step.time_ms, step.dx, step.dy, step.x, step.y
+                );
+            }
+        }
+        format => return Err(format!("Unknown format: {}", format)),
+    }
+
+    Ok(())
+}
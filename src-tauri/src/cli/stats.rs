@@ -0,0 +1,62 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::cli::Options;
+use crate::mouse_controller::{analyze, DEFAULT_THREAD_SLEEP_DURATION_MS};
+use crate::patterns::PatternCollection;
+
+/// Prints the metrics of every pattern, or of a single one with `--pattern`
+///
+/// The metrics are printed as a table sorted by name, or as JSON with `--format json`.
+pub fn run(options: &Options) -> Result<(), String> {
+    let collection = PatternCollection::load_from_path(options.config_path())?;
+    let tick = Duration::from_millis(
+        options
+            .parse_value("tick")?
+            .unwrap_or(DEFAULT_THREAD_SLEEP_DURATION_MS),
+    );
+
+    let filter = options.get("pattern");
+    let mut stats = BTreeMap::new();
+    for (name, pattern) in &collection.patterns {
+        if filter.is_none() || filter == Some(name.as_str()) {
+            stats.insert(name, analyze(pattern, &collection, tick));
+        }
+    }
+
+    if let (Some(name), true) = (filter, stats.is_empty()) {
+        return Err(format!("Pattern not found: {}", name));
+    }
+
+    match options.get("format").unwrap_or("text") {
+        "json" => {
+            let json = serde_json::to_string_pretty(&stats)
+                .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+            println!("{}", json);
+        }
+        "text" => {
+            println!(
+                "{:<16} {:>5} {:>8} {:>12} {:>12} {:>10} {:>9}",
+                "pattern", "steps", "ms", "raw dx/dy", "dx/dy", "peak", "collapsed"
+            );
+            for (name, stats) in &stats {
+                let duration = stats
+                    .duration_ms
+                    .map_or_else(|| "-".to_string(), |ms| ms.to_string());
+                println!(
+                    "{:<16} {:>5} {:>8} {:>12} {:>12} {:>10} {:>9}",
+                    name,
+                    stats.steps,
+                    duration,
+                    format!("{}/{}", stats.raw_dx, stats.raw_dy),
+                    format!("{}/{}", stats.dx, stats.dy),
+                    format!("{}/{}", stats.peak_dx, stats.peak_dy),
+                    stats.collapsed_steps
+                );
+            }
+        }
+        format => return Err(format!("Unknown format: {}", format)),
+    }
+
+    Ok(())
+}
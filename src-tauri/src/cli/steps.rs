@@ -0,0 +1,37 @@
+use crate::cli::Options;
+use crate::patterns::{format_steps, parse_steps, PatternCollection};
+
+/// Converts step lists between the step notation and JSON
+///
+/// With `--parse`, the given notation is printed as the canonical JSON steps.
+/// With `--pattern`, the steps of the pattern, or of one of its variants with
+/// `--variant`, are printed in the step notation.
+pub fn run(options: &Options) -> Result<(), String> {
+    if let Some(text) = options.get("parse") {
+        let steps = parse_steps(text).map_err(|e| e.to_string())?;
+        let json = serde_json::to_string_pretty(&steps)
+            .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    let collection = PatternCollection::load_from_path(options.config_path())?;
+    let name = options
+        .get("pattern")
+        .ok_or_else(|| "Missing required option --parse or --pattern".to_string())?;
+    let pattern = collection
+        .patterns
+        .get(name)
+        .ok_or_else(|| format!("Pattern not found: {}", name))?;
+
+    let steps = match options.get("variant") {
+        Some(variant) => pattern
+            .variants
+            .get(variant)
+            .ok_or_else(|| format!("Variant not found: {}", variant))?,
+        None => &pattern.config,
+    };
+
+    println!("{}", format_steps(steps));
+    Ok(())
+}
@@ -0,0 +1,493 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use serde::Serialize;
+
+use tauri::ipc::Response;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::calibration::{calibrate, Calibration, CalibrationSettings, ScreenCapture};
+use crate::keyboard_listener::KeyboardListener;
+use crate::mouse_controller::{
+    ControllerStatus, MouseController, PatternStats, SendInputBackend, SuspendedStatus, SystemClock,
+    Trajectory, DEFAULT_THREAD_SLEEP_DURATION_MS,
+};
+use crate::patterns::{
+    parse_csv, ConversionReport, GameSensitivity, GameSettings, MergeReport, PatternCollection,
+    Sensitivity, SimplifyReport, Steps, TransformSettings,
+};
+use crate::preview::{collect_series, render, PreviewFormat, PreviewOptions};
+use crate::recorder::{record, RawInputSource, RecorderSettings, DEFAULT_BUCKET_MS};
+
+/// Configuration file that recorded patterns are saved to
+const CONFIG_PATH: &str = "config/config.json";
+
+/// Services shared between Tauri commands and keyboard callbacks
+pub struct AppState {
+    /// Loaded patterns and settings, replaced in place when the configuration is reloaded
+    ///
+    /// Always locked before `mouse_controller` when both are needed.
+    pub pattern_collection: Arc<RwLock<PatternCollection>>,
+    /// Controller that applies the active pattern
+    pub mouse_controller: Arc<Mutex<MouseController>>,
+    /// Listener that runs the hotkey callbacks
+    pub keyboard_listener: Mutex<KeyboardListener>,
+}
+
+impl AppState {
+    /// Stops the keyboard listener and the mouse controller, joining both threads
+    ///
+    /// The keyboard listener is stopped first, so no callback can touch the
+    /// controller while it is shutting down.
+    pub fn shutdown(&self) {
+        if let Ok(mut keyboard_listener) = self.keyboard_listener.lock() {
+            keyboard_listener.stop();
+        }
+
+        if let Ok(mut controller) = self.mouse_controller.lock() {
+            controller.shutdown();
+        }
+    }
+}
+
+/// Patterns converted to other game settings
+#[derive(Serialize)]
+pub struct ConversionResult {
+    /// Configuration with all patterns rescaled to the target settings
+    pub config: PatternCollection,
+    /// Conversion report of every pattern by name
+    pub reports: BTreeMap<String, ConversionReport>,
+}
+
+/// Patterns reduced to fewer steps
+#[derive(Serialize)]
+pub struct SimplificationResult {
+    /// Configuration with the steps of all patterns simplified
+    pub config: PatternCollection,
+    /// Simplification report of every pattern with steps by name
+    pub reports: BTreeMap<String, SimplifyReport>,
+}
+
+/// Patterns merged from a CSV file
+#[derive(Serialize)]
+pub struct ImportResult {
+    /// Configuration with the imported patterns merged in
+    pub config: PatternCollection,
+    /// Which patterns were added, unchanged or in conflict
+    pub report: MergeReport,
+}
+
+/// Emits the controller status to the frontend
+pub fn emit_controller_status(app_handle: &AppHandle, status: ControllerStatus) {
+    let _ = app_handle.emit("controller-state", status);
+}
+
+/// Emits the selected scope to the frontend, or an empty string when none is selected
+pub fn emit_scope(app_handle: &AppHandle, scope: Option<String>) {
+    let _ = app_handle.emit("scope-selected", scope.unwrap_or_default());
+}
+
+/// Returns whether the controller is enabled and armed
+#[tauri::command]
+pub fn get_controller_status(state: State<'_, AppState>) -> Result<ControllerStatus, String> {
+    let controller = state.mouse_controller.lock().map_err(|e| e.to_string())?;
+
+    Ok(controller.status())
+}
+
+/// Toggles the controller on or off, re-arming it after the panic key was used
+#[tauri::command]
+pub fn toggle_controller(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<ControllerStatus, String> {
+    let mut controller = state.mouse_controller.lock().map_err(|e| e.to_string())?;
+    let status = controller.toggle_enabled();
+    emit_controller_status(&app_handle, status);
+
+    Ok(status)
+}
+
+/// Returns the transforms applied to every movement after sensitivity
+#[tauri::command]
+pub fn get_transform(state: State<'_, AppState>) -> Result<TransformSettings, String> {
+    let controller = state.mouse_controller.lock().map_err(|e| e.to_string())?;
+
+    Ok(controller.transform())
+}
+
+/// Replaces the transforms applied to every movement after sensitivity
+#[tauri::command]
+pub fn set_transform(
+    state: State<'_, AppState>,
+    transform: TransformSettings,
+) -> Result<TransformSettings, String> {
+    let mut controller = state.mouse_controller.lock().map_err(|e| e.to_string())?;
+    controller.update_transform(transform);
+
+    Ok(controller.transform())
+}
+
+/// Sets the global strength of the compensation in percent
+#[tauri::command]
+pub fn set_strength(state: State<'_, AppState>, strength: f32) -> Result<TransformSettings, String> {
+    if !strength.is_finite() || strength < 0.0 {
+        return Err(format!("Invalid strength: {}", strength));
+    }
+
+    let mut controller = state.mouse_controller.lock().map_err(|e| e.to_string())?;
+    let mut transform = controller.transform();
+    transform.strength = strength;
+    controller.update_transform(transform);
+
+    Ok(controller.transform())
+}
+
+/// Selects the scope whose multiplier is applied to the sensitivity
+#[tauri::command]
+pub fn set_scope(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    scope: Option<String>,
+) -> Result<Option<String>, String> {
+    let mut controller = state.mouse_controller.lock().map_err(|e| e.to_string())?;
+    controller.set_scope(scope);
+    let scope = controller.scope();
+    emit_scope(&app_handle, scope.clone());
+
+    Ok(scope)
+}
+
+/// Selects the next scope of the current pattern
+#[tauri::command]
+pub fn cycle_scope(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, String> {
+    let mut controller = state.mouse_controller.lock().map_err(|e| e.to_string())?;
+    let scope = controller.cycle_scope();
+    emit_scope(&app_handle, scope.clone());
+
+    Ok(scope)
+}
+
+/// Returns the reference and current game settings used to convert patterns
+#[tauri::command]
+pub fn get_game_sensitivity(state: State<'_, AppState>) -> Result<Option<GameSensitivity>, String> {
+    let controller = state.mouse_controller.lock().map_err(|e| e.to_string())?;
+
+    Ok(controller.game_sensitivity())
+}
+
+/// Sets the player's current game settings, keeping the reference the patterns were tuned at
+#[tauri::command]
+pub fn set_game_settings(
+    state: State<'_, AppState>,
+    settings: GameSettings,
+) -> Result<Option<GameSensitivity>, String> {
+    let valid = settings.dpi > 0.0
+        && settings.sensitivity > 0.0
+        && settings.ads_multiplier > 0.0
+        && settings.fov.map_or(true, |fov| fov > 0.0 && fov < 180.0);
+    if !valid {
+        return Err(format!("Invalid game settings: {:?}", settings));
+    }
+
+    let mut controller = state.mouse_controller.lock().map_err(|e| e.to_string())?;
+    let game = controller
+        .game_sensitivity()
+        .ok_or_else(|| "No reference game settings are configured".to_string())?;
+    controller.update_game_sensitivity(Some(GameSensitivity {
+        current: settings,
+        ..game
+    }));
+
+    Ok(controller.game_sensitivity())
+}
+
+/// Rescales all loaded patterns to the target game settings
+///
+/// The converted configuration is saved to `output` when given; the loaded
+/// patterns are left unchanged either way.
+#[tauri::command]
+pub fn convert_patterns(
+    state: State<'_, AppState>,
+    target: GameSettings,
+    output: Option<String>,
+) -> Result<ConversionResult, String> {
+    let collection = state.pattern_collection.read().map_err(|e| e.to_string())?;
+    let (config, reports) = collection.convert(&target)?;
+
+    if let Some(path) = output {
+        config.save_to_path(&path)?;
+    }
+
+    Ok(ConversionResult { config, reports })
+}
+
+/// Reduces the steps of all loaded patterns within a pixel tolerance
+///
+/// The simplified configuration is saved to `output` when given; the loaded
+/// patterns are left unchanged either way.
+#[tauri::command]
+pub fn simplify_patterns(
+    state: State<'_, AppState>,
+    tolerance: f32,
+    tick_ms: Option<u64>,
+    output: Option<String>,
+) -> Result<SimplificationResult, String> {
+    let tick = Duration::from_millis(tick_ms.unwrap_or(DEFAULT_THREAD_SLEEP_DURATION_MS));
+    let collection = state.pattern_collection.read().map_err(|e| e.to_string())?;
+    let (config, reports) = collection.simplify(tick, tolerance);
+
+    if let Some(path) = output {
+        config.save_to_path(&path)?;
+    }
+
+    Ok(SimplificationResult { config, reports })
+}
+
+/// Returns the steps and images of all loaded patterns as CSV
+///
+/// The CSV is also saved to `output` when given.
+#[tauri::command]
+pub fn export_patterns_csv(
+    state: State<'_, AppState>,
+    output: Option<String>,
+) -> Result<String, String> {
+    let csv = state
+        .pattern_collection
+        .read()
+        .map_err(|e| e.to_string())?
+        .to_csv();
+
+    if let Some(path) = output {
+        std::fs::write(&path, &csv).map_err(|e| format!("Failed to write file: {}", e))?;
+    }
+
+    Ok(csv)
+}
+
+/// Merges patterns from CSV into the loaded patterns
+///
+/// Patterns whose steps or images differ are reported as conflicts and only
+/// replaced when `overwrite` is set. The merged configuration is saved to
+/// `output` when given; the loaded patterns are left unchanged either way.
+#[tauri::command]
+pub fn import_patterns_csv(
+    state: State<'_, AppState>,
+    csv: String,
+    overwrite: bool,
+    output: Option<String>,
+) -> Result<ImportResult, String> {
+    let imported = parse_csv(&csv).map_err(|e| e.to_string())?;
+    let mut config = state
+        .pattern_collection
+        .read()
+        .map_err(|e| e.to_string())?
+        .clone();
+    let report = config.merge_csv(imported, overwrite);
+
+    if let Some(path) = output {
+        config.save_to_path(&path)?;
+    }
+
+    Ok(ImportResult { config, report })
+}
+
+/// Returns every movement the controller would send for a pattern with the current settings
+///
+/// Holds longer than `MAX_SIMULATED_HOLD` are simulated for that long.
+#[tauri::command]
+pub fn simulate_pattern(
+    state: State<'_, AppState>,
+    name: String,
+    hold_ms: u64,
+    tick_ms: Option<u64>,
+) -> Result<Trajectory, String> {
+    let pattern = state
+        .pattern_collection
+        .read()
+        .map_err(|e| e.to_string())?
+        .get_patterns()
+        .get(&name)
+        .cloned()
+        .ok_or_else(|| format!("Pattern not found: {}", name))?;
+    let tick = Duration::from_millis(tick_ms.unwrap_or(DEFAULT_THREAD_SLEEP_DURATION_MS));
+
+    let controller = state.mouse_controller.lock().map_err(|e| e.to_string())?;
+
+    Ok(controller.simulate(pattern, tick, Duration::from_millis(hold_ms)))
+}
+
+/// Renders the simulated path of patterns as an SVG or PNG image with the current settings
+///
+/// Every combination of the patterns and sensitivities is drawn on top of
+/// each other; without sensitivities, the configured one is used. Holds longer
+/// than `MAX_SIMULATED_HOLD` are simulated for that long.
+#[tauri::command]
+pub fn render_pattern_preview(
+    state: State<'_, AppState>,
+    patterns: Vec<String>,
+    sensitivities: Option<Vec<Sensitivity>>,
+    hold_ms: u64,
+    tick_ms: Option<u64>,
+    format: String,
+) -> Result<Response, String> {
+    let format: PreviewFormat = format.parse()?;
+    let tick = Duration::from_millis(tick_ms.unwrap_or(DEFAULT_THREAD_SLEEP_DURATION_MS));
+    let hold = Duration::from_millis(hold_ms);
+
+    let collection = state.pattern_collection.read().map_err(|e| e.to_string())?;
+    let controller = state.mouse_controller.lock().map_err(|e| e.to_string())?;
+    let series = collect_series(
+        collection.get_patterns(),
+        &patterns,
+        &sensitivities.unwrap_or_default(),
+        |pattern| controller.simulate(pattern.clone(), tick, hold),
+    )?;
+    let image = render(&series, &PreviewOptions::default(), format)?;
+
+    Ok(Response::new(image))
+}
+
+/// Returns the metrics of every loaded pattern with the current settings, sorted by name
+#[tauri::command]
+pub fn get_pattern_stats(
+    state: State<'_, AppState>,
+    tick_ms: Option<u64>,
+) -> Result<BTreeMap<String, PatternStats>, String> {
+    let tick = Duration::from_millis(tick_ms.unwrap_or(DEFAULT_THREAD_SLEEP_DURATION_MS));
+    let collection = state.pattern_collection.read().map_err(|e| e.to_string())?;
+    let controller = state.mouse_controller.lock().map_err(|e| e.to_string())?;
+
+    let mut stats = BTreeMap::new();
+    for (name, pattern) in collection.get_patterns() {
+        let pattern_stats = controller
+            .analyze(pattern.clone(), tick)
+            .ok_or_else(|| "Failed to read the controller state".to_string())?;
+        stats.insert(name.clone(), pattern_stats);
+    }
+
+    Ok(stats)
+}
+
+/// Records a pattern from live mouse movement and saves it to the configuration file
+///
+/// Recording starts when the activation rule is satisfied and ends when it is
+/// released. The controller is suspended in the meantime so its own movement is
+/// not captured. The saved pattern is applied once the configuration is reloaded.
+#[tauri::command]
+pub async fn record_pattern(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    name: String,
+    timeout_ms: Option<u64>,
+    bucket_ms: Option<u64>,
+) -> Result<Steps, String> {
+    let (settings, sensitivity) = {
+        let collection = state.pattern_collection.read().map_err(|e| e.to_string())?;
+        let existing = collection.get_patterns().get(&name);
+        let defaults = RecorderSettings::default();
+        let settings = RecorderSettings {
+            activation: existing
+                .and_then(|pattern| pattern.activation.clone())
+                .unwrap_or_else(|| collection.activation.clone()),
+            start_timeout: timeout_ms.map_or(defaults.start_timeout, Duration::from_millis),
+            ..defaults
+        };
+        let sensitivity = existing
+            .and_then(|pattern| pattern.sensitivity.clone())
+            .unwrap_or_else(|| collection.sensitivity.clone());
+        (settings, sensitivity)
+    };
+    let bucket = Duration::from_millis(bucket_ms.unwrap_or(DEFAULT_BUCKET_MS));
+
+    // Keep the controller from moving the mouse while recording
+    let (suspended, game) = {
+        let mut controller = state.mouse_controller.lock().map_err(|e| e.to_string())?;
+        let suspended = controller.suspend();
+        emit_controller_status(&app_handle, controller.status());
+        (suspended, controller.game_sensitivity())
+    };
+
+    let recording = tauri::async_runtime::spawn_blocking(move || {
+        let mut source = RawInputSource::start()?;
+        record(&mut source, &SystemClock, &settings)
+    })
+    .await;
+
+    resume_controller(&app_handle, &state, suspended)?;
+
+    let recording = recording
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+    let steps = recording.to_steps(bucket, &sensitivity);
+
+    // Update the file rather than the loaded patterns, like the F1 reload expects
+    let mut config = PatternCollection::load_from_path(CONFIG_PATH)?;
+    config.set_steps(&name, steps.clone(), game.map(|game| game.current));
+    config.save_to_path(CONFIG_PATH)?;
+
+    Ok(steps)
+}
+
+/// Measures the sensitivity of the current setup by moving the view and tracking the screen
+///
+/// A few moves are sent along each axis while the game is in focus, and the
+/// resulting view displacement is measured on captured frames. The controller
+/// is suspended in the meantime so its own movement does not disturb the
+/// measurement. The returned sensitivity makes one unit of a step turn the
+/// view by one pixel.
+#[tauri::command]
+pub async fn calibrate_sensitivity(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    moves: Option<u32>,
+    step: Option<i32>,
+    settle_ms: Option<u64>,
+) -> Result<Calibration, String> {
+    let defaults = CalibrationSettings::default();
+    let settings = CalibrationSettings {
+        moves: moves.unwrap_or(defaults.moves).max(1),
+        step: step.unwrap_or(defaults.step),
+        settle: settle_ms.map_or(defaults.settle, Duration::from_millis),
+        ..defaults
+    };
+
+    // Keep the controller from moving the mouse while calibrating
+    let suspended = {
+        let mut controller = state.mouse_controller.lock().map_err(|e| e.to_string())?;
+        let suspended = controller.suspend();
+        emit_controller_status(&app_handle, controller.status());
+        suspended
+    };
+
+    let calibration = tauri::async_runtime::spawn_blocking(move || {
+        calibrate(&mut SendInputBackend, &mut ScreenCapture, &SystemClock, &settings)
+    })
+    .await;
+
+    resume_controller(&app_handle, &state, suspended)?;
+
+    calibration
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// Restores the controller status saved before a recording or calibration
+///
+/// The restored status is emitted to the frontend. A panic made in the
+/// meantime is kept, see `MouseController::resume`.
+fn resume_controller(
+    app_handle: &AppHandle,
+    state: &AppState,
+    suspended: SuspendedStatus,
+) -> Result<(), String> {
+    let mut controller = state.mouse_controller.lock().map_err(|e| e.to_string())?;
+    let status = controller.resume(suspended);
+    emit_controller_status(app_handle, status);
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use std::fmt;
+
+/// Error type for pattern extraction operations
+#[derive(Debug)]
+pub enum ExtractionError {
+    /// The video or frame directory could not be opened
+    OpenFailed(String),
+    /// A frame could not be read or decoded
+    ReadFailed(String),
+    /// The displacement between two frames could not be measured
+    TrackingFailed(String),
+    /// The source did not contain at least two frames
+    NotEnoughFrames,
+    /// Too many frame displacements were not trusted for the result to be meaningful
+    TooManyLostFrames {
+        /// Number of frames whose displacement was not trusted
+        lost: usize,
+        /// Number of frames that were read
+        frames: usize,
+    },
+}
+
+impl fmt::Display for ExtractionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtractionError::OpenFailed(reason) => write!(f, "Failed to open source: {}", reason),
+            ExtractionError::ReadFailed(reason) => write!(f, "Failed to read frame: {}", reason),
+            ExtractionError::TrackingFailed(reason) => {
+                write!(f, "Failed to track frame displacement: {}", reason)
+            }
+            ExtractionError::NotEnoughFrames => write!(f, "At least two frames are required"),
+            ExtractionError::TooManyLostFrames { lost, frames } => write!(
+                f,
+                "{} of {} frames could not be tracked; try a larger region or a lower minimum response",
+                lost, frames
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ExtractionError {}
@@ -0,0 +1,242 @@
+use std::time::Duration;
+
+use crate::extraction::error::ExtractionError;
+use crate::extraction::motion::MotionTracker;
+use crate::extraction::source::FrameSource;
+use crate::patterns::{Sensitivity, Step, Steps};
+
+/// Settings of a pattern extraction
+#[derive(Debug, Clone)]
+pub struct ExtractionSettings {
+    /// Fraction of the frame size around the crosshair that is tracked
+    pub region: f64,
+    /// Mouse counts that turn the view by one pixel of the video
+    pub counts_per_pixel: f64,
+    /// Smallest phase correlation response that is trusted; weaker frames are interpolated
+    pub min_response: f64,
+    /// Largest fraction of frame displacements that may be untrusted before extraction fails
+    pub max_lost_fraction: f64,
+}
+
+impl Default for ExtractionSettings {
+    fn default() -> Self {
+        Self {
+            region: 0.5,
+            counts_per_pixel: 1.0,
+            min_response: 0.05,
+            max_lost_fraction: 0.25,
+        }
+    }
+}
+
+/// Mouse movement needed to cancel the recoil up to a point in time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DriftSample {
+    /// Time since the first frame
+    pub time: Duration,
+    /// Cumulative horizontal compensation in counts
+    pub x: f64,
+    /// Cumulative vertical compensation in counts
+    pub y: f64,
+}
+
+/// Recoil measured in a video or frame directory
+#[derive(Debug, Clone)]
+pub struct Extraction {
+    /// Cumulative compensation at every frame, starting at zero
+    pub samples: Vec<DriftSample>,
+    /// Number of frames that were read
+    pub frames: usize,
+    /// Number of frames whose displacement was not trusted and whose position was interpolated
+    pub lost_frames: usize,
+}
+
+/// Measures the recoil of a spray by tracking the scene from frame to frame
+///
+/// Recoil turns the view, so the scene around the crosshair moves in the
+/// direction the mouse has to move to cancel it: a kick upwards moves the
+/// scene down, which is cancelled by pulling the mouse down.
+///
+/// Frames whose displacement is not trusted, e.g. during muzzle flash, are
+/// skipped: the next trusted frame is compared with the last trusted one and
+/// the skipped frames are placed on the straight line between the two. Skipped
+/// frames at the end keep the last trusted position.
+///
+/// # Arguments
+/// * `source` - Consecutive frames of the spray
+/// * `settings` - Tracked region, calibration and confidence threshold
+///
+/// # Returns
+/// The cumulative compensation at every frame, or an error if the frames
+/// could not be read or tracked, or too many of them were not trusted
+pub fn extract(
+    source: &mut dyn FrameSource,
+    settings: &ExtractionSettings,
+) -> Result<Extraction, ExtractionError> {
+    let interval = source.frame_interval();
+    let mut tracker = MotionTracker::new(settings.region);
+    let mut extraction = Extraction {
+        samples: Vec::new(),
+        frames: 0,
+        lost_frames: 0,
+    };
+    // Position of the last trusted frame and the number of untrusted frames since
+    let (mut x, mut y) = (0.0, 0.0);
+    let mut skipped = 0;
+
+    while let Some(frame) = source.next_frame()? {
+        let shift = tracker.track(&frame)?;
+        extraction.frames += 1;
+
+        match shift {
+            Some((dx, dy, response)) if response >= settings.min_response => {
+                // The displacement spans every skipped frame, so spread it evenly over them
+                let (to_x, to_y) = (
+                    x + dx * settings.counts_per_pixel,
+                    y + dy * settings.counts_per_pixel,
+                );
+                for index in 1..=skipped {
+                    let progress = index as f64 / (skipped + 1) as f64;
+                    extraction.push(
+                        interval,
+                        x + (to_x - x) * progress,
+                        y + (to_y - y) * progress,
+                    );
+                }
+                skipped = 0;
+                (x, y) = (to_x, to_y);
+                extraction.push(interval, x, y);
+            }
+            Some(_) => {
+                tracker.reject();
+                extraction.lost_frames += 1;
+                skipped += 1;
+            }
+            None => extraction.push(interval, x, y),
+        }
+    }
+
+    for _ in 0..skipped {
+        extraction.push(interval, x, y);
+    }
+
+    if extraction.frames < 2 {
+        return Err(ExtractionError::NotEnoughFrames);
+    }
+
+    // The first frame has nothing to be compared with
+    let compared = extraction.frames - 1;
+    if extraction.lost_frames as f64 > compared as f64 * settings.max_lost_fraction {
+        return Err(ExtractionError::TooManyLostFrames {
+            lost: extraction.lost_frames,
+            frames: extraction.frames,
+        });
+    }
+
+    Ok(extraction)
+}
+
+impl Extraction {
+    /// Appends the position of the next frame
+    fn push(&mut self, interval: Duration, x: f64, y: f64) {
+        self.samples.push(DriftSample {
+            time: interval * self.samples.len() as u32,
+            x,
+            y,
+        });
+    }
+
+    /// Returns the time between the first and the last frame
+    pub fn duration(&self) -> Duration {
+        self.samples
+            .last()
+            .map_or(Duration::ZERO, |sample| sample.time)
+    }
+
+    /// Converts the measured compensation into steps
+    ///
+    /// The compensation is cut into slices of `bucket` and each slice becomes a
+    /// step that moves at the rate needed to reach the measured position at its
+    /// end. The values are multiplied by the sensitivity, so the controller
+    /// sends the measured counts after dividing by it. Neighbouring steps with
+    /// the same movement are merged.
+    ///
+    /// # Arguments
+    /// * `tick` - Time between two iterations of the controller
+    /// * `bucket` - Length of the time slices the compensation is cut into
+    /// * `sensitivity` - Sensitivity settings the controller divides the steps by
+    ///
+    /// # Returns
+    /// The steps of the compensation pattern
+    pub fn to_steps(&self, tick: Duration, bucket: Duration, sensitivity: &Sensitivity) -> Steps {
+        let tick_ms = (tick.as_secs_f64() * 1000.0).max(1.0);
+        let bucket_ms = bucket.as_millis().max(1) as u64;
+        let total_ms = self.duration().as_millis() as u64;
+        let scale_x = if sensitivity.x > 0.0 {
+            sensitivity.x as f64
+        } else {
+            1.0
+        };
+        let scale_y = if sensitivity.y > 0.0 {
+            sensitivity.y as f64
+        } else {
+            1.0
+        };
+
+        // Rates are calculated from the emitted position, so rounding does not drift
+        let mut steps: Steps = Vec::new();
+        let (mut x, mut y) = (0.0, 0.0);
+        let mut start = 0;
+        while start < total_ms {
+            let end = (start + bucket_ms).min(total_ms);
+            let duration = end - start;
+            let ticks = duration as f64 / tick_ms;
+            let (target_x, target_y) = self.position_at(Duration::from_millis(end));
+
+            let dx = ((target_x - x) / ticks * scale_x).round() as i32;
+            let dy = ((target_y - y) / ticks * scale_y).round() as i32;
+            x += dx as f64 / scale_x * ticks;
+            y += dy as f64 / scale_y * ticks;
+
+            match steps.last_mut() {
+                Some(last) if last.dx == dx && last.dy == dy => last.duration += duration,
+                _ => steps.push(Step {
+                    duration,
+                    dx,
+                    dy,
+                    adjusted_dx: 0,
+                    adjusted_dy: 0,
+                }),
+            }
+
+            start = end;
+        }
+
+        steps
+    }
+
+    /// Returns the cumulative compensation at a point in time, interpolated between frames
+    fn position_at(&self, time: Duration) -> (f64, f64) {
+        let next = self
+            .samples
+            .iter()
+            .position(|sample| sample.time >= time)
+            .unwrap_or(self.samples.len() - 1);
+        if next == 0 {
+            return (self.samples[0].x, self.samples[0].y);
+        }
+
+        let (before, after) = (self.samples[next - 1], self.samples[next]);
+        let span = (after.time - before.time).as_secs_f64();
+        let progress = if span > 0.0 {
+            (time.saturating_sub(before.time).as_secs_f64() / span).min(1.0)
+        } else {
+            1.0
+        };
+
+        (
+            before.x + (after.x - before.x) * progress,
+            before.y + (after.y - before.y) * progress,
+        )
+    }
+}
@@ -0,0 +1,35 @@
+/// Extraction module for measuring recoil patterns in recorded gameplay.
+///
+/// This module reads a video or a directory of frames, tracks how the scene
+/// around the crosshair moves from frame to frame and turns the measured
+/// drift into the steps that compensate it.
+///
+/// # Structure
+///
+/// The module is organized into several components:
+/// - `extract`: Accumulates the frame displacements and converts them into steps
+/// - `motion`: Phase correlation of consecutive frames
+/// - `source`: Video file and frame directory sources
+/// - `error`: Contains error types for extraction operations
+///
+/// # Usage
+///
+/// The main entry point is the `extract` function, which reads any `FrameSource`
+/// opened with `open_source`.
+mod error;
+mod extract;
+mod motion;
+mod source;
+
+/// Public exports from the extraction module
+///
+/// These are the primary types that users of this module will interact with:
+/// - `extract`, `ExtractionSettings`: Measures the recoil of a spray
+/// - `Extraction`, `DriftSample`: Measured compensation that can be turned into steps
+/// - `FrameSource`, `VideoSource`, `FrameDirectory`, `open_source`: Frame sources
+/// - `MotionTracker`: Displacement between consecutive frames
+/// - `ExtractionError`: Errors of an extraction
+pub use error::ExtractionError;
+pub use extract::{extract, DriftSample, Extraction, ExtractionSettings};
+pub use motion::MotionTracker;
+pub use source::{open_source, FrameDirectory, FrameSource, VideoSource};
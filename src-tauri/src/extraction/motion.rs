@@ -0,0 +1,111 @@
+use opencv::core::{AlgorithmHint, Mat, Rect, Size, CV_64F};
+use opencv::imgproc;
+use opencv::prelude::MatTraitConst;
+
+use crate::extraction::error::ExtractionError;
+
+/// Measures how far the scene around the crosshair moves between consecutive frames
+///
+/// The displacement is found by phase correlation of the central region of
+/// two frames, which is robust against the brightness changes of muzzle flash.
+pub struct MotionTracker {
+    /// Fraction of the frame size around the center that is compared
+    region: f64,
+    /// Central region of the previous frame, prepared for phase correlation
+    previous: Option<Mat>,
+    /// Region that the last tracked frame replaced, restored by `reject`
+    replaced: Option<Mat>,
+    /// Window that fades out the borders of the region
+    window: Mat,
+}
+
+impl MotionTracker {
+    /// Creates a new tracker
+    ///
+    /// # Arguments
+    /// * `region` - Fraction of the frame size around the center that is compared
+    pub fn new(region: f64) -> Self {
+        Self {
+            region: region.clamp(0.05, 1.0),
+            previous: None,
+            replaced: None,
+            window: Mat::default(),
+        }
+    }
+
+    /// Compares a frame with the previous one
+    ///
+    /// # Arguments
+    /// * `frame` - The next frame in BGR order
+    ///
+    /// # Returns
+    /// The horizontal and vertical scene displacement in pixels together with
+    /// the response of the phase correlation, or `None` for the first frame
+    pub fn track(&mut self, frame: &Mat) -> Result<Option<(f64, f64, f64)>, ExtractionError> {
+        let current = self
+            .prepare(frame)
+            .map_err(|e| ExtractionError::TrackingFailed(e.to_string()))?;
+
+        let shift = match &self.previous {
+            Some(previous) if previous.size().ok() == current.size().ok() => {
+                let mut response = 0.0;
+                let shift =
+                    imgproc::phase_correlate(previous, &current, &self.window, &mut response)
+                        .map_err(|e| ExtractionError::TrackingFailed(e.to_string()))?;
+                Some((shift.x, shift.y, response))
+            }
+            Some(_) => {
+                return Err(ExtractionError::TrackingFailed(
+                    "Frames have different sizes".to_string(),
+                ))
+            }
+            None => None,
+        };
+
+        self.replaced = self.previous.replace(current);
+
+        Ok(shift)
+    }
+
+    /// Discards the last tracked frame, so the next frame is compared with the one before it
+    ///
+    /// Used for frames whose displacement is not trusted, e.g. during muzzle
+    /// flash, so the displacement across them is measured once a clear frame follows.
+    pub fn reject(&mut self) {
+        if self.replaced.is_some() {
+            self.previous = self.replaced.take();
+        }
+    }
+
+    /// Crops the central region of a frame and converts it to floating-point grayscale
+    fn prepare(&mut self, frame: &Mat) -> Result<Mat, opencv::Error> {
+        let mut gray = Mat::default();
+        imgproc::cvt_color(
+            frame,
+            &mut gray,
+            imgproc::COLOR_BGR2GRAY,
+            0,
+            AlgorithmHint::ALGO_HINT_DEFAULT,
+        )?;
+
+        // The crosshair stays in the center, so only the scene around it is compared
+        let width = ((gray.cols() as f64 * self.region) as i32).max(1);
+        let height = ((gray.rows() as f64 * self.region) as i32).max(1);
+        let rect = Rect::new(
+            (gray.cols() - width) / 2,
+            (gray.rows() - height) / 2,
+            width,
+            height,
+        );
+        let cropped = Mat::roi(&gray, rect)?.try_clone()?;
+
+        let mut prepared = Mat::default();
+        cropped.convert_to(&mut prepared, CV_64F, 1.0, 0.0)?;
+
+        if self.window.size()? != Size::new(width, height) {
+            imgproc::create_hanning_window(&mut self.window, Size::new(width, height), CV_64F)?;
+        }
+
+        Ok(prepared)
+    }
+}
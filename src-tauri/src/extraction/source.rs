@@ -0,0 +1,180 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use opencv::core::Mat;
+use opencv::prelude::{MatTraitConst, VideoCaptureTrait, VideoCaptureTraitConst};
+use opencv::{imgcodecs, videoio};
+
+use crate::extraction::error::ExtractionError;
+
+/// Image file extensions read from a frame directory
+const FRAME_EXTENSIONS: [&str; 4] = ["png", "jpg", "jpeg", "bmp"];
+
+/// Source of consecutive gameplay frames
+pub trait FrameSource {
+    /// Returns the time between two frames
+    fn frame_interval(&self) -> Duration;
+
+    /// Reads the next frame
+    ///
+    /// # Returns
+    /// The frame in BGR order, `None` after the last frame, or an error if it could not be read
+    fn next_frame(&mut self) -> Result<Option<Mat>, ExtractionError>;
+}
+
+/// Frames decoded from a video file
+pub struct VideoSource {
+    /// Open video file
+    capture: videoio::VideoCapture,
+    /// Time between two frames
+    interval: Duration,
+}
+
+impl VideoSource {
+    /// Opens a video file
+    ///
+    /// # Arguments
+    /// * `path` - Path of the video file
+    /// * `fps` - Frame rate to use instead of the one stored in the file
+    ///
+    /// # Returns
+    /// The opened video, or an error if it could not be opened or has no frame rate
+    pub fn open(path: &str, fps: Option<f64>) -> Result<Self, ExtractionError> {
+        let capture = videoio::VideoCapture::from_file(path, videoio::CAP_ANY)
+            .map_err(|e| ExtractionError::OpenFailed(e.to_string()))?;
+        if !capture.is_opened().unwrap_or(false) {
+            return Err(ExtractionError::OpenFailed(format!(
+                "Cannot open video {}",
+                path
+            )));
+        }
+
+        let fps = match fps {
+            Some(fps) => fps,
+            None => capture
+                .get(videoio::CAP_PROP_FPS)
+                .map_err(|e| ExtractionError::OpenFailed(e.to_string()))?,
+        };
+
+        Ok(Self {
+            capture,
+            interval: frame_interval(fps)?,
+        })
+    }
+}
+
+impl FrameSource for VideoSource {
+    fn frame_interval(&self) -> Duration {
+        self.interval
+    }
+
+    fn next_frame(&mut self) -> Result<Option<Mat>, ExtractionError> {
+        let mut frame = Mat::default();
+        match self.capture.read(&mut frame) {
+            Ok(true) if !frame.empty() => Ok(Some(frame)),
+            Ok(_) => Ok(None),
+            Err(e) => Err(ExtractionError::ReadFailed(e.to_string())),
+        }
+    }
+}
+
+/// Frames read from the image files of a directory in file name order
+pub struct FrameDirectory {
+    /// Image files that were not read yet, in reverse order
+    files: Vec<PathBuf>,
+    /// Time between two frames
+    interval: Duration,
+}
+
+impl FrameDirectory {
+    /// Lists the frames of a directory
+    ///
+    /// # Arguments
+    /// * `path` - Directory containing one image file per frame
+    /// * `fps` - Frame rate the frames were captured at
+    ///
+    /// # Returns
+    /// The listed frames, or an error if the directory could not be read
+    pub fn open(path: &str, fps: f64) -> Result<Self, ExtractionError> {
+        let entries = fs::read_dir(path).map_err(|e| ExtractionError::OpenFailed(e.to_string()))?;
+
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| is_frame(path))
+            .collect();
+        files.sort();
+        files.reverse();
+
+        Ok(Self {
+            files,
+            interval: frame_interval(fps)?,
+        })
+    }
+}
+
+impl FrameSource for FrameDirectory {
+    fn frame_interval(&self) -> Duration {
+        self.interval
+    }
+
+    fn next_frame(&mut self) -> Result<Option<Mat>, ExtractionError> {
+        let path = match self.files.pop() {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+
+        let frame = imgcodecs::imread(&path.to_string_lossy(), imgcodecs::IMREAD_COLOR)
+            .map_err(|e| ExtractionError::ReadFailed(e.to_string()))?;
+        if frame.empty() {
+            return Err(ExtractionError::ReadFailed(format!(
+                "Cannot decode {}",
+                path.display()
+            )));
+        }
+
+        Ok(Some(frame))
+    }
+}
+
+/// Opens a video file or, for a directory, its image files
+///
+/// # Arguments
+/// * `path` - Path of a video file or a frame directory
+/// * `fps` - Frame rate; required for a frame directory, overrides the video's otherwise
+pub fn open_source(path: &str, fps: Option<f64>) -> Result<Box<dyn FrameSource>, ExtractionError> {
+    if Path::new(path).is_dir() {
+        let fps = fps.ok_or_else(|| {
+            ExtractionError::OpenFailed(
+                "A frame rate is required for a frame directory".to_string(),
+            )
+        })?;
+        Ok(Box::new(FrameDirectory::open(path, fps)?))
+    } else {
+        Ok(Box::new(VideoSource::open(path, fps)?))
+    }
+}
+
+/// Returns whether a file has the extension of a supported image format
+fn is_frame(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| {
+            FRAME_EXTENSIONS
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(extension))
+        })
+        .unwrap_or(false)
+}
+
+/// Returns the time between two frames at the given frame rate
+fn frame_interval(fps: f64) -> Result<Duration, ExtractionError> {
+    if fps.is_finite() && fps > 0.0 {
+        Ok(Duration::from_secs_f64(1.0 / fps))
+    } else {
+        Err(ExtractionError::OpenFailed(format!(
+            "Invalid frame rate {}",
+            fps
+        )))
+    }
+}
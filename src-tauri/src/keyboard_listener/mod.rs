@@ -1,11 +1,10 @@
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
-use winapi::um::winuser::GetAsyncKeyState;
 
-/// Constant for the high-order bit that indicates if a key is pressed
-const KEY_PRESSED_MASK: i16 = 0x8000u16 as i16;
+use crate::mouse_controller::is_key_pressed;
 
 /// Type alias for key press callback functions
 pub type KeyPressCallback = Box<dyn Fn() + Send + 'static>;
@@ -19,10 +18,76 @@ pub struct Key {
     pub name: &'static str,
 }
 
-/// Common keyboard keys
+// Keys are stored in the configuration by their human-readable name
+impl Serialize for Key {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name)
+    }
+}
+
+impl<'de> Deserialize<'de> for Key {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        keys::from_name(&name).ok_or_else(|| de::Error::custom(format!("Unknown key: {}", name)))
+    }
+}
+
+/// Common keyboard keys and mouse buttons
 pub mod keys {
     use super::Key;
 
+    pub const LBUTTON: Key = Key {
+        code: 0x01,
+        name: "LButton",
+    };
+    pub const RBUTTON: Key = Key {
+        code: 0x02,
+        name: "RButton",
+    };
+    pub const MBUTTON: Key = Key {
+        code: 0x04,
+        name: "MButton",
+    };
+    pub const XBUTTON1: Key = Key {
+        code: 0x05,
+        name: "XButton1",
+    };
+    pub const XBUTTON2: Key = Key {
+        code: 0x06,
+        name: "XButton2",
+    };
+    pub const TAB: Key = Key {
+        code: 0x09,
+        name: "Tab",
+    };
+    pub const SHIFT: Key = Key {
+        code: 0x10,
+        name: "Shift",
+    };
+    pub const CTRL: Key = Key {
+        code: 0x11,
+        name: "Ctrl",
+    };
+    pub const ALT: Key = Key {
+        code: 0x12,
+        name: "Alt",
+    };
+    pub const CAPS_LOCK: Key = Key {
+        code: 0x14,
+        name: "CapsLock",
+    };
+    pub const SPACE: Key = Key {
+        code: 0x20,
+        name: "Space",
+    };
+    pub const END: Key = Key {
+        code: 0x23,
+        name: "End",
+    };
+    pub const KEY_0: Key = Key {
+        code: 0x30,
+        name: "0",
+    };
     pub const KEY_1: Key = Key {
         code: 0x31,
         name: "1",
@@ -31,10 +96,257 @@ pub mod keys {
         code: 0x32,
         name: "2",
     };
+    pub const KEY_3: Key = Key {
+        code: 0x33,
+        name: "3",
+    };
+    pub const KEY_4: Key = Key {
+        code: 0x34,
+        name: "4",
+    };
+    pub const KEY_5: Key = Key {
+        code: 0x35,
+        name: "5",
+    };
+    pub const KEY_6: Key = Key {
+        code: 0x36,
+        name: "6",
+    };
+    pub const KEY_7: Key = Key {
+        code: 0x37,
+        name: "7",
+    };
+    pub const KEY_8: Key = Key {
+        code: 0x38,
+        name: "8",
+    };
+    pub const KEY_9: Key = Key {
+        code: 0x39,
+        name: "9",
+    };
+    pub const KEY_A: Key = Key {
+        code: 0x41,
+        name: "A",
+    };
+    pub const KEY_B: Key = Key {
+        code: 0x42,
+        name: "B",
+    };
+    pub const KEY_C: Key = Key {
+        code: 0x43,
+        name: "C",
+    };
+    pub const KEY_D: Key = Key {
+        code: 0x44,
+        name: "D",
+    };
+    pub const KEY_E: Key = Key {
+        code: 0x45,
+        name: "E",
+    };
+    pub const KEY_F: Key = Key {
+        code: 0x46,
+        name: "F",
+    };
+    pub const KEY_G: Key = Key {
+        code: 0x47,
+        name: "G",
+    };
+    pub const KEY_H: Key = Key {
+        code: 0x48,
+        name: "H",
+    };
+    pub const KEY_I: Key = Key {
+        code: 0x49,
+        name: "I",
+    };
+    pub const KEY_J: Key = Key {
+        code: 0x4A,
+        name: "J",
+    };
+    pub const KEY_K: Key = Key {
+        code: 0x4B,
+        name: "K",
+    };
+    pub const KEY_L: Key = Key {
+        code: 0x4C,
+        name: "L",
+    };
+    pub const KEY_M: Key = Key {
+        code: 0x4D,
+        name: "M",
+    };
+    pub const KEY_N: Key = Key {
+        code: 0x4E,
+        name: "N",
+    };
+    pub const KEY_O: Key = Key {
+        code: 0x4F,
+        name: "O",
+    };
+    pub const KEY_P: Key = Key {
+        code: 0x50,
+        name: "P",
+    };
+    pub const KEY_Q: Key = Key {
+        code: 0x51,
+        name: "Q",
+    };
+    pub const KEY_R: Key = Key {
+        code: 0x52,
+        name: "R",
+    };
+    pub const KEY_S: Key = Key {
+        code: 0x53,
+        name: "S",
+    };
+    pub const KEY_T: Key = Key {
+        code: 0x54,
+        name: "T",
+    };
+    pub const KEY_U: Key = Key {
+        code: 0x55,
+        name: "U",
+    };
+    pub const KEY_V: Key = Key {
+        code: 0x56,
+        name: "V",
+    };
+    pub const KEY_W: Key = Key {
+        code: 0x57,
+        name: "W",
+    };
+    pub const KEY_X: Key = Key {
+        code: 0x58,
+        name: "X",
+    };
+    pub const KEY_Y: Key = Key {
+        code: 0x59,
+        name: "Y",
+    };
+    pub const KEY_Z: Key = Key {
+        code: 0x5A,
+        name: "Z",
+    };
     pub const KEY_F1: Key = Key {
         code: 0x70,
         name: "F1",
     };
+    pub const KEY_F2: Key = Key {
+        code: 0x71,
+        name: "F2",
+    };
+    pub const KEY_F3: Key = Key {
+        code: 0x72,
+        name: "F3",
+    };
+    pub const KEY_F4: Key = Key {
+        code: 0x73,
+        name: "F4",
+    };
+    pub const KEY_F5: Key = Key {
+        code: 0x74,
+        name: "F5",
+    };
+    pub const KEY_F6: Key = Key {
+        code: 0x75,
+        name: "F6",
+    };
+    pub const KEY_F7: Key = Key {
+        code: 0x76,
+        name: "F7",
+    };
+    pub const KEY_F8: Key = Key {
+        code: 0x77,
+        name: "F8",
+    };
+    pub const KEY_F9: Key = Key {
+        code: 0x78,
+        name: "F9",
+    };
+    pub const KEY_F10: Key = Key {
+        code: 0x79,
+        name: "F10",
+    };
+    pub const KEY_F11: Key = Key {
+        code: 0x7A,
+        name: "F11",
+    };
+    pub const KEY_F12: Key = Key {
+        code: 0x7B,
+        name: "F12",
+    };
+
+    /// All keys that can be referenced by name in the configuration
+    pub const ALL: &[Key] = &[
+        LBUTTON,
+        RBUTTON,
+        MBUTTON,
+        XBUTTON1,
+        XBUTTON2,
+        TAB,
+        SHIFT,
+        CTRL,
+        ALT,
+        CAPS_LOCK,
+        SPACE,
+        END,
+        KEY_0,
+        KEY_1,
+        KEY_2,
+        KEY_3,
+        KEY_4,
+        KEY_5,
+        KEY_6,
+        KEY_7,
+        KEY_8,
+        KEY_9,
+        KEY_A,
+        KEY_B,
+        KEY_C,
+        KEY_D,
+        KEY_E,
+        KEY_F,
+        KEY_G,
+        KEY_H,
+        KEY_I,
+        KEY_J,
+        KEY_K,
+        KEY_L,
+        KEY_M,
+        KEY_N,
+        KEY_O,
+        KEY_P,
+        KEY_Q,
+        KEY_R,
+        KEY_S,
+        KEY_T,
+        KEY_U,
+        KEY_V,
+        KEY_W,
+        KEY_X,
+        KEY_Y,
+        KEY_Z,
+        KEY_F1,
+        KEY_F2,
+        KEY_F3,
+        KEY_F4,
+        KEY_F5,
+        KEY_F6,
+        KEY_F7,
+        KEY_F8,
+        KEY_F9,
+        KEY_F10,
+        KEY_F11,
+        KEY_F12,
+    ];
+
+    /// Looks up a key by its human-readable name (case-insensitive)
+    pub fn from_name(name: &str) -> Option<Key> {
+        ALL.iter()
+            .find(|key| key.name.eq_ignore_ascii_case(name))
+            .copied()
+    }
 }
 
 /// Keyboard listener that monitors key presses and executes callbacks
@@ -47,6 +359,8 @@ pub struct KeyboardListener {
     poll_interval: u64,
     /// Flag to control the listening loop
     running: Arc<Mutex<bool>>,
+    /// Handle of the listening thread, taken when the listener is stopped
+    thread: Option<thread::JoinHandle<()>>,
 }
 
 impl KeyboardListener {
@@ -62,6 +376,7 @@ impl KeyboardListener {
             key_states: Arc::new(Mutex::new(HashMap::new())),
             poll_interval,
             running: Arc::new(Mutex::new(false)),
+            thread: None,
         }
     }
 
@@ -82,11 +397,17 @@ impl KeyboardListener {
 
     /// Checks if a specific key is currently pressed
     fn is_key_pressed(key_code: i32) -> bool {
-        unsafe { GetAsyncKeyState(key_code) & KEY_PRESSED_MASK != 0 }
+        is_key_pressed(key_code)
     }
 
     /// Starts the keyboard listening loop in a background thread
-    pub fn start(&self) -> thread::JoinHandle<()> {
+    ///
+    /// Calling this method while the listener is already running has no effect.
+    pub fn start(&mut self) {
+        if self.thread.is_some() {
+            return;
+        }
+
         // Set running flag to true
         if let Ok(mut running) = self.running.lock() {
             *running = true;
@@ -99,7 +420,7 @@ impl KeyboardListener {
         let poll_interval = self.poll_interval;
 
         // Start the background thread
-        thread::spawn(move || {
+        self.thread = Some(thread::spawn(move || {
             while let Ok(is_running) = running.lock() {
                 if !*is_running {
                     break;
@@ -127,14 +448,24 @@ impl KeyboardListener {
                 // Sleep to avoid high CPU usage
                 thread::sleep(Duration::from_millis(poll_interval));
             }
-        })
+        }));
     }
 
-    /// Stops the keyboard listening loop
-    pub fn stop(&self) {
+    /// Stops the keyboard listening loop and waits for the thread to finish
+    pub fn stop(&mut self) {
         if let Ok(mut running) = self.running.lock() {
             *running = false;
         }
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for KeyboardListener {
+    fn drop(&mut self) {
+        self.stop();
     }
 }
 
@@ -1,14 +1,24 @@
+use crate::commands::{emit_controller_status, emit_scope, AppState};
 use crate::keyboard_listener::{keys, KeyboardListener};
-use crate::mouse_controller::MouseController;
+use crate::mouse_controller::{ControllerTransition, MouseController};
 use crate::patterns::PatternCollection;
 use log::{error, info};
-use std::sync::{Arc, Mutex};
-use tauri::Emitter;
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use tauri::{Emitter, Manager};
 
+pub mod calibration;
+pub mod cli;
+mod commands;
+pub mod extraction;
 mod keyboard_listener;
-mod mouse_controller;
-mod patterns;
+pub mod mouse_controller;
+pub mod patterns;
+pub mod preview;
+pub mod recorder;
 mod screen_reader;
+pub mod scripting;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -23,8 +33,10 @@ pub fn run() {
             }
 
             // Create instances of services
-            let pattern_collection = Arc::new(PatternCollection::new());
-            let mouse_controller = Arc::new(Mutex::new(MouseController::new()));
+            let pattern_collection = Arc::new(RwLock::new(PatternCollection::new()));
+            let mut controller = MouseController::new();
+            let transitions = controller.take_transitions();
+            let mouse_controller = Arc::new(Mutex::new(controller));
 
             // Create a keyboard listener
             let mut keyboard_listener = KeyboardListener::new();
@@ -32,6 +44,11 @@ pub fn run() {
             // Get the app handle for emitting events to the frontend
             let app_handle = app.handle();
 
+            // Forward the controller phase changes to the frontend
+            if let Some(transitions) = transitions {
+                forward_controller_transitions(app_handle.clone(), transitions);
+            }
+
             // Register callbacks for keys
             register_key_callback(
                 app_handle.clone(),
@@ -54,14 +71,82 @@ pub fn run() {
                 &pattern_collection,
                 &mouse_controller,
             );
+            register_toggle_callback(
+                app_handle.clone(),
+                &mut keyboard_listener,
+                keys::KEY_F2,
+                &mouse_controller,
+            );
+            register_scope_callback(
+                app_handle.clone(),
+                &mut keyboard_listener,
+                keys::KEY_F3,
+                &mouse_controller,
+            );
+            register_panic_callback(
+                app_handle.clone(),
+                &mut keyboard_listener,
+                keys::END,
+                &mouse_controller,
+            );
 
             // Start the keyboard listener
             keyboard_listener.start();
 
+            // Share the services with Tauri commands
+            app.manage(AppState {
+                pattern_collection,
+                mouse_controller,
+                keyboard_listener: Mutex::new(keyboard_listener),
+            });
+
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .invoke_handler(tauri::generate_handler![
+            commands::get_controller_status,
+            commands::toggle_controller,
+            commands::get_transform,
+            commands::set_transform,
+            commands::set_strength,
+            commands::set_scope,
+            commands::cycle_scope,
+            commands::get_game_sensitivity,
+            commands::set_game_settings,
+            commands::convert_patterns,
+            commands::simplify_patterns,
+            commands::export_patterns_csv,
+            commands::import_patterns_csv,
+            commands::simulate_pattern,
+            commands::render_pattern_preview,
+            commands::get_pattern_stats,
+            commands::record_pattern,
+            commands::calibrate_sensitivity,
+        ])
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            // Stop the background threads before the process exits; the state is
+            // missing when the setup failed before it was managed
+            if let tauri::RunEvent::Exit = event {
+                if let Some(state) = app_handle.try_state::<AppState>() {
+                    state.shutdown();
+                }
+            }
+        });
+}
+
+/// Forwards the controller phase transitions to the frontend
+///
+/// The forwarding thread ends on its own once the controller thread stops.
+fn forward_controller_transitions(
+    app_handle: tauri::AppHandle,
+    transitions: Receiver<ControllerTransition>,
+) {
+    thread::spawn(move || {
+        for transition in transitions {
+            let _ = app_handle.emit("controller-transition", transition);
+        }
+    });
 }
 
 /// Registers a callback for a specific key that will detect and set patterns
@@ -69,17 +154,18 @@ fn register_key_callback(
     app_handle: tauri::AppHandle,
     keyboard_listener: &mut KeyboardListener,
     key: keyboard_listener::Key,
-    pattern_collection: &Arc<PatternCollection>,
+    pattern_collection: &Arc<RwLock<PatternCollection>>,
     mouse_controller: &Arc<Mutex<MouseController>>,
 ) {
     let pattern_collection_clone = pattern_collection.clone();
     let mouse_controller_clone = mouse_controller.clone();
     let app_handle_clone = app_handle.clone();
     keyboard_listener.on_key_press(key, move || {
-        if let Ok(mut controller) = mouse_controller_clone.lock() {
-            if let Some(pattern_name) =
-                detect_and_set_pattern(&pattern_collection_clone, &mut controller)
-            {
+        // The collection is always locked before the controller
+        if let (Ok(collection), Ok(mut controller)) =
+            (pattern_collection_clone.read(), mouse_controller_clone.lock())
+        {
+            if let Some(pattern_name) = detect_and_set_pattern(&collection, &mut controller) {
                 // Emit the pattern name to the frontend
                 let _ = app_handle_clone.emit("pattern-selected", pattern_name);
             } else {
@@ -94,10 +180,15 @@ fn detect_and_set_pattern(
     pattern_collection: &PatternCollection,
     mouse_controller: &mut MouseController,
 ) -> Option<String> {
+    // Ignore detection while the controller is disarmed by the panic key
+    if !mouse_controller.status().armed {
+        return None;
+    }
+
     // Detect which pattern is on the screen
     if let Some(pattern_name) = pattern_collection.detect_pattern() {
         // Set the pattern for the controller
-        mouse_controller.update_steps(pattern_collection.get_pattern(&pattern_name).config);
+        mouse_controller.update_pattern(pattern_collection.get_pattern(&pattern_name));
 
         Some(pattern_name)
     } else {
@@ -113,7 +204,7 @@ fn register_reload_callback(
     app_handle: tauri::AppHandle,
     keyboard_listener: &mut KeyboardListener,
     key: keyboard_listener::Key,
-    pattern_collection: &Arc<PatternCollection>,
+    pattern_collection: &Arc<RwLock<PatternCollection>>,
     mouse_controller: &Arc<Mutex<MouseController>>,
 ) {
     let pattern_collection_clone = Arc::clone(pattern_collection);
@@ -123,23 +214,25 @@ fn register_reload_callback(
     keyboard_listener.on_key_press(key, move || {
         info!("Reloading JSON config...");
 
-        // Create a mutable reference to the pattern collection
-        let pattern_collection_mut =
-            unsafe { &mut *(Arc::as_ptr(&pattern_collection_clone) as *mut PatternCollection) };
+        // Reload the JSON config, holding the write lock only while it is replaced
+        let reloaded = match pattern_collection_clone.write() {
+            Ok(mut collection) => collection.reload(),
+            Err(e) => Err(format!("Failed to acquire write lock on pattern collection: {}", e)),
+        };
 
-        // Reload the JSON config
-        match pattern_collection_mut.reload() {
+        match reloaded {
             Ok(()) => {
                 info!("JSON config reloaded successfully");
 
                 // Update the sensitivity settings
-                if let Ok(mut controller) = mouse_controller_clone.lock() {
-                    // Update sensitivity from the reloaded config
-                    controller.update_sensitivity(pattern_collection_clone.sensitivity.clone());
+                if let (Ok(collection), Ok(mut controller)) =
+                    (pattern_collection_clone.read(), mouse_controller_clone.lock())
+                {
+                    // Update the settings from the reloaded config
+                    controller.update_settings(&collection);
 
                     // Update the active pattern
-                    if let Some(pattern_name) =
-                        detect_and_set_pattern(&pattern_collection_clone, &mut controller)
+                    if let Some(pattern_name) = detect_and_set_pattern(&collection, &mut controller)
                     {
                         // Emit the pattern name to the frontend
                         let _ = app_handle_clone.emit("pattern-selected", pattern_name);
@@ -155,3 +248,57 @@ fn register_reload_callback(
         }
     });
 }
+
+/// Registers a callback for a specific key that will enable or disable the controller
+fn register_toggle_callback(
+    app_handle: tauri::AppHandle,
+    keyboard_listener: &mut KeyboardListener,
+    key: keyboard_listener::Key,
+    mouse_controller: &Arc<Mutex<MouseController>>,
+) {
+    let mouse_controller_clone = Arc::clone(mouse_controller);
+
+    keyboard_listener.on_key_press(key, move || {
+        if let Ok(mut controller) = mouse_controller_clone.lock() {
+            let status = controller.toggle_enabled();
+            emit_controller_status(&app_handle, status);
+        }
+    });
+}
+
+/// Registers a callback for a specific key that will select the next scope of the pattern
+fn register_scope_callback(
+    app_handle: tauri::AppHandle,
+    keyboard_listener: &mut KeyboardListener,
+    key: keyboard_listener::Key,
+    mouse_controller: &Arc<Mutex<MouseController>>,
+) {
+    let mouse_controller_clone = Arc::clone(mouse_controller);
+
+    keyboard_listener.on_key_press(key, move || {
+        if let Ok(mut controller) = mouse_controller_clone.lock() {
+            let scope = controller.cycle_scope();
+            emit_scope(&app_handle, scope);
+        }
+    });
+}
+
+/// Registers a callback for the panic key that will immediately stop the controller
+fn register_panic_callback(
+    app_handle: tauri::AppHandle,
+    keyboard_listener: &mut KeyboardListener,
+    key: keyboard_listener::Key,
+    mouse_controller: &Arc<Mutex<MouseController>>,
+) {
+    let mouse_controller_clone = Arc::clone(mouse_controller);
+
+    keyboard_listener.on_key_press(key, move || {
+        if let Ok(mut controller) = mouse_controller_clone.lock() {
+            let status = controller.panic();
+            emit_controller_status(&app_handle, status);
+
+            // The pattern was cleared as well
+            let _ = app_handle.emit("pattern-selected", "");
+        }
+    });
+}
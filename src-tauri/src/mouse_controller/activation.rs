@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use crate::patterns::ActivationRule;
+
+/// Tracked state of a single toggle key
+#[derive(Debug, Default, Clone, Copy)]
+struct ToggleState {
+    /// Whether the key was pressed during the last evaluation
+    pressed: bool,
+    /// Whether the toggle is currently on
+    on: bool,
+    /// Evaluation round in which this toggle was last updated
+    round: u64,
+}
+
+/// Rule engine that evaluates activation rules against the current key states
+///
+/// The engine keeps track of toggle keys between evaluations, so it must be
+/// evaluated continuously (once per controller tick) for toggles to register
+/// every press.
+#[derive(Debug, Default)]
+pub struct ActivationEngine {
+    /// Toggle states by virtual key code
+    toggles: HashMap<i32, ToggleState>,
+    /// Number of evaluations performed so far
+    round: u64,
+}
+
+impl ActivationEngine {
+    /// Creates a new engine with all toggles turned off
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Turns all toggles off and forgets the tracked key states
+    ///
+    /// Called when the rules change, so a toggle that was turned on for the
+    /// previous rules does not carry over to the new ones.
+    pub fn reset(&mut self) {
+        self.toggles.clear();
+    }
+
+    /// Evaluates the rule against the current key states
+    ///
+    /// All nested rules are evaluated without short-circuiting, so toggle keys
+    /// are tracked even while other parts of the rule are inactive.
+    ///
+    /// # Arguments
+    /// * `rule` - The activation rule to evaluate
+    /// * `is_pressed` - Returns whether the key with the given virtual key code is held down
+    ///
+    /// # Returns
+    /// `true` if recoil compensation should be active, `false` otherwise
+    pub fn evaluate(&mut self, rule: &ActivationRule, is_pressed: &impl Fn(i32) -> bool) -> bool {
+        self.round += 1;
+        self.evaluate_rule(rule, is_pressed)
+    }
+
+    fn evaluate_rule(&mut self, rule: &ActivationRule, is_pressed: &impl Fn(i32) -> bool) -> bool {
+        match rule {
+            ActivationRule::Key(key) => is_pressed(key.code),
+            ActivationRule::Toggle(key) => {
+                let round = self.round;
+                let toggle = self.toggles.entry(key.code).or_default();
+
+                // Update each toggle only once per evaluation, even if the key appears several times
+                if toggle.round != round {
+                    let pressed = is_pressed(key.code);
+                    if pressed && !toggle.pressed {
+                        toggle.on = !toggle.on;
+                    }
+                    toggle.pressed = pressed;
+                    toggle.round = round;
+                }
+
+                toggle.on
+            }
+            ActivationRule::All(rules) => {
+                let mut all = true;
+                for rule in rules {
+                    all &= self.evaluate_rule(rule, is_pressed);
+                }
+                all
+            }
+            ActivationRule::Any(rules) => {
+                let mut any = false;
+                for rule in rules {
+                    any |= self.evaluate_rule(rule, is_pressed);
+                }
+                any
+            }
+            ActivationRule::Not(rule) => !self.evaluate_rule(rule, is_pressed),
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::mouse_controller::simulation::simulate_state;
+use crate::mouse_controller::state::MouseControllerState;
+use crate::mouse_controller::transform::{collapses_to_minimum, TransformStage};
+use crate::patterns::{Pattern, PatternCollection, Sensitivity, TransformSettings};
+
+/// Time over which a script without steps is measured, since it never ends on its own
+pub const SCRIPT_WINDOW: Duration = Duration::from_secs(3);
+
+/// Metrics of a pattern, used to spot mis-tuned patterns
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PatternStats {
+    /// Number of steps, bullet offsets or shots
+    pub steps: usize,
+    /// Time needed to go through the whole pattern, including the start delay; not set for
+    /// semi-automatic patterns and scripts without steps
+    pub duration_ms: Option<u64>,
+    /// Total horizontal displacement before sensitivity
+    pub raw_dx: i32,
+    /// Total vertical displacement before sensitivity
+    pub raw_dy: i32,
+    /// Length of the total displacement before sensitivity
+    pub raw_distance: f32,
+    /// Total horizontal displacement after sensitivity and transforms
+    pub dx: i32,
+    /// Total vertical displacement after sensitivity and transforms
+    pub dy: i32,
+    /// Length of the total displacement after sensitivity and transforms
+    pub distance: f32,
+    /// Largest horizontal movement sent in a single iteration
+    pub peak_dx: i32,
+    /// Largest vertical movement sent in a single iteration
+    pub peak_dy: i32,
+    /// Number of steps whose movement on an axis is lost by the transforms, either
+    /// because it only keeps the one-pixel minimum after sensitivity or because it
+    /// is reduced to zero
+    pub collapsed_steps: usize,
+}
+
+/// Computes the metrics of a pattern with the settings of a configuration
+///
+/// # Arguments
+/// * `pattern` - The pattern to analyze
+/// * `collection` - Configuration whose settings are applied to the pattern
+/// * `tick` - Time between two iterations of the controller
+///
+/// # Returns
+/// The metrics of the pattern
+pub fn analyze(pattern: &Pattern, collection: &PatternCollection, tick: Duration) -> PatternStats {
+    let mut state = MouseControllerState::from_collection(collection);
+    state.set_pattern(pattern.clone());
+
+    analyze_state(state, tick)
+}
+
+/// Computes the metrics of the pattern of a controller state
+///
+/// Displacements are measured by simulating the whole pattern, so they match
+/// what the controller would send. Semi-automatic patterns are measured by
+/// firing every shot once, and their peak is the largest impulse. Scripts
+/// without steps are measured over `SCRIPT_WINDOW`.
+pub(crate) fn analyze_state(mut state: MouseControllerState, tick: Duration) -> PatternStats {
+    state.recalculate_adjusted();
+    let sensitivity = state.effective_sensitivity();
+
+    // Raw and adjusted movement values of the steps, bullet offsets or shots
+    let values: Vec<((i32, i32), (i32, i32))> =
+        match (&state.semi_auto, &state.fire_rate, &state.steps) {
+            (Some(semi_auto), _, _) => semi_auto
+                .shots
+                .iter()
+                .map(|s| ((s.dx, s.dy), (s.adjusted_dx, s.adjusted_dy)))
+                .collect(),
+            (None, Some(fire_rate), _) => fire_rate
+                .offsets
+                .iter()
+                .map(|o| ((o.dx, o.dy), (o.adjusted_dx, o.adjusted_dy)))
+                .collect(),
+            (None, None, Some(steps)) => steps
+                .iter()
+                .map(|s| ((s.dx, s.dy), (s.adjusted_dx, s.adjusted_dy)))
+                .collect(),
+            (None, None, None) => Vec::new(),
+        };
+    let clamp = TransformStage::Clamp {
+        max_dx: state.transform.max_dx,
+        max_dy: state.transform.max_dy,
+    };
+    let collapses = |raw: i32, adjusted: i32, sensitivity: f32| {
+        raw != 0 && (adjusted == 0 || collapses_to_minimum(raw, sensitivity))
+    };
+    let collapsed_steps = values
+        .iter()
+        .filter(|((dx, dy), (adjusted_dx, adjusted_dy))| {
+            // The clamps limit whole iterations, a single value is clamped like one
+            let (adjusted_dx, adjusted_dy) = clamp.apply(*adjusted_dx, *adjusted_dy);
+            collapses(*dx, adjusted_dx, sensitivity.x) || collapses(*dy, adjusted_dy, sensitivity.y)
+        })
+        .count();
+
+    let mut stats = PatternStats {
+        steps: values.len(),
+        duration_ms: None,
+        raw_dx: 0,
+        raw_dy: 0,
+        raw_distance: 0.0,
+        dx: 0,
+        dy: 0,
+        distance: 0.0,
+        peak_dx: 0,
+        peak_dy: 0,
+        collapsed_steps,
+    };
+
+    if let Some(semi_auto) = &state.semi_auto {
+        for shot in &semi_auto.shots {
+            stats.raw_dx += shot.dx;
+            stats.raw_dy += shot.dy;
+            stats.dx += shot.adjusted_dx;
+            stats.dy += shot.adjusted_dy;
+            stats.peak_dx = stats.peak_dx.max(shot.adjusted_dx.abs());
+            stats.peak_dy = stats.peak_dy.max(shot.adjusted_dy.abs());
+        }
+    } else {
+        let duration = match (&state.fire_rate, &state.steps) {
+            (Some(fire_rate), _) => Some(fire_rate.bullet_interval() * fire_rate.magazine),
+            (None, Some(steps)) if steps.is_empty() && state.script.is_some() => None,
+            (None, Some(steps)) => {
+                Some(Duration::from_millis(steps.iter().map(|s| s.duration).sum()))
+            }
+            (None, None) => Some(Duration::ZERO),
+        };
+        let hold = match duration {
+            Some(duration) => state.start_delay + duration,
+            None => state.start_delay + SCRIPT_WINDOW,
+        };
+        stats.duration_ms = duration.map(|_| hold.as_millis() as u64);
+
+        let raw = simulate_state(without_scaling(&state), tick, hold);
+        stats.raw_dx = raw.x;
+        stats.raw_dy = raw.y;
+
+        let trajectory = simulate_state(state, tick, hold);
+        stats.dx = trajectory.x;
+        stats.dy = trajectory.y;
+        for step in &trajectory.moves {
+            stats.peak_dx = stats.peak_dx.max(step.dx.abs());
+            stats.peak_dy = stats.peak_dy.max(step.dy.abs());
+        }
+    }
+
+    stats.raw_distance = (stats.raw_dx as f32).hypot(stats.raw_dy as f32);
+    stats.distance = (stats.dx as f32).hypot(stats.dy as f32);
+    stats
+}
+
+/// Returns a copy of the state that sends the raw pattern values
+fn without_scaling(state: &MouseControllerState) -> MouseControllerState {
+    let mut state = state.clone();
+    state.sensitivity = Sensitivity { x: 1.0, y: 1.0 };
+    state.pattern_sensitivity = None;
+    state.game = None;
+    state.scope = None;
+    state.transform = TransformSettings::default();
+    state
+}
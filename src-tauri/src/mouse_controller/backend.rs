@@ -0,0 +1,51 @@
+use crate::mouse_controller::error::MouseInputError;
+use crate::mouse_controller::utils::{is_key_pressed, send_mouse_input};
+
+/// Destination of relative mouse movement
+///
+/// Tools that move the mouse send their movement only through this trait, so
+/// they can be driven against a synthetic backend instead of the system.
+pub trait InputBackend {
+    /// Moves the mouse by the given number of counts
+    ///
+    /// # Arguments
+    /// * `dx` - Horizontal movement in counts
+    /// * `dy` - Vertical movement in counts
+    fn send_move(&mut self, dx: i32, dy: i32) -> Result<(), MouseInputError>;
+}
+
+/// Backend that sends the movement to the operating system with `SendInput`
+///
+/// Only Windows is supported; elsewhere every movement fails with `MouseInputError::Unsupported`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SendInputBackend;
+
+impl InputBackend for SendInputBackend {
+    fn send_move(&mut self, dx: i32, dy: i32) -> Result<(), MouseInputError> {
+        send_mouse_input(dx, dy)
+    }
+}
+
+/// Source of the state of keys and mouse buttons
+///
+/// The controller thread reads keys only through this trait, so the activation
+/// rule and modifiers can be driven by a synthetic key state.
+pub trait KeyState {
+    /// Returns whether the key with the given virtual key code is held down
+    ///
+    /// # Arguments
+    /// * `key_code` - Virtual key code of the key or mouse button
+    fn is_pressed(&self, key_code: i32) -> bool;
+}
+
+/// Key state read from the operating system with `GetAsyncKeyState`
+///
+/// Only Windows is supported; elsewhere no key is ever pressed.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemKeyState;
+
+impl KeyState for SystemKeyState {
+    fn is_pressed(&self, key_code: i32) -> bool {
+        is_key_pressed(key_code)
+    }
+}
@@ -0,0 +1,150 @@
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Source of time for the controller loop
+///
+/// The controller thread reads the current time and sleeps between iterations
+/// only through this trait, so its timing behaviour can be driven by a virtual clock.
+pub trait Clock: Send + Sync {
+    /// Returns the current time
+    fn now(&self) -> Instant;
+
+    /// Waits for the given duration
+    fn sleep(&self, duration: Duration);
+
+    /// Wakes every thread sleeping on the clock and stops further sleeps from waiting
+    ///
+    /// Called when the controller shuts down, so its thread can notice that it
+    /// has to stop. Clocks whose sleeps always end on their own do nothing.
+    fn release(&self) {}
+}
+
+/// Clock backed by the operating system
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// Virtual time of a `ManualClock`
+#[derive(Debug, Default)]
+struct ManualTime {
+    /// Virtual time elapsed since the origin
+    elapsed: Duration,
+    /// Number of sleeps started on the clock
+    sleeps: u64,
+    /// Whether the clock was released, so sleeps return immediately
+    released: bool,
+}
+
+/// Virtual clock that only advances when told to
+///
+/// Sleeping on this clock blocks until another thread has advanced the virtual
+/// time past the end of the sleep, so a test can run the controller thread one
+/// iteration at a time and observe it between iterations.
+///
+/// # Thread Safety
+///
+/// The clock is shared between the thread that sleeps on it and the thread
+/// that advances it. `wait_for_sleeps` lets the advancing thread wait until the
+/// sleeping thread has finished an iteration.
+#[derive(Debug)]
+pub struct ManualClock {
+    /// Real instant that represents the virtual time zero
+    origin: Instant,
+    /// Virtual time and sleep count, protected by a mutex
+    time: Mutex<ManualTime>,
+    /// Notified whenever the virtual time advances or a sleep starts
+    changed: Condvar,
+}
+
+impl ManualClock {
+    /// Creates a new virtual clock starting at zero
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+            time: Mutex::new(ManualTime::default()),
+            changed: Condvar::new(),
+        }
+    }
+
+    /// Advances the virtual time by the given duration, waking threads whose sleep has ended
+    pub fn advance(&self, duration: Duration) {
+        if let Some(mut time) = self.lock() {
+            time.elapsed += duration;
+            self.changed.notify_all();
+        }
+    }
+
+    /// Returns the virtual time elapsed since the clock was created
+    pub fn elapsed(&self) -> Duration {
+        self.lock().map_or(Duration::ZERO, |time| time.elapsed)
+    }
+
+    /// Waits until `sleep` has been called the given number of times in total
+    ///
+    /// A thread that sleeps once per iteration has then finished that many
+    /// iterations and waits for the virtual time to advance.
+    ///
+    /// # Arguments
+    /// * `count` - Number of sleeps to wait for
+    pub fn wait_for_sleeps(&self, count: u64) {
+        let Some(mut time) = self.lock() else {
+            return;
+        };
+        while time.sleeps < count && !time.released {
+            time = match self.changed.wait(time) {
+                Ok(time) => time,
+                Err(_) => return,
+            };
+        }
+    }
+
+    /// Locks the virtual time, or returns `None` if a thread panicked while holding it
+    fn lock(&self) -> Option<MutexGuard<'_, ManualTime>> {
+        self.time.lock().ok()
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.origin + self.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        let Some(mut time) = self.lock() else {
+            return;
+        };
+        time.sleeps += 1;
+        self.changed.notify_all();
+
+        let wake = time.elapsed + duration;
+        while time.elapsed < wake && !time.released {
+            time = match self.changed.wait(time) {
+                Ok(time) => time,
+                Err(_) => return,
+            };
+        }
+    }
+
+    fn release(&self) {
+        if let Some(mut time) = self.lock() {
+            time.released = true;
+            self.changed.notify_all();
+        }
+    }
+}
@@ -1,15 +1,34 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
 use std::sync::Arc;
-use std::thread;
-use std::time::{Duration, Instant};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
 use log::{debug, error, info};
 
-use crate::mouse_controller::state::MouseControllerState;
-use crate::mouse_controller::utils::{send_mouse_input, should_run};
-use crate::patterns::{PatternCollection, Sensitivity, Step, Steps};
+use crate::mouse_controller::state::{ControllerSnapshot, ControllerStatus, MouseControllerState};
+use crate::mouse_controller::activation::ActivationEngine;
+use crate::mouse_controller::analysis::{analyze_state, PatternStats};
+use crate::mouse_controller::backend::{InputBackend, KeyState, SendInputBackend, SystemKeyState};
+use crate::mouse_controller::clock::{Clock, SystemClock};
+use crate::mouse_controller::simulation::{simulate_state, Trajectory};
+use crate::mouse_controller::state_machine::{ControllerTransition, StateMachine};
+use crate::patterns::{
+    ActivationRule, FireRate, GameSensitivity, Modifier, Pattern, PatternCollection, SemiAuto,
+    Sensitivity, Steps, TransformSettings,
+};
 
 /// Default sleep duration between pattern processing iterations
-const DEFAULT_THREAD_SLEEP_DURATION_MS: u64 = 24;
+pub const DEFAULT_THREAD_SLEEP_DURATION_MS: u64 = 24;
+
+/// Status of the controller saved by `MouseController::suspend`
+#[derive(Debug, Clone, Copy)]
+pub struct SuspendedStatus {
+    /// Status before the controller was suspended
+    status: ControllerStatus,
+    /// Number of status changes made before the controller was suspended
+    changes: u64,
+}
 
 /// Controller for programmatic mouse movements
 ///
@@ -22,6 +41,19 @@ pub struct MouseController {
     /// and the background thread. Protected by a read-write lock to ensure
     /// thread safety.
     state: Arc<std::sync::RwLock<MouseControllerState>>,
+    /// Incremented whenever a new snapshot of the state is published, so the
+    /// background thread only needs to acquire the lock after a change
+    generation: Arc<AtomicU64>,
+    /// Flag that keeps the background thread running until the controller is shut down
+    running: Arc<AtomicBool>,
+    /// Clock of the background thread, released on shutdown so the thread wakes up
+    clock: Arc<dyn Clock>,
+    /// Handle of the background thread, taken when the controller is shut down
+    thread: Option<JoinHandle<()>>,
+    /// Receiver of the phase transitions, until taken by an observer
+    transitions: Option<Receiver<ControllerTransition>>,
+    /// Number of times the controller was enabled, disabled or panicked
+    status_changes: u64,
 }
 
 impl MouseController {
@@ -32,27 +64,131 @@ impl MouseController {
     /// monitor mouse button states and apply the pattern when conditions are met.
     ///
     /// # Thread Management
-    /// This method spawns a background thread that runs for the lifetime of the
-    /// program. The thread is not joined or stopped explicitly, as it's expected
-    /// to run until program termination.
+    /// This method spawns a background thread that runs until `shutdown` is
+    /// called or the controller is dropped.
     ///
     /// # Returns
     /// A MouseController instance that can be used to update the movement pattern
     pub fn new() -> Self {
         // Load sensitivity settings from configuration
         let pattern_collection = PatternCollection::new();
-        let sensitivity = pattern_collection.sensitivity.clone();
 
+        let mut controller = Self::with_settings(
+            pattern_collection.sensitivity.clone(),
+            pattern_collection.activation.clone(),
+        );
+        controller.update_settings(&pattern_collection);
+        controller
+    }
+
+    /// Creates a new MouseController with the given settings and starts the internal thread
+    ///
+    /// Unlike `new`, this constructor does not read the configuration file.
+    ///
+    /// # Arguments
+    /// * `sensitivity` - Sensitivity settings for mouse movements
+    /// * `activation` - Condition under which the controller compensates recoil
+    ///
+    /// # Returns
+    /// A MouseController instance that can be used to update the movement pattern
+    pub fn with_settings(sensitivity: Sensitivity, activation: ActivationRule) -> Self {
+        Self::with_clock(sensitivity, activation, Arc::new(SystemClock))
+    }
+
+    /// Creates a new MouseController that takes its time from the given clock
+    ///
+    /// Passing a `ManualClock` makes the controller thread run on virtual time,
+    /// which allows timing behaviour to be observed without waiting in real time.
+    ///
+    /// # Arguments
+    /// * `sensitivity` - Sensitivity settings for mouse movements
+    /// * `activation` - Condition under which the controller compensates recoil
+    /// * `clock` - Clock used to advance steps and to wait between iterations
+    ///
+    /// # Returns
+    /// A MouseController instance that can be used to update the movement pattern
+    pub fn with_clock(
+        sensitivity: Sensitivity,
+        activation: ActivationRule,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        Self::with_input(
+            sensitivity,
+            activation,
+            clock,
+            Box::new(SystemKeyState),
+            Box::new(SendInputBackend),
+        )
+    }
+
+    /// Creates a new MouseController that reads keys from and sends movement to the given sources
+    ///
+    /// Together with a `ManualClock`, this runs the controller thread without
+    /// touching the system, so a whole spray can be replayed deterministically.
+    ///
+    /// # Arguments
+    /// * `sensitivity` - Sensitivity settings for mouse movements
+    /// * `activation` - Condition under which the controller compensates recoil
+    /// * `clock` - Clock used to advance steps and to wait between iterations
+    /// * `keys` - State of the keys read by the activation rule and modifiers
+    /// * `backend` - Destination of the movement
+    ///
+    /// # Returns
+    /// A MouseController instance that can be used to update the movement pattern
+    pub fn with_input(
+        sensitivity: Sensitivity,
+        activation: ActivationRule,
+        clock: Arc<dyn Clock>,
+        keys: Box<dyn KeyState + Send>,
+        backend: Box<dyn InputBackend + Send>,
+    ) -> Self {
         // Create a shared state with an empty pattern and sensitivity settings
-        let state = Arc::new(std::sync::RwLock::new(
-            MouseControllerState::with_pattern_and_sensitivity(vec![], sensitivity),
-        ));
+        let mut initial_state = MouseControllerState::with_pattern_and_sensitivity(vec![], sensitivity);
+        initial_state.activation = activation;
+        initial_state.refresh_snapshot();
+        let state = Arc::new(std::sync::RwLock::new(initial_state));
+        let generation = Arc::new(AtomicU64::new(0));
+        let running = Arc::new(AtomicBool::new(true));
+        let (sender, receiver) = mpsc::channel();
 
         // Start the controller thread
-        Self::start_controller_thread(Arc::clone(&state));
+        let thread = Self::start_controller_thread(
+            Arc::clone(&state),
+            Arc::clone(&generation),
+            Arc::clone(&running),
+            Arc::clone(&clock),
+            keys,
+            backend,
+            sender,
+        );
 
         // Return a new MouseController with the state and thread
-        Self { state }
+        Self {
+            state,
+            generation,
+            running,
+            clock,
+            thread: Some(thread),
+            transitions: Some(receiver),
+            status_changes: 0,
+        }
+    }
+
+    /// Stops the background thread and waits for it to finish
+    ///
+    /// The controller no longer applies any movements afterwards. Calling this
+    /// method more than once has no effect.
+    pub fn shutdown(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        self.clock.release();
+
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                error!("Mouse controller thread panicked");
+            } else {
+                info!("Mouse controller thread stopped");
+            }
+        }
     }
 
     /// Updates the pattern used by the mouse controller
@@ -67,40 +203,216 @@ impl MouseController {
     /// # Thread Safety
     /// This method acquires a write lock on the shared state, ensuring that
     /// the pattern is not being read by the background thread while it's being updated.
-    pub fn update_steps(&mut self, mut steps: Steps) {
+    pub fn update_steps(&mut self, steps: Steps) {
         if let Ok(mut state) = self.state.write() {
-            // Pre-calculate adjusted dx and dy values based on sensitivity
-            for step in &mut steps {
-                // Apply sensitivity to dx and dy values
-                // Higher sensitivity values make the movement smaller (divided by sensitivity)
-                step.adjusted_dx = if step.dx == 0 {
-                    0
-                } else if state.sensitivity.x > 1.0 {
-                    let calculated = (step.dx as f32 / state.sensitivity.x) as i32;
-                    if calculated == 0 {
-                        if step.dx > 0 { 1 } else { -1 }
-                    } else {
-                        calculated
-                    }
-                } else {
-                    step.dx
-                };
-
-                step.adjusted_dy = if step.dy == 0 {
-                    0
-                } else if state.sensitivity.y > 1.0 {
-                    let calculated = (step.dy as f32 / state.sensitivity.y) as i32;
-                    if calculated == 0 {
-                        if step.dy > 0 { 1 } else { -1 }
-                    } else {
-                        calculated
-                    }
-                } else {
-                    step.dy
-                };
+            state.clear_pattern();
+            state.steps = Some(steps);
+
+            // Pre-calculate adjusted dx and dy values based on sensitivity and transforms
+            state.recalculate_adjusted();
+            self.publish(&mut state);
+        } else {
+            error!("Failed to acquire write lock on mouse controller state");
+        }
+    }
+
+    /// Updates the fire-rate based pattern used by the mouse controller
+    ///
+    /// While a fire-rate pattern is set, the controller applies one offset per
+    /// bullet at the weapon's cadence instead of following duration-based steps.
+    ///
+    /// # Arguments
+    /// * `fire_rate` - The fire rate, magazine size and per-bullet offsets of the weapon
+    ///
+    /// # Thread Safety
+    /// This method acquires a write lock on the shared state, ensuring that
+    /// the pattern is not being read by the background thread while it's being updated.
+    pub fn update_fire_rate(&mut self, fire_rate: FireRate) {
+        if let Ok(mut state) = self.state.write() {
+            state.clear_pattern();
+            state.fire_rate = Some(fire_rate);
+
+            // Pre-calculate adjusted dx and dy values based on sensitivity and transforms
+            state.recalculate_adjusted();
+            self.publish(&mut state);
+        } else {
+            error!("Failed to acquire write lock on mouse controller state");
+        }
+    }
+
+    /// Updates the per-click pattern used by the mouse controller
+    ///
+    /// While a semi-automatic pattern is set, every press of the fire button
+    /// triggers one impulse instead of following steps while the button is held.
+    ///
+    /// # Arguments
+    /// * `semi_auto` - The per-shot impulses, pull duration and reset timeout of the weapon
+    ///
+    /// # Thread Safety
+    /// This method acquires a write lock on the shared state, ensuring that
+    /// the pattern is not being read by the background thread while it's being updated.
+    pub fn update_semi_auto(&mut self, semi_auto: SemiAuto) {
+        if let Ok(mut state) = self.state.write() {
+            state.clear_pattern();
+            state.semi_auto = Some(semi_auto);
+
+            // Pre-calculate adjusted dx and dy values based on sensitivity and transforms
+            state.recalculate_adjusted();
+            self.publish(&mut state);
+        } else {
+            error!("Failed to acquire write lock on mouse controller state");
+        }
+    }
+
+    /// Updates the controller with the movement defined by a pattern
+    ///
+    /// Semi-automatic patterns take precedence over fire-rate based ones, which
+    /// take precedence over duration-based steps. The pattern's own activation
+    /// rule, sensitivity and scopes replace the global ones.
+    ///
+    /// # Arguments
+    /// * `pattern` - The pattern to apply
+    ///
+    /// # Thread Safety
+    /// This method acquires a write lock on the shared state, ensuring that
+    /// the pattern is not being read by the background thread while it's being updated.
+    pub fn update_pattern(&mut self, pattern: Pattern) {
+        if let Ok(mut state) = self.state.write() {
+            state.set_pattern(pattern);
+
+            // Pre-calculate adjusted dx and dy values based on sensitivity and transforms
+            state.recalculate_adjusted();
+            self.publish(&mut state);
+        } else {
+            error!("Failed to acquire write lock on mouse controller state");
+        }
+    }
+
+    /// Returns the name of the selected scope
+    pub fn scope(&self) -> Option<String> {
+        match self.state.read() {
+            Ok(state) => state.scope.clone(),
+            Err(e) => {
+                error!("Failed to acquire read lock on mouse controller state: {}", e);
+                None
             }
+        }
+    }
 
-            state.steps = Some(steps);
+    /// Selects the scope whose multiplier is applied to the sensitivity
+    ///
+    /// The selection is kept when the pattern changes; patterns that don't
+    /// define the selected scope use their sensitivity unchanged.
+    ///
+    /// # Arguments
+    /// * `scope` - Name of the scope, or `None` for no scope
+    pub fn set_scope(&mut self, scope: Option<String>) {
+        if let Ok(mut state) = self.state.write() {
+            state.scope = scope;
+
+            // Recalculate adjusted values with the new scope multiplier
+            state.recalculate_adjusted();
+            self.publish(&mut state);
+
+            info!("Selected scope: {:?}", state.scope);
+        } else {
+            error!("Failed to acquire write lock on mouse controller state");
+        }
+    }
+
+    /// Selects the next scope of the current pattern in alphabetical order
+    ///
+    /// After the last scope, no scope is selected.
+    ///
+    /// # Returns
+    /// The name of the selected scope
+    pub fn cycle_scope(&mut self) -> Option<String> {
+        let next = match self.state.read() {
+            Ok(state) => {
+                let mut names: Vec<&String> = state.scopes.keys().collect();
+                names.sort();
+
+                match &state.scope {
+                    None => names.first().map(|name| (*name).clone()),
+                    Some(current) => names
+                        .iter()
+                        .position(|name| *name == current)
+                        .and_then(|index| names.get(index + 1))
+                        .map(|name| (*name).clone()),
+                }
+            }
+            Err(e) => {
+                error!("Failed to acquire read lock on mouse controller state: {}", e);
+                return None;
+            }
+        };
+
+        self.set_scope(next.clone());
+        next
+    }
+
+    /// Updates the global condition under which the controller compensates recoil
+    ///
+    /// The rule is ignored while the current pattern defines its own activation rule.
+    /// Toggles start off again whenever the effective rule changes.
+    ///
+    /// # Arguments
+    /// * `activation` - The activation rule to evaluate on every iteration
+    ///
+    /// # Thread Safety
+    /// This method acquires a write lock on the shared state, ensuring that
+    /// the rule is not being read by the background thread while it's being updated.
+    pub fn update_activation(&mut self, activation: ActivationRule) {
+        if let Ok(mut state) = self.state.write() {
+            state.activation = activation;
+            self.publish(&mut state);
+            info!("Updated mouse controller activation rule");
+        } else {
+            error!("Failed to acquire write lock on mouse controller state");
+        }
+    }
+
+    /// Replaces the global settings with those of a configuration
+    ///
+    /// The sensitivity, game settings, transforms, activation rule and
+    /// modifiers are updated at once; the current pattern and scope are kept.
+    ///
+    /// # Arguments
+    /// * `collection` - Configuration the settings are read from
+    ///
+    /// # Thread Safety
+    /// This method acquires a write lock on the shared state, ensuring that
+    /// the settings are not being read by the background thread while they're being updated.
+    pub fn update_settings(&mut self, collection: &PatternCollection) {
+        if let Ok(mut state) = self.state.write() {
+            state.apply_settings(collection);
+            self.publish(&mut state);
+            info!("Updated mouse controller settings from the configuration");
+        } else {
+            error!("Failed to acquire write lock on mouse controller state");
+        }
+    }
+
+    /// Updates the keys that change the compensation while held
+    ///
+    /// Modifiers are evaluated on every iteration of the controller thread, so
+    /// holding or releasing one takes effect without re-detecting the pattern.
+    ///
+    /// # Arguments
+    /// * `modifiers` - Modifiers in order of priority
+    ///
+    /// # Thread Safety
+    /// This method acquires a write lock on the shared state, ensuring that
+    /// the stances are not being read by the background thread while they're being updated.
+    pub fn update_modifiers(&mut self, modifiers: Vec<Modifier>) {
+        if let Ok(mut state) = self.state.write() {
+            state.modifiers = modifiers;
+
+            // Recalculate the adjusted values of every stance
+            state.recalculate_adjusted();
+
+            self.publish(&mut state);
+            info!("Updated mouse controller modifiers: {}", state.modifiers.len());
         } else {
             error!("Failed to acquire write lock on mouse controller state");
         }
@@ -116,13 +428,205 @@ impl MouseController {
     /// the pattern is not being read by the background thread while it's being updated.
     pub fn clear_steps(&mut self) {
         if let Ok(mut state) = self.state.write() {
-            state.steps = None;
+            state.clear_pattern();
+            self.publish(&mut state);
             info!("Cleared mouse controller pattern (set to None)");
         } else {
             error!("Failed to acquire write lock on mouse controller state");
         }
     }
 
+    /// Returns whether the controller is enabled and armed
+    pub fn status(&self) -> ControllerStatus {
+        match self.state.read() {
+            Ok(state) => state.status(),
+            Err(e) => {
+                error!("Failed to acquire read lock on mouse controller state: {}", e);
+                ControllerStatus {
+                    enabled: false,
+                    armed: false,
+                }
+            }
+        }
+    }
+
+    /// Enables or disables the controller
+    ///
+    /// Enabling the controller also re-arms pattern detection after the panic key was used.
+    ///
+    /// # Arguments
+    /// * `enabled` - Whether the controller should compensate recoil
+    ///
+    /// # Returns
+    /// The status of the controller after the change
+    pub fn set_enabled(&mut self, enabled: bool) -> ControllerStatus {
+        if let Ok(mut state) = self.state.write() {
+            state.enabled = enabled;
+            if enabled {
+                state.armed = true;
+            }
+            self.publish(&mut state);
+            self.status_changes += 1;
+            info!("Mouse controller {}", if enabled { "enabled" } else { "disabled" });
+        } else {
+            error!("Failed to acquire write lock on mouse controller state");
+        }
+
+        self.status()
+    }
+
+    /// Toggles the controller between enabled and disabled
+    ///
+    /// # Returns
+    /// The status of the controller after the change
+    pub fn toggle_enabled(&mut self) -> ControllerStatus {
+        let enabled = self.status().enabled;
+        self.set_enabled(!enabled)
+    }
+
+    /// Immediately stops all recoil compensation
+    ///
+    /// This method clears the steps, disables the controller and disarms pattern
+    /// detection. Detection stays ignored until the controller is enabled again.
+    ///
+    /// # Returns
+    /// The status of the controller after the change
+    pub fn panic(&mut self) -> ControllerStatus {
+        self.clear_steps();
+
+        if let Ok(mut state) = self.state.write() {
+            state.enabled = false;
+            state.armed = false;
+            self.publish(&mut state);
+            self.status_changes += 1;
+            info!("Panic key pressed, mouse controller disabled until re-armed");
+        } else {
+            error!("Failed to acquire write lock on mouse controller state");
+        }
+
+        self.status()
+    }
+
+    /// Disables the controller while a tool moves or reads the mouse
+    ///
+    /// Unlike `set_enabled`, the previous status is saved so `resume` can
+    /// restore it exactly, including whether detection was armed.
+    ///
+    /// # Returns
+    /// The saved status, to pass to `resume` once the tool is done
+    pub fn suspend(&mut self) -> SuspendedStatus {
+        let suspended = SuspendedStatus {
+            status: self.status(),
+            changes: self.status_changes,
+        };
+
+        if let Ok(mut state) = self.state.write() {
+            state.enabled = false;
+            self.publish(&mut state);
+            info!("Mouse controller suspended");
+        } else {
+            error!("Failed to acquire write lock on mouse controller state");
+        }
+
+        suspended
+    }
+
+    /// Restores the status saved by `suspend`
+    ///
+    /// When the status was changed while suspended, e.g. with the panic key,
+    /// the change is kept instead, so a panic is never overridden.
+    ///
+    /// # Arguments
+    /// * `suspended` - Status returned by `suspend`
+    ///
+    /// # Returns
+    /// The status of the controller after the change
+    pub fn resume(&mut self, suspended: SuspendedStatus) -> ControllerStatus {
+        if suspended.changes != self.status_changes {
+            info!("Mouse controller status changed while suspended, keeping it");
+        } else if let Ok(mut state) = self.state.write() {
+            state.enabled = suspended.status.enabled;
+            state.armed = suspended.status.armed;
+            self.publish(&mut state);
+            info!("Mouse controller resumed");
+        } else {
+            error!("Failed to acquire write lock on mouse controller state");
+        }
+
+        self.status()
+    }
+
+    /// Returns the game settings used to convert patterns to the current player's setup
+    pub fn game_sensitivity(&self) -> Option<GameSensitivity> {
+        match self.state.read() {
+            Ok(state) => state.game.clone(),
+            Err(e) => {
+                error!("Failed to acquire read lock on mouse controller state: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Updates the game settings used to convert patterns to the current player's setup
+    ///
+    /// # Arguments
+    /// * `game` - Reference and current game settings, or `None` to use the sensitivity as is
+    ///
+    /// # Thread Safety
+    /// This method acquires a write lock on the shared state, ensuring that
+    /// the adjusted values are not being read by the background thread while they're being updated.
+    pub fn update_game_sensitivity(&mut self, game: Option<GameSensitivity>) {
+        if let Ok(mut state) = self.state.write() {
+            state.game = game;
+
+            // Recalculate adjusted values for all steps and bullet offsets
+            state.recalculate_adjusted();
+
+            self.publish(&mut state);
+
+            info!(
+                "Updated game sensitivity, effective sensitivity is {:?}",
+                state.effective_sensitivity()
+            );
+        } else {
+            error!("Failed to acquire write lock on mouse controller state");
+        }
+    }
+
+    /// Returns the transforms applied to every movement after sensitivity
+    pub fn transform(&self) -> TransformSettings {
+        match self.state.read() {
+            Ok(state) => state.transform.clone(),
+            Err(e) => {
+                error!("Failed to acquire read lock on mouse controller state: {}", e);
+                TransformSettings::default()
+            }
+        }
+    }
+
+    /// Updates the transforms applied to every movement after sensitivity
+    ///
+    /// # Arguments
+    /// * `transform` - Strength, per-axis multipliers, inversion and per-tick clamps
+    ///
+    /// # Thread Safety
+    /// This method acquires a write lock on the shared state, ensuring that
+    /// the adjusted values are not being read by the background thread while they're being updated.
+    pub fn update_transform(&mut self, transform: TransformSettings) {
+        if let Ok(mut state) = self.state.write() {
+            state.transform = transform;
+
+            // Recalculate adjusted values for all steps and bullet offsets
+            state.recalculate_adjusted();
+
+            self.publish(&mut state);
+
+            info!("Updated mouse controller transforms: {:?}", state.transform);
+        } else {
+            error!("Failed to acquire write lock on mouse controller state");
+        }
+    }
+
     /// Updates the sensitivity settings for mouse movements
     ///
     /// # Arguments
@@ -141,38 +645,10 @@ impl MouseController {
             // Update the sensitivity in the state
             state.sensitivity = sensitivity;
 
-            // Recalculate adjusted values for all steps if steps exist
-            if let Some(steps) = &mut state.steps {
-                for step in steps {
-                    // Apply sensitivity to dx and dy values
-                    // Higher sensitivity values make the movement smaller (divided by sensitivity)
-                    step.adjusted_dx = if step.dx == 0 {
-                        0
-                    } else if sensitivity_x > 1.0 {
-                        let calculated = (step.dx as f32 / sensitivity_x) as i32;
-                        if calculated == 0 {
-                            if step.dx > 0 { 1 } else { -1 }
-                        } else {
-                            calculated
-                        }
-                    } else {
-                        step.dx
-                    };
-
-                    step.adjusted_dy = if step.dy == 0 {
-                        0
-                    } else if sensitivity_y > 1.0 {
-                        let calculated = (step.dy as f32 / sensitivity_y) as i32;
-                        if calculated == 0 {
-                            if step.dy > 0 { 1 } else { -1 }
-                        } else {
-                            calculated
-                        }
-                    } else {
-                        step.dy
-                    };
-                }
-            }
+            // Recalculate adjusted values for all steps and bullet offsets
+            state.recalculate_adjusted();
+
+            self.publish(&mut state);
 
             info!(
                 "Updated mouse controller sensitivity to x={}, y={} and recalculated adjusted values",
@@ -183,130 +659,176 @@ impl MouseController {
         }
     }
 
+    /// Simulates a pattern with the current settings of the controller
+    ///
+    /// The sensitivity, game settings, scope and transforms of the controller
+    /// are used, so the result matches what the controller would send for the pattern.
+    ///
+    /// # Arguments
+    /// * `pattern` - The pattern to simulate
+    /// * `tick` - Time between two iterations of the controller
+    /// * `hold` - How long the activation rule stays satisfied, at most `MAX_SIMULATED_HOLD`
+    ///
+    /// # Returns
+    /// The moves the controller would send, or an empty trajectory if the state can't be read
+    pub fn simulate(&self, pattern: Pattern, tick: Duration, hold: Duration) -> Trajectory {
+        match self.state.read() {
+            Ok(state) => {
+                let mut state = state.clone();
+                state.set_pattern(pattern);
+                simulate_state(state, tick, hold)
+            }
+            Err(e) => {
+                error!("Failed to acquire read lock on mouse controller state: {}", e);
+                Trajectory::default()
+            }
+        }
+    }
+
+    /// Computes the metrics of a pattern with the current settings of the controller
+    ///
+    /// # Arguments
+    /// * `pattern` - The pattern to analyze
+    /// * `tick` - Time between two iterations of the controller
+    ///
+    /// # Returns
+    /// The metrics of the pattern, or `None` if the state can't be read
+    pub fn analyze(&self, pattern: Pattern, tick: Duration) -> Option<PatternStats> {
+        match self.state.read() {
+            Ok(state) => {
+                let mut state = state.clone();
+                state.set_pattern(pattern);
+                Some(analyze_state(state, tick))
+            }
+            Err(e) => {
+                error!("Failed to acquire read lock on mouse controller state: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Publishes a new snapshot of the state for the background thread
+    ///
+    /// Must be called while holding the write lock, after every change of the state.
+    fn publish(&self, state: &mut MouseControllerState) {
+        state.refresh_snapshot();
+        self.generation.fetch_add(1, Ordering::Release);
+    }
+
+    /// Returns the receiver of the controller phase transitions
+    ///
+    /// Every phase change of the background thread is published on this channel.
+    /// The receiver can only be taken once; later calls return `None`.
+    pub fn take_transitions(&mut self) -> Option<Receiver<ControllerTransition>> {
+        self.transitions.take()
+    }
+
     /// Starts a background thread that continuously monitors the state and applies
     /// mouse movements according to the configured pattern when conditions are met.
     ///
     /// This method is called automatically when a new MouseController is created.
-    /// The thread runs indefinitely until the program terminates.
+    /// The thread runs until the `running` flag is cleared by `shutdown`.
     ///
     /// # Implementation Details
     /// The thread performs the following operations in a loop:
-    /// 1. Reads the current pattern and enabled state from the shared state
-    /// 2. Checks if the pattern is empty and skips the iteration if it is
-    /// 3. Determines if the controller should be running based on mouse button states
-    /// 4. Applies mouse movements according to the current step in the pattern
-    /// 5. Advances to the next step when the current step's duration has elapsed
+    /// 1. Re-reads the state snapshot if its generation has changed
+    /// 2. Determines if the controller should be running by evaluating the activation rule
+    /// 3. Advances the `StateMachine`, which selects the current step of the pattern
+    /// 4. Publishes the phase transition, if any, on the transitions channel
+    /// 5. Sends the mouse movement returned by the state machine to the backend
     ///
     /// # Performance Considerations
-    /// - The thread minimizes lock duration by only acquiring the read lock briefly
-    /// - It uses caching to avoid repeated pattern access and cloning
-    /// - State changes are logged only when they occur to reduce logging overhead
+    /// - The read lock is only acquired when the generation counter has changed
+    /// - The snapshot is shared through an `Arc`, so no heap allocation happens per iteration
+    /// - Phase changes are logged and published only when they occur
     ///
     /// # Arguments
     /// * `state` - Shared state that can be accessed by both the controller and the thread
-    fn start_controller_thread(state: Arc<std::sync::RwLock<MouseControllerState>>) {
+    /// * `generation` - Counter that is incremented whenever a new snapshot is published
+    /// * `running` - Flag that keeps the thread running while set
+    /// * `clock` - Clock used to advance steps and to wait between iterations
+    /// * `keys` - State of the keys read by the activation rule and modifiers
+    /// * `backend` - Destination of the movement
+    /// * `transitions` - Channel on which phase transitions are published
+    ///
+    /// # Returns
+    /// The handle of the spawned thread
+    fn start_controller_thread(
+        state: Arc<std::sync::RwLock<MouseControllerState>>,
+        generation: Arc<AtomicU64>,
+        running: Arc<AtomicBool>,
+        clock: Arc<dyn Clock>,
+        keys: Box<dyn KeyState + Send>,
+        mut backend: Box<dyn InputBackend + Send>,
+        transitions: Sender<ControllerTransition>,
+    ) -> JoinHandle<()> {
         info!("Starting mouse controller thread");
 
         thread::spawn(move || {
-            // Variables to track the pattern progress
-            let mut current_step_index = 0;
-            let mut last_step_time = Instant::now();
-            let mut is_running = false;
+            // State machine that tracks the pattern progress
+            let mut machine = StateMachine::new(clock.now());
+
+            // Rule engine that tracks toggle keys between iterations
+            let mut activation = ActivationEngine::new();
+
+            // Latest snapshot of the state and the generation it was read at
+            let mut snapshot = Arc::new(ControllerSnapshot::default());
+            let mut snapshot_generation = None;
 
-            // Cache for the current step to avoid repeated array access
-            let mut current_step: Option<Step> = None;
+            let is_pressed = |key_code| keys.is_pressed(key_code);
 
-            loop {
-                // Get only what we need from the state, minimizing lock duration
-                let (pattern, enabled, pattern_is_empty) = {
+            while running.load(Ordering::SeqCst) {
+                // Only acquire the lock when a new snapshot has been published
+                let current_generation = generation.load(Ordering::Acquire);
+                if snapshot_generation != Some(current_generation) {
                     match state.read() {
                         Ok(guard) => {
-                            // Check if steps is None or empty
-                            let is_empty = guard.steps.as_ref().map_or(true, |s| s.is_empty());
-
-                            // If pattern is None or empty, don't clone anything
-                            if is_empty {
-                                (vec![], guard.enabled, true)
-                            } else {
-                                // Only clone the pattern, not the entire state
-                                (guard.steps.as_ref().unwrap().clone(), guard.enabled, false)
+                            // Toggles of the previous rules do not apply to new ones
+                            if !guard.snapshot.has_same_rules(&snapshot) {
+                                activation.reset();
                             }
+                            snapshot = Arc::clone(&guard.snapshot);
+                            snapshot_generation = Some(current_generation);
                         }
                         Err(e) => {
                             error!("Failed to acquire read lock on state: {}", e);
-                            thread::sleep(Duration::from_millis(DEFAULT_THREAD_SLEEP_DURATION_MS));
+                            clock.sleep(Duration::from_millis(DEFAULT_THREAD_SLEEP_DURATION_MS));
                             continue;
                         }
-                    }
-                }; // Lock is released here
-
-                // Check if the pattern is empty
-                if pattern_is_empty {
-                    debug!("Pattern is empty, skipping iteration");
-                    thread::sleep(Duration::from_millis(DEFAULT_THREAD_SLEEP_DURATION_MS));
-                    continue;
+                    } // Lock is released here
                 }
 
-                // Check if the controller is enabled and should be running
-                let should_be_running = enabled && should_run();
-
-                // Only log state changes to reduce logging overhead
-                if should_be_running != is_running {
-                    if should_be_running {
-                        debug!("Mouse controller activated");
-                        // Always reset to step 1 when the controller is activated
-                        current_step_index = 0;
-                        last_step_time = Instant::now();
-                        // Update the cached step
-                        current_step = Some(pattern[0].clone());
-                        debug!("Mouse buttons pressed, resetting to step 1");
-                    } else {
-                        debug!("Mouse controller deactivated");
-                    }
-                    is_running = should_be_running;
-                }
+                // Evaluate the activation rule on every iteration to keep toggles up to date
+                let active = activation.evaluate(&snapshot.activation, &is_pressed);
 
-                if is_running {
-                    // Check if the current step index is out of bounds of the pattern
-                    // This can happen if the pattern was updated while the controller was running
-                    if current_step_index >= pattern.len() {
-                        debug!("Current step index out of bounds, resetting to step 0");
-                        current_step_index = 0;
-                        last_step_time = Instant::now();
-                        // Update the cached step
-                        current_step = Some(pattern[0].clone());
-                    }
+                // Evaluate every modifier as well; the first active one selects its stance
+                let stance = snapshot.select_stance(&mut activation, &is_pressed);
 
-                    // Use the cached step if available, otherwise update it
-                    if current_step.is_none() {
-                        current_step = Some(pattern[current_step_index].clone());
-                    }
+                let output = snapshot.tick(&mut machine, clock.now(), active, stance);
 
-                    // Get the current step (safe because we've checked bounds and emptiness)
-                    if let Some(step) = &current_step {
-                        // Use pre-calculated adjusted values
-                        // Send mouse input based on the adjusted values
-
-                        if let Err(e) = send_mouse_input(step.adjusted_dx, step.adjusted_dy) {
-                            error!("Failed to send mouse input: {}", e);
-                        }
+                // Publish phase changes; nobody may be listening, so send errors are ignored
+                if let Some(transition) = output.transition {
+                    debug!(
+                        "Mouse controller transition: {:?} -> {:?}",
+                        transition.from, transition.to
+                    );
+                    let _ = transitions.send(transition);
+                }
 
-                        // Check if it's time to move to the next step
-                        if last_step_time.elapsed() >= Duration::from_millis(step.duration) {
-                            // If we're not at the last step, move to the next step
-                            if current_step_index < pattern.len() - 1 {
-                                current_step_index += 1;
-                                // Update the cached step
-                                current_step = Some(pattern[current_step_index].clone());
-                            }
-                            // Otherwise, stay at the last step (don't loop back to the beginning)
-                            last_step_time = Instant::now();
-                        }
+                if output.dx != 0 || output.dy != 0 {
+                    if let Err(e) = backend.send_move(output.dx, output.dy) {
+                        error!("Failed to send mouse input: {}", e);
                     }
                 }
 
-                thread::sleep(Duration::from_millis(DEFAULT_THREAD_SLEEP_DURATION_MS));
+                clock.sleep(Duration::from_millis(DEFAULT_THREAD_SLEEP_DURATION_MS));
             }
-        });
+        })
+    }
+}
+
+impl Drop for MouseController {
+    fn drop(&mut self) {
+        self.shutdown();
     }
 }
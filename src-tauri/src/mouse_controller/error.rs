@@ -5,12 +5,17 @@ use std::fmt;
 pub enum MouseInputError {
     /// Error when sending input to the system
     SendInputFailed,
+    /// Sending input is not supported on this platform
+    Unsupported,
 }
 
 impl fmt::Display for MouseInputError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             MouseInputError::SendInputFailed => write!(f, "Failed to send mouse input"),
+            MouseInputError::Unsupported => {
+                write!(f, "Mouse input is not supported on this platform")
+            }
         }
     }
 }
@@ -7,7 +7,14 @@
 ///
 /// The module is organized into several components:
 /// - `controller`: Contains the main `MouseController` that manages mouse movements
+/// - `backend`: Destination of the mouse movement and source of key states, with the system implementations
+/// - `analysis`: Metrics of a pattern, used to spot mis-tuned patterns
+/// - `activation`: Rule engine that decides when the controller should be running
+/// - `clock`: Time source of the controller thread, with a virtual implementation
+/// - `simulation`: Dry run of a pattern that returns every movement the controller would send
 /// - `state`: Defines the state structure used by the controller
+/// - `state_machine`: Explicit state machine that drives the controller thread
+/// - `transform`: Pipeline that turns raw step values into the pixels that are sent
 /// - `step`: Defines the structure for individual movement steps and patterns
 /// - `error`: Contains error types for mouse input operations
 /// - `utils`: Utility functions for mouse input and state detection
@@ -16,16 +23,47 @@
 ///
 /// The main entry point is the `MouseController` struct, which can be used to
 /// create a controller and update its movement pattern.
+mod activation;
+mod analysis;
+mod backend;
+mod clock;
 mod controller;
 mod error;
+mod simulation;
 mod state;
+mod state_machine;
+mod transform;
 mod utils;
 
 /// Public exports from the mouse_controller module
 ///
 /// These are the primary types that users of this module will interact with:
 /// - `MouseController`: The main controller for programmatic mouse movements
+/// - `ActivationEngine`: Evaluates activation rules against key states
+/// - `InputBackend`, `SendInputBackend`: Destination of relative mouse movement
+/// - `KeyState`, `SystemKeyState`: Source of the key states read by the controller
+/// - `MouseInputError`: Errors of sending mouse input
+/// - `is_key_pressed`: Reads the state of a key or mouse button from the system
+/// - `Clock`, `SystemClock`, `ManualClock`: Real and virtual time sources for the controller
+/// - `ControllerStatus`: Whether the controller is enabled and armed
+/// - `SuspendedStatus`: Status saved while a tool suspends the controller
+/// - `ControllerPhase`, `ControllerTransition`: Observable phases of the controller thread
+/// - `TransformPipeline`, `TransformStage`: Movement transforms applied after sensitivity
+/// - `simulate`, `Trajectory`, `TrajectoryMove`: Dry run of a pattern without sending input
+/// - `MAX_SIMULATED_HOLD`: Longest activation a dry run simulates
+/// - `analyze`, `PatternStats`: Metrics of a pattern
+/// - `SCRIPT_WINDOW`: Time over which scripts without steps are measured
 /// - `Pattern`: A sequence of mouse movement steps
 /// - `Step`: A single mouse movement step with direction and duration
-pub use controller::MouseController;
+pub use activation::ActivationEngine;
+pub use analysis::{analyze, PatternStats, SCRIPT_WINDOW};
+pub use backend::{InputBackend, KeyState, SendInputBackend, SystemKeyState};
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use controller::{MouseController, SuspendedStatus, DEFAULT_THREAD_SLEEP_DURATION_MS};
+pub use error::MouseInputError;
+pub use simulation::{simulate, Trajectory, TrajectoryMove, MAX_SIMULATED_HOLD};
+pub use state::ControllerStatus;
+pub use state_machine::{ControllerPhase, ControllerTransition};
+pub use transform::{TransformPipeline, TransformStage};
+pub use utils::is_key_pressed;
 
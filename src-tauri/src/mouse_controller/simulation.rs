@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::mouse_controller::clock::{Clock, ManualClock};
+use crate::mouse_controller::state::MouseControllerState;
+use crate::mouse_controller::state_machine::{ControllerPhase, StateMachine};
+use crate::patterns::{Pattern, PatternCollection};
+
+/// Longest time the activation rule may stay satisfied in a simulation
+///
+/// Longer holds are shortened to this, so a simulation always ends.
+pub const MAX_SIMULATED_HOLD: Duration = Duration::from_secs(60);
+
+/// Single movement sent by the controller during a simulation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TrajectoryMove {
+    /// Time since activation in milliseconds
+    pub time_ms: u64,
+    /// Index of the step (or bullet or shot) that produced the movement
+    pub step: usize,
+    /// Horizontal movement in pixels
+    pub dx: i32,
+    /// Vertical movement in pixels
+    pub dy: i32,
+    /// Horizontal position after the movement, relative to the start
+    pub x: i32,
+    /// Vertical position after the movement, relative to the start
+    pub y: i32,
+}
+
+/// Every movement the controller would send for a pattern
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Trajectory {
+    /// Movements in the order they are sent
+    pub moves: Vec<TrajectoryMove>,
+    /// Horizontal position after the last movement
+    pub x: i32,
+    /// Vertical position after the last movement
+    pub y: i32,
+}
+
+/// Simulates a pattern without sending any input
+///
+/// The controller is driven by a virtual clock with exactly the same code as
+/// the live loop, and with the sensitivity, game settings and transforms of the
+/// configuration, so the result matches what the controller would send.
+///
+/// # Arguments
+/// * `pattern` - The pattern to simulate
+/// * `collection` - Configuration whose settings are applied to the pattern
+/// * `tick` - Time between two iterations of the controller
+/// * `hold` - How long the activation rule stays satisfied, at most `MAX_SIMULATED_HOLD`
+///
+/// # Returns
+/// The timestamped movements together with the cumulative position
+pub fn simulate(
+    pattern: &Pattern,
+    collection: &PatternCollection,
+    tick: Duration,
+    hold: Duration,
+) -> Trajectory {
+    let mut state = MouseControllerState::from_collection(collection);
+    state.set_pattern(pattern.clone());
+
+    simulate_state(state, tick, hold)
+}
+
+/// Simulates the pattern of a controller state without sending any input
+///
+/// The activation rule is considered satisfied for `hold`, but no longer than
+/// `MAX_SIMULATED_HOLD`, and no modifier is active. Semi-automatic impulses
+/// that are still being applied when the activation ends are simulated until
+/// they are complete.
+pub(crate) fn simulate_state(
+    mut state: MouseControllerState,
+    tick: Duration,
+    hold: Duration,
+) -> Trajectory {
+    state.enabled = true;
+    state.recalculate_adjusted();
+    state.refresh_snapshot();
+
+    // A zero tick would never advance the virtual time
+    let tick = tick.max(Duration::from_millis(1));
+    let hold = hold.min(MAX_SIMULATED_HOLD);
+    let clock = ManualClock::new();
+    let mut machine = StateMachine::new(clock.now());
+    let mut trajectory = Trajectory::default();
+    let mut step = 0;
+
+    loop {
+        let elapsed = clock.elapsed();
+        let active = elapsed < hold;
+        let output = state.snapshot.tick(&mut machine, clock.now(), active, None);
+
+        // Movements sent while finishing keep the index of the last step
+        if let ControllerPhase::Compensating { step: current } = machine.phase() {
+            step = current;
+        }
+
+        if output.dx != 0 || output.dy != 0 {
+            trajectory.x += output.dx;
+            trajectory.y += output.dy;
+            trajectory.moves.push(TrajectoryMove {
+                time_ms: elapsed.as_millis() as u64,
+                step,
+                dx: output.dx,
+                dy: output.dy,
+                x: trajectory.x,
+                y: trajectory.y,
+            });
+        }
+
+        if !active && !matches!(machine.phase(), ControllerPhase::Compensating { .. }) {
+            break;
+        }
+
+        clock.advance(tick);
+    }
+
+    trajectory
+}
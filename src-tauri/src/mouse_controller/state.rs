@@ -1,32 +1,502 @@
-use crate::patterns::{Sensitivity, Steps};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use log::error;
+use serde::Serialize;
+
+use crate::mouse_controller::activation::ActivationEngine;
+use crate::mouse_controller::state_machine::{StateMachine, TickInput, TickOutput};
+use crate::mouse_controller::transform::TransformPipeline;
+use crate::patterns::{
+    ActivationRule, FireRate, GameSensitivity, GameSettings, Modifier, Pattern,
+    PatternCollection, SemiAuto, Sensitivity, Steps, TransformSettings,
+};
+use crate::scripting::PatternScript;
+
+/// Snapshot of whether the controller is live, reported to the frontend
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ControllerStatus {
+    /// Whether the controller is enabled
+    pub enabled: bool,
+    /// Whether pattern detection is allowed
+    pub armed: bool,
+}
+
+/// Pattern applied while a modifier is active, with pre-calculated adjusted values
+#[derive(Debug, Clone)]
+pub struct Stance {
+    /// Name of the modifier, passed to the pattern script
+    pub name: String,
+    /// Condition under which the modifier is active
+    pub when: ActivationRule,
+    /// Duration-based steps of the stance
+    pub steps: Steps,
+    /// Fire-rate based pattern of the stance, used instead of `steps` when set
+    pub fire_rate: Option<FireRate>,
+    /// Per-click pattern of the stance, used instead of `fire_rate` and `steps` when set
+    pub semi_auto: Option<SemiAuto>,
+    /// Transforms of the stance, applied to the movement returned by the pattern script
+    pub pipeline: TransformPipeline,
+}
+
+/// Immutable view of everything the controller thread needs for an iteration
+///
+/// A new snapshot is published whenever the state changes, so the thread can
+/// keep using the same snapshot without locking or cloning between changes.
+#[derive(Debug, Clone, Default)]
+pub struct ControllerSnapshot {
+    /// Duration-based steps with pre-calculated adjusted values
+    pub steps: Steps,
+    /// Fire-rate based pattern, used instead of `steps` when set
+    pub fire_rate: Option<FireRate>,
+    /// Per-click pattern, used instead of `fire_rate` and `steps` when set
+    pub semi_auto: Option<SemiAuto>,
+    /// Compiled script that computes the movement of `steps`, if any
+    pub script: Option<Arc<PatternScript>>,
+    /// Transforms applied to the movement returned by the script, whose clamps
+    /// limit the movement of every iteration
+    pub pipeline: TransformPipeline,
+    /// Whether the controller is enabled
+    pub enabled: bool,
+    /// Effective activation rule (the pattern's own rule or the global one)
+    pub activation: ActivationRule,
+    /// Time between activation and the start of the compensation
+    pub start_delay: Duration,
+    /// Time over which the compensation is scaled up to full strength
+    pub ramp_in: Duration,
+    /// Patterns applied while a modifier is active, in order of priority
+    pub stances: Vec<Stance>,
+}
+
+impl ControllerSnapshot {
+    /// Returns whether the activation rule and the modifier rules equal those of another snapshot
+    ///
+    /// # Arguments
+    /// * `other` - The snapshot to compare with
+    pub fn has_same_rules(&self, other: &ControllerSnapshot) -> bool {
+        self.activation == other.activation
+            && self.stances.len() == other.stances.len()
+            && self
+                .stances
+                .iter()
+                .zip(&other.stances)
+                .all(|(stance, other)| stance.when == other.when)
+    }
+
+    /// Returns the stance of the first active modifier
+    ///
+    /// Every modifier is evaluated, so toggle keys are tracked even while a
+    /// modifier with a higher priority is active.
+    ///
+    /// # Arguments
+    /// * `activation` - Rule engine that tracks toggle keys between iterations
+    /// * `is_pressed` - Returns whether the key with the given virtual key code is held down
+    pub fn select_stance(
+        &self,
+        activation: &mut ActivationEngine,
+        is_pressed: &impl Fn(i32) -> bool,
+    ) -> Option<&Stance> {
+        let mut stance = None;
+        for candidate in &self.stances {
+            if activation.evaluate(&candidate.when, is_pressed) && stance.is_none() {
+                stance = Some(candidate);
+            }
+        }
+        stance
+    }
+
+    /// Advances the state machine by one iteration with the pattern of this snapshot
+    ///
+    /// This is the single place where a snapshot is turned into movement, shared
+    /// by the controller thread and the simulation so the two cannot diverge.
+    ///
+    /// # Arguments
+    /// * `machine` - State machine that tracks the pattern progress
+    /// * `now` - The current time
+    /// * `active` - Whether the activation rule is satisfied
+    /// * `stance` - Stance of the active modifier, or `None` for the pattern itself
+    ///
+    /// # Returns
+    /// The movement to send and the phase transition, if one happened
+    pub fn tick(
+        &self,
+        machine: &mut StateMachine,
+        now: Instant,
+        active: bool,
+        stance: Option<&Stance>,
+    ) -> TickOutput {
+        let (steps, fire_rate, semi_auto, pipeline, modifier) = match stance {
+            Some(stance) => (
+                &stance.steps,
+                stance.fire_rate.as_ref(),
+                stance.semi_auto.as_ref(),
+                &stance.pipeline,
+                Some(stance.name.as_str()),
+            ),
+            None => (
+                &self.steps,
+                self.fire_rate.as_ref(),
+                self.semi_auto.as_ref(),
+                &self.pipeline,
+                None,
+            ),
+        };
+
+        machine.tick(
+            now,
+            TickInput {
+                enabled: self.enabled,
+                active,
+                steps,
+                fire_rate,
+                semi_auto,
+                start_delay: self.start_delay,
+                ramp_in: self.ramp_in,
+                script: self.script.as_deref(),
+                pipeline,
+                modifier,
+            },
+        )
+    }
+}
 
 /// Shared state for the mouse controller that can be updated at runtime
 #[derive(Debug, Clone)]
 pub struct MouseControllerState {
     /// The current pattern of mouse movements
     pub steps: Option<Steps>,
+    /// The current fire-rate based pattern, used instead of `steps` when set
+    pub fire_rate: Option<FireRate>,
+    /// The current per-click pattern, used instead of `fire_rate` and `steps` when set
+    pub semi_auto: Option<SemiAuto>,
+    /// Compiled script of the current pattern, dropped when it does not compile
+    pub script: Option<Arc<PatternScript>>,
     /// Whether the controller is enabled
     pub enabled: bool,
+    /// Whether pattern detection is allowed; cleared by the panic key until re-armed
+    pub armed: bool,
     /// Sensitivity settings for mouse movements
     pub sensitivity: Sensitivity,
+    /// Game settings that scale the sensitivity to the current player's setup
+    pub game: Option<GameSensitivity>,
+    /// Transforms applied to every movement after sensitivity
+    pub transform: TransformSettings,
+    /// Global condition under which the controller compensates recoil
+    pub activation: ActivationRule,
+    /// Keys that change the compensation while held, in order of priority
+    pub modifiers: Vec<Modifier>,
+    /// Patterns of the current pattern for each modifier, derived from the modifiers and variants
+    pub stances: Vec<Stance>,
+    /// Alternative steps of the current pattern by modifier name
+    pub variants: HashMap<String, Steps>,
+    /// Activation rule of the current pattern, overriding the global one when set
+    pub pattern_activation: Option<ActivationRule>,
+    /// Sensitivity of the current pattern, overriding the global one when set
+    pub pattern_sensitivity: Option<Sensitivity>,
+    /// Game settings the current pattern was tuned at, overriding the reference when set
+    pub pattern_baseline: Option<GameSettings>,
+    /// Start delay of the current pattern
+    pub start_delay: Duration,
+    /// Ramp-in time of the current pattern
+    pub ramp_in: Duration,
+    /// Named scope multipliers of the current pattern
+    pub scopes: HashMap<String, f32>,
+    /// Name of the selected scope, kept when the pattern changes
+    pub scope: Option<String>,
+    /// Latest snapshot published for the controller thread
+    pub snapshot: Arc<ControllerSnapshot>,
 }
 
 impl MouseControllerState {
+    /// Returns whether the controller is enabled and armed
+    pub fn status(&self) -> ControllerStatus {
+        ControllerStatus {
+            enabled: self.enabled,
+            armed: self.armed,
+        }
+    }
+
+    /// Removes the current pattern together with its overrides
+    pub fn clear_pattern(&mut self) {
+        self.steps = None;
+        self.fire_rate = None;
+        self.semi_auto = None;
+        self.script = None;
+        self.pattern_activation = None;
+        self.pattern_sensitivity = None;
+        self.pattern_baseline = None;
+        self.start_delay = Duration::ZERO;
+        self.ramp_in = Duration::ZERO;
+        self.scopes.clear();
+        self.variants.clear();
+        self.stances.clear();
+    }
+
+    /// Replaces the current pattern together with its overrides
+    ///
+    /// Semi-automatic patterns take precedence over fire-rate based ones, which
+    /// take precedence over duration-based steps. A script that does not
+    /// compile is dropped, so the pattern runs with its steps.
+    pub fn set_pattern(&mut self, pattern: Pattern) {
+        self.clear_pattern();
+
+        if let Some(script) = &pattern.script {
+            match PatternScript::compile(script) {
+                Ok(script) => self.script = Some(Arc::new(script)),
+                Err(e) => error!("Ignoring the pattern script: {}", e),
+            }
+        }
+
+        match (pattern.semi_auto, pattern.fire_rate) {
+            (Some(semi_auto), _) => self.semi_auto = Some(semi_auto),
+            (None, Some(fire_rate)) => self.fire_rate = Some(fire_rate),
+            (None, None) => self.steps = Some(pattern.config),
+        }
+        self.pattern_activation = pattern.activation;
+        self.pattern_sensitivity = pattern.sensitivity;
+        self.pattern_baseline = pattern.baseline;
+        self.start_delay = Duration::from_millis(pattern.start_delay_ms);
+        self.ramp_in = Duration::from_millis(pattern.ramp_in_ms);
+        self.scopes = pattern.scopes;
+        self.variants = pattern.variants;
+    }
+
+    /// Returns the sensitivity used for the current pattern and scope
+    ///
+    /// The pattern's own sensitivity replaces the global one, the multiplier of
+    /// the selected scope is applied on top when the pattern defines it, and the
+    /// result is converted from the pattern's baseline (or the reference) to the
+    /// current game settings.
+    ///
+    /// Without game settings the result is only divided by when it is above 1.0.
+    /// With game settings, values of 1.0 and below are replaced by 1.0 before
+    /// the conversion, so only the game model can enlarge the movement.
+    pub fn effective_sensitivity(&self) -> Sensitivity {
+        let base = self.pattern_sensitivity.as_ref().unwrap_or(&self.sensitivity);
+        let scope_multiplier = self
+            .scope
+            .as_ref()
+            .and_then(|scope| self.scopes.get(scope))
+            .copied()
+            .unwrap_or(1.0);
+        let x = base.x * scope_multiplier;
+        let y = base.y * scope_multiplier;
+
+        match &self.game {
+            Some(game) => {
+                let game_scale = game.divisor_scale(self.pattern_baseline.as_ref());
+                Sensitivity {
+                    x: x.max(1.0) * game_scale,
+                    y: y.max(1.0) * game_scale,
+                }
+            }
+            None => Sensitivity { x, y },
+        }
+    }
+
+    /// Creates the pipeline for the given sensitivity and transforms
+    ///
+    /// A sensitivity converted by the game model may enlarge the movement,
+    /// the configured one only reduces it.
+    fn pipeline(
+        &self,
+        sensitivity: &Sensitivity,
+        transform: &TransformSettings,
+    ) -> TransformPipeline {
+        if self.game.is_some() {
+            TransformPipeline::for_game(sensitivity, transform)
+        } else {
+            TransformPipeline::new(sensitivity, transform)
+        }
+    }
+
+    /// Recalculates the adjusted values of all steps, bullet offsets and stances
+    ///
+    /// Must be called after the pattern, the sensitivity, the scope, the
+    /// transforms or the modifiers change.
+    pub fn recalculate_adjusted(&mut self) {
+        let sensitivity = self.effective_sensitivity();
+        let pipeline = self.pipeline(&sensitivity, &self.transform);
+
+        if let Some(steps) = &mut self.steps {
+            adjust_steps(steps, &pipeline);
+        }
+
+        if let Some(fire_rate) = &mut self.fire_rate {
+            adjust_fire_rate(fire_rate, &pipeline);
+        }
+
+        if let Some(semi_auto) = &mut self.semi_auto {
+            adjust_semi_auto(semi_auto, &pipeline);
+        }
+
+        // Stances only exist while a pattern is loaded
+        self.stances.clear();
+        if self.steps.is_none() && self.fire_rate.is_none() && self.semi_auto.is_none() {
+            return;
+        }
+
+        for modifier in &self.modifiers {
+            // The multiplier scales the movement together with the global strength
+            let transform = TransformSettings {
+                strength: self.transform.strength * modifier.multiplier,
+                ..self.transform.clone()
+            };
+            let pipeline = self.pipeline(&sensitivity, &transform);
+
+            let mut stance = match self.variants.get(&modifier.name) {
+                Some(variant) => Stance {
+                    name: modifier.name.clone(),
+                    when: modifier.when.clone(),
+                    steps: variant.clone(),
+                    fire_rate: None,
+                    semi_auto: None,
+                    pipeline,
+                },
+                None => Stance {
+                    name: modifier.name.clone(),
+                    when: modifier.when.clone(),
+                    steps: self.steps.clone().unwrap_or_default(),
+                    fire_rate: self.fire_rate.clone(),
+                    semi_auto: self.semi_auto.clone(),
+                    pipeline,
+                },
+            };
+
+            adjust_steps(&mut stance.steps, &stance.pipeline);
+            if let Some(fire_rate) = &mut stance.fire_rate {
+                adjust_fire_rate(fire_rate, &stance.pipeline);
+            }
+            if let Some(semi_auto) = &mut stance.semi_auto {
+                adjust_semi_auto(semi_auto, &stance.pipeline);
+            }
+
+            self.stances.push(stance);
+        }
+    }
+
+    /// Rebuilds the snapshot read by the controller thread from the current state
+    pub fn refresh_snapshot(&mut self) {
+        self.snapshot = Arc::new(ControllerSnapshot {
+            steps: self.steps.clone().unwrap_or_default(),
+            fire_rate: self.fire_rate.clone(),
+            semi_auto: self.semi_auto.clone(),
+            script: self.script.clone(),
+            pipeline: self.pipeline(&self.effective_sensitivity(), &self.transform),
+            enabled: self.enabled,
+            activation: self
+                .pattern_activation
+                .clone()
+                .unwrap_or_else(|| self.activation.clone()),
+            start_delay: self.start_delay,
+            ramp_in: self.ramp_in,
+            stances: self.stances.clone(),
+        });
+    }
+
+    /// Replaces the global settings with those of a configuration
+    ///
+    /// The sensitivity, game settings, transforms, activation rule and
+    /// modifiers are replaced and the adjusted values recalculated; the
+    /// current pattern and scope are kept.
+    ///
+    /// # Arguments
+    /// * `collection` - Configuration the settings are read from
+    pub fn apply_settings(&mut self, collection: &PatternCollection) {
+        self.sensitivity = collection.sensitivity.clone();
+        self.game = collection.game.clone();
+        self.transform = collection.transform.clone();
+        self.activation = collection.activation.clone();
+        self.modifiers = collection.modifiers.clone();
+        self.recalculate_adjusted();
+    }
+
+    /// Creates a new state without a pattern from the settings of a configuration
+    pub fn from_collection(collection: &PatternCollection) -> Self {
+        let mut state =
+            Self::with_pattern_and_sensitivity(Vec::new(), collection.sensitivity.clone());
+        state.apply_settings(collection);
+        state.refresh_snapshot();
+        state
+    }
+
     /// Creates a new state with the given pattern
     pub fn with_pattern(pattern: Steps) -> Self {
-        Self {
+        let mut state = Self {
             steps: Some(pattern),
+            fire_rate: None,
+            semi_auto: None,
+            script: None,
             enabled: true,
+            armed: true,
             sensitivity: Sensitivity { x: 1.0, y: 1.0 },
-        }
+            game: None,
+            transform: TransformSettings::default(),
+            activation: ActivationRule::default(),
+            modifiers: Vec::new(),
+            stances: Vec::new(),
+            variants: HashMap::new(),
+            pattern_activation: None,
+            pattern_sensitivity: None,
+            pattern_baseline: None,
+            start_delay: Duration::ZERO,
+            ramp_in: Duration::ZERO,
+            scopes: HashMap::new(),
+            scope: None,
+            snapshot: Arc::default(),
+        };
+        state.refresh_snapshot();
+        state
     }
 
     /// Creates a new state with the given pattern and sensitivity
     pub fn with_pattern_and_sensitivity(pattern: Steps, sensitivity: Sensitivity) -> Self {
-        Self {
+        let mut state = Self {
             steps: Some(pattern),
+            fire_rate: None,
+            semi_auto: None,
+            script: None,
             enabled: true,
+            armed: true,
             sensitivity,
-        }
+            game: None,
+            transform: TransformSettings::default(),
+            activation: ActivationRule::default(),
+            modifiers: Vec::new(),
+            stances: Vec::new(),
+            variants: HashMap::new(),
+            pattern_activation: None,
+            pattern_sensitivity: None,
+            pattern_baseline: None,
+            start_delay: Duration::ZERO,
+            ramp_in: Duration::ZERO,
+            scopes: HashMap::new(),
+            scope: None,
+            snapshot: Arc::default(),
+        };
+        state.refresh_snapshot();
+        state
+    }
+}
+
+/// Pre-calculates the adjusted values of duration-based steps
+fn adjust_steps(steps: &mut Steps, pipeline: &TransformPipeline) {
+    for step in steps {
+        (step.adjusted_dx, step.adjusted_dy) = pipeline.apply(step.dx, step.dy);
+    }
+}
+
+/// Pre-calculates the adjusted values of bullet offsets
+fn adjust_fire_rate(fire_rate: &mut FireRate, pipeline: &TransformPipeline) {
+    for offset in &mut fire_rate.offsets {
+        (offset.adjusted_dx, offset.adjusted_dy) = pipeline.apply(offset.dx, offset.dy);
+    }
+}
+
+/// Pre-calculates the adjusted values of semi-automatic impulses
+fn adjust_semi_auto(semi_auto: &mut SemiAuto, pipeline: &TransformPipeline) {
+    for shot in &mut semi_auto.shots {
+        (shot.adjusted_dx, shot.adjusted_dy) = pipeline.apply(shot.dx, shot.dy);
     }
 }
@@ -0,0 +1,452 @@
+use std::time::{Duration, Instant};
+
+use log::error;
+use serde::Serialize;
+
+use crate::mouse_controller::transform::TransformPipeline;
+use crate::patterns::{FireRate, SemiAuto, Step};
+use crate::scripting::{PatternScript, ScriptInput, ScriptScope};
+
+/// Phase of the mouse controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "phase", rename_all = "snake_case")]
+pub enum ControllerPhase {
+    /// No pattern is loaded
+    Idle,
+    /// A pattern is loaded and the controller waits for the activation rule
+    Armed,
+    /// The activation rule is satisfied and the start delay of the pattern has not elapsed yet
+    Delayed,
+    /// Recoil is being compensated with the given step (or bullet or shot for other patterns)
+    Compensating { step: usize },
+    /// The pattern is exhausted (e.g. the magazine is empty) while still active
+    Finished,
+    /// The controller is disabled
+    Disabled,
+}
+
+/// Change of the controller phase, published to observers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ControllerTransition {
+    /// Phase before the transition
+    pub from: ControllerPhase,
+    /// Phase after the transition
+    pub to: ControllerPhase,
+}
+
+/// Everything the state machine needs to know about the current iteration
+pub struct TickInput<'a> {
+    /// Whether the controller is enabled
+    pub enabled: bool,
+    /// Whether the activation rule is satisfied
+    pub active: bool,
+    /// Duration-based steps of the current pattern
+    pub steps: &'a [Step],
+    /// Fire-rate based pattern, used instead of `steps` when set
+    pub fire_rate: Option<&'a FireRate>,
+    /// Per-click pattern, used instead of `fire_rate` and `steps` when set
+    pub semi_auto: Option<&'a SemiAuto>,
+    /// Time between activation and the start of the compensation
+    pub start_delay: Duration,
+    /// Time over which the compensation is scaled up from zero to full strength
+    pub ramp_in: Duration,
+    /// Script that computes the movement of every iteration, if any
+    pub script: Option<&'a PatternScript>,
+    /// Transforms applied to the movement returned by the script, whose clamps
+    /// limit the movement of every iteration
+    pub pipeline: &'a TransformPipeline,
+    /// Name of the active modifier, if any
+    pub modifier: Option<&'a str>,
+}
+
+/// Result of a single state machine iteration
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TickOutput {
+    /// Horizontal movement to send in pixels
+    pub dx: i32,
+    /// Vertical movement to send in pixels
+    pub dy: i32,
+    /// Phase change caused by this iteration, if any
+    pub transition: Option<ControllerTransition>,
+}
+
+/// Impulse of a semi-automatic shot that is being spread over several iterations
+#[derive(Debug, Clone, Copy)]
+struct Pull {
+    /// Index of the shot that triggered the impulse
+    shot: usize,
+    /// Time at which the shot was fired
+    started: Instant,
+    /// Total horizontal movement of the impulse
+    dx: i32,
+    /// Total vertical movement of the impulse
+    dy: i32,
+    /// Horizontal movement applied so far
+    applied_dx: i32,
+    /// Vertical movement applied so far
+    applied_dy: i32,
+}
+
+/// Explicit state machine that drives the mouse controller
+///
+/// The machine does not read the clock itself; every iteration receives the
+/// current time, so its behaviour can be reproduced with any sequence of instants.
+#[derive(Debug, Clone)]
+pub struct StateMachine {
+    /// Current phase of the controller
+    phase: ControllerPhase,
+    /// Index of the current step of a duration-based pattern
+    step_index: usize,
+    /// Time at which the current step started
+    step_started: Instant,
+    /// Time at which compensation started, after the start delay, or the first
+    /// semi-automatic shot after an idle period
+    activated_at: Instant,
+    /// Number of bullets fired since activation for fire-rate based patterns
+    bullets_fired: u32,
+    /// Whether the activation rule was satisfied during the previous iteration
+    was_active: bool,
+    /// Number of shots fired since the last reset for semi-automatic patterns
+    shots_fired: u32,
+    /// Time of the last semi-automatic shot
+    last_shot_at: Option<Instant>,
+    /// Impulse of the last semi-automatic shot that has not been fully applied yet
+    pull: Option<Pull>,
+    /// Number of iterations since compensation started, or since the first
+    /// semi-automatic shot after an idle period
+    ticks: u64,
+    /// Whether the script failed, so the pattern is applied without it until the next activation
+    script_failed: bool,
+    /// Variables of the script, reused for every evaluation
+    script_scope: ScriptScope,
+}
+
+impl StateMachine {
+    /// Creates a new state machine in the idle phase
+    ///
+    /// # Arguments
+    /// * `now` - The current time
+    pub fn new(now: Instant) -> Self {
+        Self {
+            phase: ControllerPhase::Idle,
+            step_index: 0,
+            step_started: now,
+            activated_at: now,
+            bullets_fired: 0,
+            was_active: false,
+            shots_fired: 0,
+            last_shot_at: None,
+            pull: None,
+            ticks: 0,
+            script_failed: false,
+            script_scope: ScriptScope::new(),
+        }
+    }
+
+    /// Returns the current phase of the controller
+    pub fn phase(&self) -> ControllerPhase {
+        self.phase
+    }
+
+    /// Advances the state machine by one iteration
+    ///
+    /// # Arguments
+    /// * `now` - The current time
+    /// * `input` - The controller state and pattern for this iteration
+    ///
+    /// # Returns
+    /// The movement to send and the phase transition, if one happened
+    pub fn tick(&mut self, now: Instant, input: TickInput<'_>) -> TickOutput {
+        let has_pattern = input.semi_auto.is_some()
+            || input.fire_rate.is_some()
+            || input.script.is_some()
+            || !input.steps.is_empty();
+
+        // A shot is fired on every press edge of the activation rule
+        let pressed = input.active && !self.was_active;
+        self.was_active = input.active;
+        let was_compensating = matches!(
+            self.phase,
+            ControllerPhase::Delayed
+                | ControllerPhase::Compensating { .. }
+                | ControllerPhase::Finished
+        );
+
+        let mut output = TickOutput::default();
+
+        let next_phase = if !input.enabled {
+            self.pull = None;
+            ControllerPhase::Disabled
+        } else if !has_pattern {
+            self.pull = None;
+            ControllerPhase::Idle
+        } else if let Some(semi_auto) = input.semi_auto {
+            // Semi-automatic impulses keep being applied after the button is released
+            self.tick_semi_auto(now, pressed, &input, semi_auto, &mut output)
+        } else if !input.active {
+            ControllerPhase::Armed
+        } else {
+            if !was_compensating {
+                // Always start from the first step once the start delay has elapsed
+                self.step_index = 0;
+                self.activated_at = now + input.start_delay;
+                self.step_started = self.activated_at;
+                self.bullets_fired = 0;
+                self.ticks = 0;
+                self.script_failed = false;
+            }
+
+            if now < self.activated_at {
+                ControllerPhase::Delayed
+            } else {
+                let phase = match input.fire_rate {
+                    Some(fire_rate) => self.tick_fire_rate(now, &input, fire_rate, &mut output),
+                    None => self.tick_steps(now, &input, &mut output),
+                };
+                self.ramp_in(now, input.ramp_in, &mut output);
+                phase
+            }
+        };
+
+        // Bullets fired together and flushed impulses are limited as a whole
+        (output.dx, output.dy) = input.pipeline.limit(output.dx, output.dy);
+
+        if next_phase != self.phase {
+            output.transition = Some(ControllerTransition {
+                from: self.phase,
+                to: next_phase,
+            });
+            self.phase = next_phase;
+        }
+
+        output
+    }
+
+    /// Scales the movement down while the compensation is ramping in
+    fn ramp_in(&self, now: Instant, ramp_in: Duration, output: &mut TickOutput) {
+        if ramp_in.is_zero() {
+            return;
+        }
+
+        let progress =
+            now.saturating_duration_since(self.activated_at).as_secs_f32() / ramp_in.as_secs_f32();
+        if progress < 1.0 {
+            output.dx = (output.dx as f32 * progress).round() as i32;
+            output.dy = (output.dy as f32 * progress).round() as i32;
+        }
+    }
+
+    /// Starts an impulse on every shot and applies the part of it that is due
+    ///
+    /// The script, if any, is evaluated while an impulse is being applied and
+    /// sees the time since the first shot after an idle period.
+    fn tick_semi_auto(
+        &mut self,
+        now: Instant,
+        pressed: bool,
+        input: &TickInput<'_>,
+        semi_auto: &SemiAuto,
+        output: &mut TickOutput,
+    ) -> ControllerPhase {
+        if pressed {
+            // Start over from the first impulse after an idle period
+            let idle = match self.last_shot_at {
+                Some(last) => now.saturating_duration_since(last) >= semi_auto.reset_after(),
+                None => true,
+            };
+            if idle {
+                self.shots_fired = 0;
+                self.activated_at = now;
+                self.ticks = 0;
+                self.script_failed = false;
+            }
+
+            // The rest of the previous impulse is applied at once before the next one starts
+            if let Some(pull) = self.pull.take() {
+                output.dx += pull.dx - pull.applied_dx;
+                output.dy += pull.dy - pull.applied_dy;
+            }
+
+            if let Some(offset) = semi_auto.offset_for(self.shots_fired) {
+                self.pull = Some(Pull {
+                    shot: self.shots_fired as usize,
+                    started: now,
+                    dx: offset.adjusted_dx,
+                    dy: offset.adjusted_dy,
+                    applied_dx: 0,
+                    applied_dy: 0,
+                });
+            }
+            self.shots_fired = self.shots_fired.saturating_add(1);
+            self.last_shot_at = Some(now);
+        }
+
+        let Some(pull) = &mut self.pull else {
+            return ControllerPhase::Armed;
+        };
+        let shot = pull.shot;
+
+        // Spread the impulse linearly over the pull duration
+        let pull_duration = semi_auto.pull_duration();
+        let progress = if pull_duration.is_zero() {
+            1.0
+        } else {
+            (now.saturating_duration_since(pull.started).as_secs_f32()
+                / pull_duration.as_secs_f32())
+            .min(1.0)
+        };
+        let due_dx = (pull.dx as f32 * progress).round() as i32;
+        let due_dy = (pull.dy as f32 * progress).round() as i32;
+        output.dx += due_dx - pull.applied_dx;
+        output.dy += due_dy - pull.applied_dy;
+        pull.applied_dx = due_dx;
+        pull.applied_dy = due_dy;
+
+        if progress >= 1.0 {
+            self.pull = None;
+        }
+
+        let offset = semi_auto.offset_for(shot as u32);
+        let script_input = ScriptInput {
+            time_ms: now.saturating_duration_since(self.activated_at).as_millis() as u64,
+            tick: self.ticks,
+            shots: self.shots_fired,
+            step: shot,
+            step_dx: offset.map_or(0, |offset| offset.dx),
+            step_dy: offset.map_or(0, |offset| offset.dy),
+            modifier: input.modifier,
+        };
+        self.apply_script(input, &script_input, output);
+        self.ticks += 1;
+
+        ControllerPhase::Compensating { step: shot }
+    }
+
+    /// Applies the offsets of all bullets fired since the last iteration
+    ///
+    /// The script, if any, is evaluated until the magazine is empty.
+    fn tick_fire_rate(
+        &mut self,
+        now: Instant,
+        input: &TickInput<'_>,
+        fire_rate: &FireRate,
+        output: &mut TickOutput,
+    ) -> ControllerPhase {
+        let was_empty = self.bullets_fired >= fire_rate.magazine;
+
+        // Number of bullets fired since activation, capped by the magazine size
+        let interval = fire_rate.bullet_interval().as_micros().max(1);
+        let elapsed_bullets = now.saturating_duration_since(self.activated_at).as_micros() / interval;
+        let due_bullets = u32::try_from(elapsed_bullets)
+            .unwrap_or(u32::MAX)
+            .saturating_add(1)
+            .min(fire_rate.magazine);
+
+        while self.bullets_fired < due_bullets {
+            if let Some(offset) = fire_rate.offset_for(self.bullets_fired) {
+                output.dx += offset.adjusted_dx;
+                output.dy += offset.adjusted_dy;
+            }
+            self.bullets_fired += 1;
+        }
+
+        if !was_empty {
+            let bullet = self.bullets_fired.saturating_sub(1);
+            let offset = fire_rate.offset_for(bullet);
+            let script_input = ScriptInput {
+                time_ms: now.saturating_duration_since(self.activated_at).as_millis() as u64,
+                tick: self.ticks,
+                shots: self.bullets_fired,
+                step: bullet as usize,
+                step_dx: offset.map_or(0, |offset| offset.dx),
+                step_dy: offset.map_or(0, |offset| offset.dy),
+                modifier: input.modifier,
+            };
+            self.apply_script(input, &script_input, output);
+            self.ticks += 1;
+        }
+
+        if self.bullets_fired >= fire_rate.magazine {
+            ControllerPhase::Finished
+        } else {
+            ControllerPhase::Compensating {
+                step: self.bullets_fired.saturating_sub(1) as usize,
+            }
+        }
+    }
+
+    /// Applies the current step and advances to the next one when its duration has elapsed
+    ///
+    /// When the pattern has a script, its movement replaces the one of the step.
+    /// A script without steps starts from no movement and stays at step 0.
+    fn tick_steps(
+        &mut self,
+        now: Instant,
+        input: &TickInput<'_>,
+        output: &mut TickOutput,
+    ) -> ControllerPhase {
+        let steps = input.steps;
+
+        // The pattern may have been updated while the controller was running
+        if self.step_index >= steps.len() {
+            self.step_index = 0;
+            self.step_started = now;
+        }
+
+        let step_index = self.step_index;
+        let step = steps.get(step_index);
+        if let Some(step) = step {
+            output.dx = step.adjusted_dx;
+            output.dy = step.adjusted_dy;
+        }
+
+        let script_input = ScriptInput {
+            time_ms: now.saturating_duration_since(self.activated_at).as_millis() as u64,
+            tick: self.ticks,
+            shots: 0,
+            step: step_index,
+            step_dx: step.map_or(0, |step| step.dx),
+            step_dy: step.map_or(0, |step| step.dy),
+            modifier: input.modifier,
+        };
+        self.apply_script(input, &script_input, output);
+        self.ticks += 1;
+
+        // Check if it's time to move to the next step
+        let Some(step) = step else {
+            return ControllerPhase::Compensating { step: step_index };
+        };
+        if now.saturating_duration_since(self.step_started) >= Duration::from_millis(step.duration) {
+            // Stay at the last step instead of looping back to the beginning
+            if self.step_index < steps.len() - 1 {
+                self.step_index += 1;
+            }
+            self.step_started = now;
+        }
+
+        ControllerPhase::Compensating { step: step_index }
+    }
+
+    /// Replaces the movement of this iteration by the one returned by the script, if any
+    ///
+    /// A failing script is not evaluated again until the next activation.
+    fn apply_script(
+        &mut self,
+        input: &TickInput<'_>,
+        script_input: &ScriptInput<'_>,
+        output: &mut TickOutput,
+    ) {
+        let Some(script) = input.script.filter(|_| !self.script_failed) else {
+            return;
+        };
+
+        match script.evaluate(&mut self.script_scope, script_input) {
+            Ok(Some((dx, dy))) => (output.dx, output.dy) = input.pipeline.apply(dx, dy),
+            Ok(None) => {}
+            Err(e) => {
+                error!("Falling back to the pattern without its script: {}", e);
+                self.script_failed = true;
+            }
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use crate::patterns::{Sensitivity, TransformSettings};
+
+/// Single stage of the movement transform pipeline
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransformStage {
+    /// Divides the movement by the sensitivity when it is above 1.0; non-zero
+    /// movements keep at least one pixel
+    Sensitivity { x: f32, y: f32 },
+    /// Divides the movement by a sensitivity converted by the game model, which
+    /// enlarges the movement when below 1.0; non-zero movements keep at least one pixel
+    GameSensitivity { x: f32, y: f32 },
+    /// Scales both axes by a percentage
+    Strength(f32),
+    /// Scales each axis by its own multiplier
+    Scale { x: f32, y: f32 },
+    /// Flips the direction of the selected axes
+    Invert { x: bool, y: bool },
+    /// Limits the absolute movement of each axis sent in a single iteration;
+    /// applied by `TransformPipeline::limit` instead of to every movement value
+    Clamp { max_dx: Option<i32>, max_dy: Option<i32> },
+}
+
+impl TransformStage {
+    /// Applies this stage to a movement
+    ///
+    /// # Arguments
+    /// * `dx` - Horizontal movement in pixels
+    /// * `dy` - Vertical movement in pixels
+    ///
+    /// # Returns
+    /// The transformed movement in pixels
+    pub fn apply(&self, dx: i32, dy: i32) -> (i32, i32) {
+        match *self {
+            TransformStage::Sensitivity { x, y } => {
+                (divide_by_sensitivity(dx, x), divide_by_sensitivity(dy, y))
+            }
+            TransformStage::GameSensitivity { x, y } => {
+                (divide_by_game_sensitivity(dx, x), divide_by_game_sensitivity(dy, y))
+            }
+            TransformStage::Strength(percent) => {
+                (scale(dx, percent / 100.0), scale(dy, percent / 100.0))
+            }
+            TransformStage::Scale { x, y } => (scale(dx, x), scale(dy, y)),
+            TransformStage::Invert { x, y } => {
+                (if x { -dx } else { dx }, if y { -dy } else { dy })
+            }
+            TransformStage::Clamp { max_dx, max_dy } => (clamp(dx, max_dx), clamp(dy, max_dy)),
+        }
+    }
+}
+
+/// Ordered list of transforms applied to the output of every step
+///
+/// The pipeline turns the raw `dx`/`dy` values of a pattern into the pixels
+/// that are actually sent, so the adjusted values of steps and bullet offsets
+/// are always calculated the same way. Clamp stages limit what is sent in an
+/// iteration, which may add up several values, so they are only applied by
+/// `limit` to the total movement of the iteration.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransformPipeline {
+    /// Stages in the order they are applied
+    stages: Vec<TransformStage>,
+}
+
+impl TransformPipeline {
+    /// Creates the pipeline described by the configuration
+    ///
+    /// Stages are applied in a fixed order: sensitivity, strength, per-axis
+    /// multipliers, inversion and finally the per-tick clamps.
+    ///
+    /// # Arguments
+    /// * `sensitivity` - Sensitivity settings for mouse movements
+    /// * `settings` - Transforms applied after sensitivity
+    pub fn new(sensitivity: &Sensitivity, settings: &TransformSettings) -> Self {
+        Self::with_sensitivity_stage(
+            TransformStage::Sensitivity {
+                x: sensitivity.x,
+                y: sensitivity.y,
+            },
+            settings,
+        )
+    }
+
+    /// Creates the pipeline for a sensitivity converted by the game model
+    ///
+    /// Unlike `new`, a sensitivity below 1.0 enlarges the movement, so a player
+    /// whose view turns less per count than the reference gets more counts.
+    ///
+    /// # Arguments
+    /// * `sensitivity` - Sensitivity already scaled to the current game settings
+    /// * `settings` - Transforms applied after sensitivity
+    pub fn for_game(sensitivity: &Sensitivity, settings: &TransformSettings) -> Self {
+        Self::with_sensitivity_stage(
+            TransformStage::GameSensitivity {
+                x: sensitivity.x,
+                y: sensitivity.y,
+            },
+            settings,
+        )
+    }
+
+    /// Creates the pipeline of the configuration with the given sensitivity stage
+    fn with_sensitivity_stage(sensitivity: TransformStage, settings: &TransformSettings) -> Self {
+        Self::with_stages(vec![
+            sensitivity,
+            TransformStage::Strength(settings.strength),
+            TransformStage::Scale {
+                x: settings.scale_x,
+                y: settings.scale_y,
+            },
+            TransformStage::Invert {
+                x: settings.invert_x,
+                y: settings.invert_y,
+            },
+            TransformStage::Clamp {
+                max_dx: settings.max_dx,
+                max_dy: settings.max_dy,
+            },
+        ])
+    }
+
+    /// Creates a pipeline from custom stages
+    pub fn with_stages(stages: Vec<TransformStage>) -> Self {
+        Self { stages }
+    }
+
+    /// Applies all stages but the clamps to a movement value
+    ///
+    /// # Arguments
+    /// * `dx` - Horizontal movement in pixels
+    /// * `dy` - Vertical movement in pixels
+    ///
+    /// # Returns
+    /// The transformed movement in pixels
+    pub fn apply(&self, dx: i32, dy: i32) -> (i32, i32) {
+        self.stages
+            .iter()
+            .filter(|stage| !matches!(stage, TransformStage::Clamp { .. }))
+            .fold((dx, dy), |(dx, dy), stage| stage.apply(dx, dy))
+    }
+
+    /// Applies the clamp stages to the total movement sent in an iteration
+    ///
+    /// # Arguments
+    /// * `dx` - Horizontal movement of the iteration in pixels
+    /// * `dy` - Vertical movement of the iteration in pixels
+    ///
+    /// # Returns
+    /// The movement to send in pixels
+    pub fn limit(&self, dx: i32, dy: i32) -> (i32, i32) {
+        self.stages
+            .iter()
+            .filter(|stage| matches!(stage, TransformStage::Clamp { .. }))
+            .fold((dx, dy), |(dx, dy), stage| stage.apply(dx, dy))
+    }
+}
+
+/// Divides a movement value by a sensitivity
+///
+/// Higher sensitivity values make the movement smaller; values of 1.0 and below
+/// leave it unchanged. A non-zero movement never collapses to zero and keeps at
+/// least one pixel in its original direction.
+fn divide_by_sensitivity(value: i32, sensitivity: f32) -> i32 {
+    if sensitivity > 1.0 {
+        divide_by_game_sensitivity(value, sensitivity)
+    } else {
+        value
+    }
+}
+
+/// Divides a movement value by a sensitivity converted by the game model
+///
+/// Unlike `divide_by_sensitivity`, values below 1.0 make the movement larger.
+/// A non-zero movement never collapses to zero and keeps at least one pixel in
+/// its original direction.
+fn divide_by_game_sensitivity(value: i32, sensitivity: f32) -> i32 {
+    if value == 0 {
+        0
+    } else if sensitivity > 0.0 {
+        let calculated = (value as f32 / sensitivity) as i32;
+        if calculated == 0 {
+            value.signum()
+        } else {
+            calculated
+        }
+    } else {
+        value
+    }
+}
+
+/// Returns whether dividing a movement value by a sensitivity only keeps the one-pixel minimum
+///
+/// # Arguments
+/// * `value` - Movement value in pixels
+/// * `sensitivity` - Sensitivity the value is divided by
+pub fn collapses_to_minimum(value: i32, sensitivity: f32) -> bool {
+    value != 0 && sensitivity > 0.0 && (value as f32 / sensitivity) as i32 == 0
+}
+
+/// Multiplies a movement value by a factor, rounding to the nearest pixel
+fn scale(value: i32, factor: f32) -> i32 {
+    (value as f32 * factor).round() as i32
+}
+
+/// Limits the absolute value of a movement
+fn clamp(value: i32, max: Option<i32>) -> i32 {
+    match max {
+        Some(max) => value.clamp(-max.abs(), max.abs()),
+        None => value,
+    }
+}
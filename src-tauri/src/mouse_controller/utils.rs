@@ -1,7 +1,8 @@
+#[cfg(windows)]
 use log::error;
+#[cfg(windows)]
 use winapi::um::winuser::{
-    GetAsyncKeyState, SendInput, INPUT, INPUT_MOUSE, MOUSEEVENTF_MOVE, MOUSEINPUT, VK_LBUTTON,
-    VK_RBUTTON,
+    GetAsyncKeyState, SendInput, INPUT, INPUT_MOUSE, MOUSEEVENTF_MOVE, MOUSEINPUT,
 };
 
 use crate::mouse_controller::error::MouseInputError;
@@ -15,6 +16,7 @@ use crate::mouse_controller::error::MouseInputError;
 /// # Returns
 /// * `Ok(())` if the input was sent successfully
 /// * `Err(MouseInputError)` if the input could not be sent
+#[cfg(windows)]
 pub fn send_mouse_input(dx: i32, dy: i32) -> Result<(), MouseInputError> {
     // Create a MOUSEINPUT struct for the mouse movement
     let mouse_input = MOUSEINPUT {
@@ -51,9 +53,8 @@ pub fn send_mouse_input(dx: i32, dy: i32) -> Result<(), MouseInputError> {
     Ok(())
 }
 
-/// Checks if the mouse controller should be running.
+/// Checks if a key or mouse button is currently pressed.
 ///
-/// This function checks if both the left and right mouse buttons are currently pressed.
 /// It uses the Windows API function `GetAsyncKeyState` which returns the state of the
 /// specified virtual key. The high-order bit (0x8000) is set if the key is currently pressed.
 ///
@@ -61,26 +62,33 @@ pub fn send_mouse_input(dx: i32, dy: i32) -> Result<(), MouseInputError> {
 /// This function uses unsafe code to call the Windows API. It's safe to use as long as
 /// the Windows API is available and functioning correctly.
 ///
+/// # Arguments
+/// * `code` - Virtual key code of the key or mouse button
+///
 /// # Returns
-/// `true` if both left and right mouse buttons are pressed, `false` otherwise
-pub fn should_run() -> bool {
+/// `true` if the key is pressed, `false` otherwise
+#[cfg(windows)]
+pub fn is_key_pressed(code: i32) -> bool {
     // Constant for the high-order bit that indicates if a key is pressed
     const KEY_PRESSED_MASK: i16 = 0x8000u16 as i16;
 
-    // Check if the left mouse button is pressed
-    let left_pressed = unsafe { 
-        GetAsyncKeyState(VK_LBUTTON) & KEY_PRESSED_MASK != 0 
-    };
-
-    // Only check the right button if the left is pressed (short-circuit optimization)
-    if !left_pressed {
-        return false;
-    }
+    unsafe { GetAsyncKeyState(code) & KEY_PRESSED_MASK != 0 }
+}
 
-    // Check if the right mouse button is pressed
-    let right_pressed: bool = unsafe { 
-        GetAsyncKeyState(VK_RBUTTON) & KEY_PRESSED_MASK != 0 
-    };
+/// Mouse input can only be sent on Windows
+///
+/// # Returns
+/// Always `Err(MouseInputError::Unsupported)`
+#[cfg(not(windows))]
+pub fn send_mouse_input(_dx: i32, _dy: i32) -> Result<(), MouseInputError> {
+    Err(MouseInputError::Unsupported)
+}
 
-    right_pressed
-}
\ No newline at end of file
+/// Key states can only be read on Windows
+///
+/// # Returns
+/// Always `false`, as if no key were pressed
+#[cfg(not(windows))]
+pub fn is_key_pressed(_code: i32) -> bool {
+    false
+}
@@ -1,4 +1,6 @@
-use crate::patterns::types::{Pattern, Sensitivity};
+use crate::patterns::types::{
+    ActivationRule, GameSensitivity, GameSettings, Modifier, Pattern, Sensitivity, Steps, TransformSettings,
+};
 use screenshots::Screen;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -11,6 +13,18 @@ pub struct PatternCollection {
     pub patterns: HashMap<String, Pattern>,
     /// Sensitivity settings for mouse movements
     pub sensitivity: Sensitivity,
+    /// Optional game settings used to convert patterns to the current player's setup
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game: Option<GameSensitivity>,
+    /// Condition under which recoil compensation is active
+    #[serde(default)]
+    pub activation: ActivationRule,
+    /// Transforms applied to every movement after sensitivity
+    #[serde(default)]
+    pub transform: TransformSettings,
+    /// Keys that change the compensation while held, in order of priority
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modifiers: Vec<Modifier>,
 }
 
 impl PatternCollection {
@@ -22,6 +36,10 @@ impl PatternCollection {
         Self {
             patterns: config.patterns,
             sensitivity: config.sensitivity,
+            game: config.game,
+            activation: config.activation,
+            transform: config.transform,
+            modifiers: config.modifiers,
         }
     }
 
@@ -35,6 +53,44 @@ impl PatternCollection {
         &self.patterns
     }
 
+    /// Replaces the steps of a pattern, adding the pattern when it does not exist
+    ///
+    /// Fire-rate, semi-automatic, generator and script data of an existing
+    /// pattern are removed, so the new steps are the ones that are applied;
+    /// images, scopes and other settings are kept.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the pattern
+    /// * `steps` - Steps of the pattern
+    /// * `baseline` - Game settings the steps were tuned at
+    pub fn set_steps(&mut self, name: &str, steps: Steps, baseline: Option<GameSettings>) {
+        let pattern = self
+            .patterns
+            .entry(name.to_string())
+            .or_insert_with(|| Pattern {
+                config: Vec::new(),
+                fire_rate: None,
+                generator: None,
+                script: None,
+                semi_auto: None,
+                activation: None,
+                sensitivity: None,
+                scopes: HashMap::new(),
+                start_delay_ms: 0,
+                ramp_in_ms: 0,
+                variants: HashMap::new(),
+                baseline: None,
+                images: Vec::new(),
+            });
+
+        pattern.config = steps;
+        pattern.fire_rate = None;
+        pattern.generator = None;
+        pattern.script = None;
+        pattern.semi_auto = None;
+        pattern.baseline = baseline;
+    }
+
     /// Detects which pattern is currently on the screen
     ///
     /// This function checks all images from all patterns and returns the name
@@ -92,22 +148,65 @@ impl PatternCollection {
         None
     }
 
-    /// Loads patterns from a JSON file
+    /// Loads patterns from the default configuration file
     fn load_from_file() -> Result<Self, String> {
-        match fs::read_to_string("config/config.json") {
-            Ok(json) => match serde_json::from_str(&json) {
-                Ok(collection) => Ok(collection),
+        Self::load_from_path("config/config.json")
+    }
+
+    /// Loads patterns from the given JSON file
+    ///
+    /// The steps of patterns with a generator are generated once loaded.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the configuration file
+    pub fn load_from_path(path: &str) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(json) => match serde_json::from_str::<Self>(&json) {
+                Ok(mut collection) => {
+                    collection.generate_steps()?;
+                    Ok(collection)
+                }
                 Err(e) => Err(format!("Failed to parse JSON: {}", e)),
             },
             Err(e) => Err(format!("Failed to read file: {}", e)),
         }
     }
 
+    /// Saves patterns to the given JSON file
+    ///
+    /// # Arguments
+    /// * `path` - Path of the configuration file
+    pub fn save_to_path(&self, path: &str) -> Result<(), String> {
+        let json = self.to_json()?;
+        fs::write(path, json).map_err(|e| format!("Failed to write file: {}", e))
+    }
+
+    /// Serializes the patterns to pretty-printed JSON
+    ///
+    /// Generated steps are left out, since they are generated again when the
+    /// configuration is loaded.
+    pub fn to_json(&self) -> Result<String, String> {
+        let mut collection = self.clone();
+        for pattern in collection.patterns.values_mut() {
+            if pattern.generator.is_some() {
+                pattern.config.clear();
+            }
+        }
+
+        serde_json::to_string_pretty(&collection)
+            .map_err(|e| format!("Failed to serialize JSON: {}", e))
+    }
+
     /// Reloads patterns from the JSON file
     pub fn reload(&mut self) -> Result<(), String> {
         match Self::load_from_file() {
             Ok(collection) => {
                 self.patterns = collection.patterns;
+                self.sensitivity = collection.sensitivity;
+                self.game = collection.game;
+                self.activation = collection.activation;
+                self.transform = collection.transform;
+                self.modifiers = collection.modifiers;
                 Ok(())
             },
             Err(e) => Err(e),
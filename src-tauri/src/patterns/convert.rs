@@ -0,0 +1,205 @@
+use crate::patterns::collection::PatternCollection;
+use crate::patterns::types::{GameSettings, Pattern};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Smallest difference between the exact and the rounded value that is reported as a loss
+const LOSS_TOLERANCE: f32 = 0.01;
+
+/// Movement whose converted value could not be represented exactly in whole pixels
+#[derive(Debug, Clone, Serialize)]
+pub struct RoundingLoss {
+    /// Index of the step, bullet offset or shot
+    pub index: usize,
+    /// Exact horizontal movement at the target settings
+    pub exact_dx: f32,
+    /// Exact vertical movement at the target settings
+    pub exact_dy: f32,
+    /// Rounded horizontal movement that was stored
+    pub dx: i32,
+    /// Rounded vertical movement that was stored
+    pub dy: i32,
+}
+
+/// Summary of the conversion of a single pattern
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversionReport {
+    /// Factor every movement was multiplied by
+    pub factor: f32,
+    /// Steps whose movement was rounded
+    pub steps: Vec<RoundingLoss>,
+    /// Bullet offsets whose movement was rounded
+    pub offsets: Vec<RoundingLoss>,
+    /// Semi-automatic impulses whose movement was rounded
+    pub shots: Vec<RoundingLoss>,
+    /// Steps of each modifier variant whose movement was rounded, by modifier name
+    pub variants: BTreeMap<String, Vec<RoundingLoss>>,
+    /// Whether the pattern has a script, whose movement cannot be converted
+    pub unconverted_script: bool,
+}
+
+impl ConversionReport {
+    /// Returns whether every movement was converted without rounding
+    pub fn is_lossless(&self) -> bool {
+        self.steps.is_empty()
+            && self.offsets.is_empty()
+            && self.shots.is_empty()
+            && self.variants.is_empty()
+            && !self.unconverted_script
+    }
+}
+
+/// Returns the factor that converts movements from the baseline to the target settings
+///
+/// # Arguments
+/// * `baseline` - Settings the movements were tuned at
+/// * `target` - Settings the movements are converted to
+///
+/// # Returns
+/// The factor, or an error if the settings produce an invalid one
+pub fn conversion_factor(baseline: &GameSettings, target: &GameSettings) -> Result<f32, String> {
+    // Counts needed to turn the same angle shrink as the target turns further per count
+    let factor = baseline.turn_ratio(target);
+    if factor.is_finite() && factor > 0.0 {
+        Ok(factor)
+    } else {
+        Err(format!(
+            "Cannot convert from {:?} to {:?}: invalid conversion factor {}",
+            baseline, target, factor
+        ))
+    }
+}
+
+/// Rescales all movements of a pattern from its baseline to the target settings
+///
+/// Steps, bullet offsets, semi-automatic impulses and the steps of every
+/// modifier variant are converted. The converted pattern records the target as its new baseline.
+/// A script computes its movement in code, so it is kept unchanged and reported
+/// as unconverted.
+///
+/// # Arguments
+/// * `pattern` - The pattern to convert
+/// * `baseline` - Settings the pattern was tuned at
+/// * `target` - Settings the pattern is converted to
+///
+/// # Returns
+/// The converted pattern together with the movements that lost precision
+pub fn convert_pattern(
+    pattern: &Pattern,
+    baseline: &GameSettings,
+    target: &GameSettings,
+) -> Result<(Pattern, ConversionReport), String> {
+    let factor = conversion_factor(baseline, target)?;
+    let mut converted = pattern.clone();
+    let mut report = ConversionReport {
+        factor,
+        steps: Vec::new(),
+        offsets: Vec::new(),
+        shots: Vec::new(),
+        variants: BTreeMap::new(),
+        unconverted_script: pattern.script.is_some(),
+    };
+
+    // Generator parameters are not rounded, so the steps are generated again from them
+    if let Some(generator) = &mut converted.generator {
+        generator.scale(factor);
+        converted.config = generator.generate()?;
+    } else {
+        for (index, step) in converted.config.iter_mut().enumerate() {
+            if let Some(loss) = rescale(index, &mut step.dx, &mut step.dy, factor) {
+                report.steps.push(loss);
+            }
+        }
+    }
+
+    if let Some(fire_rate) = &mut converted.fire_rate {
+        for (index, offset) in fire_rate.offsets.iter_mut().enumerate() {
+            if let Some(loss) = rescale(index, &mut offset.dx, &mut offset.dy, factor) {
+                report.offsets.push(loss);
+            }
+        }
+    }
+
+    if let Some(semi_auto) = &mut converted.semi_auto {
+        for (index, shot) in semi_auto.shots.iter_mut().enumerate() {
+            if let Some(loss) = rescale(index, &mut shot.dx, &mut shot.dy, factor) {
+                report.shots.push(loss);
+            }
+        }
+    }
+
+    for (name, steps) in &mut converted.variants {
+        let losses: Vec<RoundingLoss> = steps
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, step)| rescale(index, &mut step.dx, &mut step.dy, factor))
+            .collect();
+        if !losses.is_empty() {
+            report.variants.insert(name.clone(), losses);
+        }
+    }
+
+    converted.baseline = Some(target.clone());
+
+    Ok((converted, report))
+}
+
+impl PatternCollection {
+    /// Rescales all patterns of the collection to the target settings
+    ///
+    /// Each pattern is converted from its own baseline, or from the reference
+    /// of the game settings when it does not record one. The reference of the
+    /// converted collection is replaced by the target.
+    ///
+    /// # Arguments
+    /// * `target` - Settings the patterns are converted to
+    ///
+    /// # Returns
+    /// The converted collection and a report for every pattern, or an error if
+    /// a pattern has no baseline to convert from
+    pub fn convert(
+        &self,
+        target: &GameSettings,
+    ) -> Result<(PatternCollection, BTreeMap<String, ConversionReport>), String> {
+        let reference = self.game.as_ref().map(|game| &game.reference);
+        let mut converted = self.clone();
+        let mut reports = BTreeMap::new();
+
+        for (name, pattern) in &self.patterns {
+            let baseline = pattern
+                .baseline
+                .as_ref()
+                .or(reference)
+                .ok_or_else(|| format!("Pattern '{}' has no baseline settings", name))?;
+            let (pattern, report) = convert_pattern(pattern, baseline, target)?;
+
+            converted.patterns.insert(name.clone(), pattern);
+            reports.insert(name.clone(), report);
+        }
+
+        if let Some(game) = &mut converted.game {
+            game.reference = target.clone();
+        }
+
+        Ok((converted, reports))
+    }
+}
+
+/// Multiplies a movement by the factor, returning the loss if it had to be rounded
+fn rescale(index: usize, dx: &mut i32, dy: &mut i32, factor: f32) -> Option<RoundingLoss> {
+    let exact_dx = *dx as f32 * factor;
+    let exact_dy = *dy as f32 * factor;
+    *dx = exact_dx.round() as i32;
+    *dy = exact_dy.round() as i32;
+
+    let lost = (exact_dx - *dx as f32).abs() >= LOSS_TOLERANCE
+        || (exact_dy - *dy as f32).abs() >= LOSS_TOLERANCE;
+
+    lost.then_some(RoundingLoss {
+        index,
+        exact_dx,
+        exact_dy,
+        dx: *dx,
+        dy: *dy,
+    })
+}
@@ -0,0 +1,401 @@
+use crate::patterns::collection::PatternCollection;
+use crate::patterns::types::{Step, Steps};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Columns written to and read from CSV files, in the order they are written
+const COLUMNS: [&str; 6] = ["pattern", "step", "duration", "dx", "dy", "images"];
+
+/// Separator between the image paths of a pattern in the `images` column
+const IMAGE_SEPARATOR: char = ';';
+
+/// Error found while reading patterns from CSV
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvError {
+    /// Line of the offending row, starting at 1
+    pub line: usize,
+    /// Description of the problem
+    pub message: String,
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid CSV at line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+/// Steps and images of a pattern read from CSV
+#[derive(Debug, Clone)]
+pub struct CsvPattern {
+    /// Steps in the order of their index
+    pub steps: Steps,
+    /// Images of the pattern, or `None` when no row gave any
+    pub images: Option<Vec<String>>,
+}
+
+/// Difference between an imported pattern and the pattern already in the collection
+#[derive(Debug, Clone, Serialize)]
+pub struct CsvConflict {
+    /// Name of the pattern
+    pub pattern: String,
+    /// Fields that differ, `steps` and/or `images`
+    pub fields: Vec<&'static str>,
+}
+
+/// Summary of merging imported patterns into a collection
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MergeReport {
+    /// Patterns that did not exist before
+    pub added: Vec<String>,
+    /// Patterns that already existed with the same steps and images
+    pub unchanged: Vec<String>,
+    /// Patterns that already existed with different steps or images
+    pub conflicts: Vec<CsvConflict>,
+    /// Patterns that did not exist before and were not added because they have no steps
+    pub skipped: Vec<String>,
+    /// Whether the conflicting patterns were replaced by the imported ones
+    pub overwritten: bool,
+}
+
+impl PatternCollection {
+    /// Writes the steps and images of all patterns as CSV
+    ///
+    /// Every step is written as a row, sorted by pattern name and step index.
+    /// The images of a pattern are written on its first row, separated by `;`.
+    /// Patterns without steps, such as fire-rate patterns, are written as a
+    /// single row with empty step columns so their images are kept; importing
+    /// such a row leaves the pattern itself unchanged.
+    ///
+    /// # Returns
+    /// The CSV text including a header row
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!("{}\n", COLUMNS.join(","));
+        let patterns: BTreeMap<_, _> = self.patterns.iter().collect();
+
+        for (name, pattern) in patterns {
+            let images = pattern.images.join(&IMAGE_SEPARATOR.to_string());
+
+            if pattern.config.is_empty() {
+                write_row(&mut csv, &[name, "", "", "", "", &images]);
+                continue;
+            }
+
+            for (index, step) in pattern.config.iter().enumerate() {
+                write_row(
+                    &mut csv,
+                    &[
+                        name,
+                        &index.to_string(),
+                        &step.duration.to_string(),
+                        &step.dx.to_string(),
+                        &step.dy.to_string(),
+                        if index == 0 { &images } else { "" },
+                    ],
+                );
+            }
+        }
+
+        csv
+    }
+
+    /// Merges patterns read from CSV into the collection
+    ///
+    /// New patterns are added, unless the CSV gives no steps for them. Existing
+    /// patterns whose steps or images differ are reported as conflicts and are
+    /// only replaced when `overwrite` is set; replacing the steps removes
+    /// fire-rate, semi-automatic and script data, like `set_steps`. Steps and images
+    /// are each left unchanged when the CSV gives none, so fire-rate and
+    /// semi-automatic patterns exported with `to_csv` are read back unchanged.
+    ///
+    /// # Arguments
+    /// * `imported` - Patterns read with `parse_csv`
+    /// * `overwrite` - Whether conflicting patterns are replaced
+    ///
+    /// # Returns
+    /// Which patterns were added, unchanged or in conflict
+    pub fn merge_csv(
+        &mut self,
+        imported: BTreeMap<String, CsvPattern>,
+        overwrite: bool,
+    ) -> MergeReport {
+        let mut report = MergeReport {
+            overwritten: overwrite,
+            ..MergeReport::default()
+        };
+
+        for (name, csv_pattern) in imported {
+            let Some(existing) = self.patterns.get(&name) else {
+                // A pattern without steps would never move the mouse
+                if csv_pattern.steps.is_empty() {
+                    report.skipped.push(name);
+                    continue;
+                }
+                self.set_steps(&name, csv_pattern.steps, None);
+                if let Some(pattern) = self.patterns.get_mut(&name) {
+                    pattern.images = csv_pattern.images.unwrap_or_default();
+                }
+                report.added.push(name);
+                continue;
+            };
+
+            // Rows without steps only carry images; otherwise the steps replace
+            // fire-rate and semi-automatic data, so those always differ
+            let mut fields = Vec::new();
+            let same_steps = csv_pattern.steps.is_empty()
+                || (existing.fire_rate.is_none()
+                    && existing.semi_auto.is_none()
+                    && same_steps(&existing.config, &csv_pattern.steps));
+            if !same_steps {
+                fields.push("steps");
+            }
+            let same_images = match &csv_pattern.images {
+                Some(images) => *images == existing.images,
+                None => true,
+            };
+            if !same_images {
+                fields.push("images");
+            }
+
+            if fields.is_empty() {
+                report.unchanged.push(name);
+                continue;
+            }
+
+            if overwrite {
+                if !same_steps {
+                    let baseline = existing.baseline.clone();
+                    self.set_steps(&name, csv_pattern.steps, baseline);
+                }
+                if let (Some(images), Some(pattern)) =
+                    (csv_pattern.images, self.patterns.get_mut(&name))
+                {
+                    pattern.images = images;
+                }
+            }
+            report.conflicts.push(CsvConflict {
+                pattern: name,
+                fields,
+            });
+        }
+
+        report
+    }
+}
+
+/// Reads patterns from CSV
+///
+/// The first row must name the columns; `pattern`, `step`, `duration`, `dx`
+/// and `dy` are required and `images` is optional, in any order. Rows with an
+/// empty `step` column only carry images. The step indices of every pattern
+/// must start at 0 without gaps, but the rows may come in any order.
+///
+/// # Arguments
+/// * `text` - The CSV text
+///
+/// # Returns
+/// The patterns by name, or an error with the line of the first invalid row
+pub fn parse_csv(text: &str) -> Result<BTreeMap<String, CsvPattern>, CsvError> {
+    let mut rows = read_rows(text)?.into_iter();
+    let (_, header) = rows.next().ok_or_else(|| CsvError {
+        line: 1,
+        message: "missing header row".to_string(),
+    })?;
+
+    // Spreadsheets may start the file with a byte order mark
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|field| field.trim_start_matches('\u{feff}').trim() == name)
+    };
+    let required = |name: &str| {
+        column(name).ok_or_else(|| CsvError {
+            line: 1,
+            message: format!("missing column `{}`", name),
+        })
+    };
+    let pattern_column = required("pattern")?;
+    let step_column = required("step")?;
+    let duration_column = required("duration")?;
+    let dx_column = required("dx")?;
+    let dy_column = required("dy")?;
+    let images_column = column("images");
+
+    let mut patterns: BTreeMap<String, RowPattern> = BTreeMap::new();
+
+    for (line, fields) in rows {
+        if fields.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+
+        let error = |message: String| CsvError { line, message };
+        let field = |index: usize| fields.get(index).map_or("", |field| field.trim());
+
+        let name = field(pattern_column);
+        if name.is_empty() {
+            return Err(error("missing pattern name".to_string()));
+        }
+        let RowPattern { steps, images } = patterns.entry(name.to_string()).or_default();
+
+        let row_images = images_column.map(field).unwrap_or("");
+        if !row_images.is_empty() {
+            let row_images: Vec<String> = row_images
+                .split(IMAGE_SEPARATOR)
+                .map(|image| image.trim().to_string())
+                .filter(|image| !image.is_empty())
+                .collect();
+            match images {
+                Some((first, existing)) if *existing != row_images => {
+                    return Err(error(format!(
+                        "images of `{}` differ from line {}",
+                        name, first
+                    )))
+                }
+                Some(_) => {}
+                None => *images = Some((line, row_images)),
+            }
+        }
+
+        if field(step_column).is_empty() {
+            continue;
+        }
+        let index: usize = parse_number(field(step_column), "step index", line)?;
+        let step = Step {
+            duration: parse_number(field(duration_column), "duration", line)?,
+            dx: parse_number(field(dx_column), "dx", line)?,
+            dy: parse_number(field(dy_column), "dy", line)?,
+            adjusted_dx: 0,
+            adjusted_dy: 0,
+        };
+        if let Some((first, _)) = steps.insert(index, (line, step)) {
+            return Err(error(format!(
+                "step {} of `{}` is already defined at line {}",
+                index, name, first
+            )));
+        }
+    }
+
+    let mut result = BTreeMap::new();
+    for (name, RowPattern { steps, images }) in patterns {
+        // Indices are sorted, so a gap shows up as the first index that does not match its position
+        if let Some((expected, (_, (line, _)))) = steps
+            .iter()
+            .enumerate()
+            .find(|(position, (index, _))| *position != **index)
+        {
+            return Err(CsvError {
+                line: *line,
+                message: format!("step {} of `{}` is missing", expected, name),
+            });
+        }
+
+        result.insert(
+            name,
+            CsvPattern {
+                steps: steps.into_values().map(|(_, step)| step).collect(),
+                images: images.map(|(_, images)| images),
+            },
+        );
+    }
+
+    Ok(result)
+}
+
+/// Steps and images of a pattern collected from the rows of a CSV file
+#[derive(Default)]
+struct RowPattern {
+    /// Steps by index, with the line they were read from
+    steps: BTreeMap<usize, (usize, Step)>,
+    /// Images, with the line they were first read from
+    images: Option<(usize, Vec<String>)>,
+}
+
+/// Parses a numeric field of the row on the given line
+fn parse_number<T: FromStr>(field: &str, name: &str, line: usize) -> Result<T, CsvError> {
+    field.parse().map_err(|_| CsvError {
+        line,
+        message: format!("invalid {} `{}`", name, field),
+    })
+}
+
+/// Returns whether two step lists move the same way
+fn same_steps(a: &[Step], b: &[Step]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .all(|(a, b)| a.duration == b.duration && a.dx == b.dx && a.dy == b.dy)
+}
+
+/// Appends a row, quoting the fields that contain separators, quotes or line breaks
+fn write_row(csv: &mut String, fields: &[&str]) {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect();
+    csv.push_str(&fields.join(","));
+    csv.push('\n');
+}
+
+/// Splits CSV text into rows of fields, together with the line each row starts on
+///
+/// Fields may be quoted with `"`, in which case they can contain separators,
+/// line breaks and doubled quotes.
+fn read_rows(text: &str) -> Result<Vec<(usize, Vec<String>)>, CsvError> {
+    let mut rows = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut line = 1;
+    let mut row_line = 1;
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            '\n' if quoted => {
+                line += 1;
+                field.push(c);
+            }
+            _ if quoted => field.push(c),
+            ',' => fields.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                fields.push(std::mem::take(&mut field));
+                rows.push((row_line, std::mem::take(&mut fields)));
+                line += 1;
+                row_line = line;
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if quoted {
+        return Err(CsvError {
+            line: row_line,
+            message: "unterminated quoted field".to_string(),
+        });
+    }
+    if !field.is_empty() || !fields.is_empty() {
+        fields.push(field);
+        rows.push((row_line, fields));
+    }
+
+    Ok(rows)
+}
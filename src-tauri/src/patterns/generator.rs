@@ -0,0 +1,297 @@
+use crate::patterns::collection::PatternCollection;
+use crate::patterns::notation::MAX_STEPS;
+use crate::patterns::types::{Step, Steps};
+use serde::{Deserialize, Serialize};
+
+/// Movement per tick in pixels, allowing fractions that are spread over time
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Rate {
+    /// Horizontal movement per tick in pixels
+    pub dx: f32,
+    /// Vertical movement per tick in pixels
+    pub dy: f32,
+}
+
+/// Movement per tick at a point in time of a piecewise curve
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ControlPoint {
+    /// Time since the start of the pattern in milliseconds
+    pub time_ms: u64,
+    /// Horizontal movement per tick in pixels
+    pub dx: f32,
+    /// Vertical movement per tick in pixels
+    pub dy: f32,
+}
+
+/// Shape of the movement over time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Curve {
+    /// The same movement for the whole duration
+    Constant {
+        /// Length of the curve in milliseconds
+        duration_ms: u64,
+        /// Horizontal movement per tick in pixels
+        dx: f32,
+        /// Vertical movement per tick in pixels
+        dy: f32,
+    },
+    /// Movement that changes linearly from `from` to `to`
+    Linear {
+        /// Length of the curve in milliseconds
+        duration_ms: u64,
+        /// Movement at the start
+        from: Rate,
+        /// Movement at the end
+        to: Rate,
+    },
+    /// Movement that starts at `from` and settles exponentially towards `to`
+    Exponential {
+        /// Length of the curve in milliseconds
+        duration_ms: u64,
+        /// Movement at the start
+        from: Rate,
+        /// Movement the curve settles towards
+        to: Rate,
+        /// Time in milliseconds after which about 63% of the change has happened
+        time_constant_ms: f32,
+    },
+    /// Movement interpolated linearly between control points
+    Piecewise {
+        /// Control points in order of time; the curve ends at the last one
+        points: Vec<ControlPoint>,
+    },
+}
+
+/// Parameters from which the steps of a pattern are generated
+///
+/// The curve is cut into slices of `step_ms` and every slice becomes a step
+/// with the movement at its middle. Fractions are carried over to the next
+/// slice, so the generated path follows the curve without drifting.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Generator {
+    /// Length of the generated steps in milliseconds
+    #[serde(default = "default_step_ms")]
+    pub step_ms: u64,
+    /// Shape of the movement
+    #[serde(flatten)]
+    pub curve: Curve,
+}
+
+// Default function for step_ms
+fn default_step_ms() -> u64 {
+    50
+}
+
+impl Generator {
+    /// Generates the steps described by the parameters
+    ///
+    /// Neighbouring steps with the same movement are merged. A curve without
+    /// any duration becomes a single step of zero duration, which the
+    /// controller keeps applying like any last step.
+    ///
+    /// # Returns
+    /// The generated steps, or an error if the parameters are invalid or the
+    /// curve would be cut into more than `MAX_STEPS` steps
+    pub fn generate(&self) -> Result<Steps, String> {
+        self.validate()?;
+
+        let duration = self.duration();
+        if duration == 0 {
+            let rate = self.rate_at(0.0);
+            return Ok(vec![step(
+                0,
+                rate.dx.round() as i32,
+                rate.dy.round() as i32,
+            )]);
+        }
+
+        let step_ms = self.step_ms.max(1);
+        let mut steps: Steps = Vec::new();
+        let (mut carry_x, mut carry_y) = (0.0f64, 0.0f64);
+        let mut start = 0;
+
+        while start < duration {
+            let length = step_ms.min(duration - start);
+            let rate = self.rate_at(start as f32 + length as f32 / 2.0);
+
+            // Movement is weighted by the length of the slice, so short slices carry less
+            let exact_x = carry_x + rate.dx as f64 * length as f64;
+            let exact_y = carry_y + rate.dy as f64 * length as f64;
+            let dx = (exact_x / length as f64).round() as i32;
+            let dy = (exact_y / length as f64).round() as i32;
+            carry_x = exact_x - dx as f64 * length as f64;
+            carry_y = exact_y - dy as f64 * length as f64;
+
+            match steps.last_mut() {
+                Some(last) if last.dx == dx && last.dy == dy => last.duration += length,
+                _ => steps.push(step(length, dx, dy)),
+            }
+            start += length;
+        }
+
+        Ok(steps)
+    }
+
+    /// Multiplies every movement of the curve by the factor
+    ///
+    /// # Arguments
+    /// * `factor` - Factor the movement is multiplied by
+    pub fn scale(&mut self, factor: f32) {
+        let scale_rate = |rate: &mut Rate| {
+            rate.dx *= factor;
+            rate.dy *= factor;
+        };
+
+        match &mut self.curve {
+            Curve::Constant { dx, dy, .. } => {
+                *dx *= factor;
+                *dy *= factor;
+            }
+            Curve::Linear { from, to, .. } | Curve::Exponential { from, to, .. } => {
+                scale_rate(from);
+                scale_rate(to);
+            }
+            Curve::Piecewise { points } => {
+                for point in points {
+                    point.dx *= factor;
+                    point.dy *= factor;
+                }
+            }
+        }
+    }
+
+    /// Returns the length of the curve in milliseconds
+    fn duration(&self) -> u64 {
+        match &self.curve {
+            Curve::Constant { duration_ms, .. }
+            | Curve::Linear { duration_ms, .. }
+            | Curve::Exponential { duration_ms, .. } => *duration_ms,
+            Curve::Piecewise { points } => points.last().map_or(0, |point| point.time_ms),
+        }
+    }
+
+    /// Returns the movement per tick at the given time in milliseconds
+    fn rate_at(&self, time: f32) -> Rate {
+        match &self.curve {
+            Curve::Constant { dx, dy, .. } => Rate { dx: *dx, dy: *dy },
+            Curve::Linear {
+                duration_ms,
+                from,
+                to,
+            } => {
+                let progress = if *duration_ms > 0 {
+                    (time / *duration_ms as f32).clamp(0.0, 1.0)
+                } else {
+                    1.0
+                };
+                interpolate(*from, *to, progress)
+            }
+            Curve::Exponential {
+                from,
+                to,
+                time_constant_ms,
+                ..
+            } => interpolate(*from, *to, 1.0 - (-time / time_constant_ms).exp()),
+            Curve::Piecewise { points } => {
+                let next = points
+                    .iter()
+                    .position(|point| point.time_ms as f32 > time)
+                    .unwrap_or(points.len() - 1);
+                let end = points[next];
+                let start = points[next.saturating_sub(1)];
+                let span = end.time_ms.saturating_sub(start.time_ms) as f32;
+                let progress = if span > 0.0 {
+                    ((time - start.time_ms as f32) / span).clamp(0.0, 1.0)
+                } else {
+                    1.0
+                };
+                interpolate(
+                    Rate {
+                        dx: start.dx,
+                        dy: start.dy,
+                    },
+                    Rate {
+                        dx: end.dx,
+                        dy: end.dy,
+                    },
+                    progress,
+                )
+            }
+        }
+    }
+
+    /// Checks that the parameters describe a curve of at most `MAX_STEPS` slices
+    fn validate(&self) -> Result<(), String> {
+        // Slices are only merged after they are generated, so each one counts
+        let slices = self.duration().div_ceil(self.step_ms.max(1));
+        if slices > MAX_STEPS as u64 {
+            return Err(format!(
+                "the curve is cut into {} steps, at most {} are allowed",
+                slices, MAX_STEPS
+            ));
+        }
+
+        match &self.curve {
+            Curve::Exponential {
+                time_constant_ms, ..
+            } if !(time_constant_ms.is_finite() && *time_constant_ms > 0.0) => Err(format!(
+                "the time constant must be positive, got {}",
+                time_constant_ms
+            )),
+            Curve::Piecewise { points } if points.is_empty() => {
+                Err("a piecewise curve needs at least one control point".to_string())
+            }
+            Curve::Piecewise { points } => {
+                match points
+                    .windows(2)
+                    .find(|pair| pair[1].time_ms < pair[0].time_ms)
+                {
+                    Some(pair) => Err(format!(
+                        "control points must be in order of time, {} ms follows {} ms",
+                        pair[1].time_ms, pair[0].time_ms
+                    )),
+                    None => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl PatternCollection {
+    /// Replaces the steps of every pattern with a generator by the generated ones
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or an error naming the pattern whose generator is invalid
+    pub fn generate_steps(&mut self) -> Result<(), String> {
+        for (name, pattern) in &mut self.patterns {
+            if let Some(generator) = &pattern.generator {
+                pattern.config = generator
+                    .generate()
+                    .map_err(|e| format!("Invalid generator of pattern '{}': {}", name, e))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the movement between `from` and `to` at the given progress
+fn interpolate(from: Rate, to: Rate, progress: f32) -> Rate {
+    Rate {
+        dx: from.dx + (to.dx - from.dx) * progress,
+        dy: from.dy + (to.dy - from.dy) * progress,
+    }
+}
+
+/// Creates a step with the given movement
+fn step(duration: u64, dx: i32, dy: i32) -> Step {
+    Step {
+        duration,
+        dx,
+        dy,
+        adjusted_dx: 0,
+        adjusted_dy: 0,
+    }
+}
@@ -1,6 +1,19 @@
 mod collection;
+mod convert;
+mod csv;
+mod generator;
+mod notation;
+mod simplify;
 mod types;
 
 pub use collection::PatternCollection;
+pub use convert::{conversion_factor, convert_pattern, ConversionReport, RoundingLoss};
+pub use csv::{parse_csv, CsvConflict, CsvError, CsvPattern, MergeReport};
+pub use generator::{ControlPoint, Curve, Generator, Rate};
+pub use notation::{format_steps, parse_steps, StepsParseError, MAX_REPEAT_COUNT, MAX_STEPS};
+pub use simplify::{simplify_steps, SimplifyReport};
 // Re-export the types for use by other modules
-pub use types::{Pattern, Sensitivity, Step, Steps};
+pub use types::{
+    ActivationRule, FireRate, GameSensitivity, GameSettings, Modifier, Offset, Pattern,
+    Script, SemiAuto, Sensitivity, Step, Steps, TransformSettings,
+};
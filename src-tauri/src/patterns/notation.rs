@@ -0,0 +1,276 @@
+use crate::patterns::types::{Step, Steps};
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Largest repeat count of a single step
+pub const MAX_REPEAT_COUNT: usize = 1_000;
+
+/// Largest number of steps a step list may expand to
+pub const MAX_STEPS: usize = 10_000;
+
+/// Error found while parsing a step list written in the compact notation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepsParseError {
+    /// Column of the offending character, starting at 1
+    pub column: usize,
+    /// Description of what was expected
+    pub message: String,
+}
+
+impl fmt::Display for StepsParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid steps at column {}: {}",
+            self.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for StepsParseError {}
+
+/// Parses a step list written in the compact notation
+///
+/// Steps are separated by `;` and written as `<duration>ms: <dx>,<dy>`, with an
+/// optional repeat count after the duration, e.g. `1000ms: -5,122; 700ms x3: 0,200`.
+/// Whitespace between tokens and a trailing `;` are allowed. Repeat counts are
+/// limited to `MAX_REPEAT_COUNT` and the whole list to `MAX_STEPS` steps.
+///
+/// # Arguments
+/// * `text` - The step list in the compact notation
+///
+/// # Returns
+/// The parsed steps, or an error with the column of the first invalid character
+pub fn parse_steps(text: &str) -> Result<Steps, StepsParseError> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        position: 0,
+    };
+    let mut steps = Vec::new();
+
+    parser.skip_whitespace();
+    while !parser.at_end() {
+        parser.parse_step(&mut steps)?;
+
+        parser.skip_whitespace();
+        if parser.at_end() {
+            break;
+        }
+        parser.expect(';', "expected `;` between steps")?;
+        parser.skip_whitespace();
+    }
+
+    Ok(steps)
+}
+
+/// Writes a step list in the compact notation
+///
+/// Consecutive identical steps are written once with a repeat count, so
+/// parsing the result gives back the same steps. Runs longer than
+/// `MAX_REPEAT_COUNT` are split.
+///
+/// # Arguments
+/// * `steps` - The steps to write
+pub fn format_steps(steps: &[Step]) -> String {
+    let mut parts = Vec::new();
+    let mut index = 0;
+
+    while index < steps.len() {
+        let step = &steps[index];
+        let count = steps[index..]
+            .iter()
+            .take(MAX_REPEAT_COUNT)
+            .take_while(|other| {
+                other.duration == step.duration && other.dx == step.dx && other.dy == step.dy
+            })
+            .count();
+
+        parts.push(if count > 1 {
+            format!("{}ms x{}: {},{}", step.duration, count, step.dx, step.dy)
+        } else {
+            format!("{}ms: {},{}", step.duration, step.dx, step.dy)
+        });
+        index += count;
+    }
+
+    parts.join("; ")
+}
+
+/// Cursor over the characters of a step list
+struct Parser {
+    /// Characters of the step list
+    chars: Vec<char>,
+    /// Index of the next character to read
+    position: usize,
+}
+
+impl Parser {
+    /// Parses a single step with its optional repeat count
+    fn parse_step(&mut self, steps: &mut Steps) -> Result<(), StepsParseError> {
+        let start = self.column();
+        let duration = self.parse_number("expected a duration in milliseconds")?;
+        self.skip_whitespace();
+        self.expect('m', "expected `ms` after the duration")?;
+        self.expect('s', "expected `ms` after the duration")?;
+        self.skip_whitespace();
+
+        let mut count: usize = 1;
+        if self.peek() == Some('x') {
+            self.position += 1;
+            self.skip_whitespace();
+            let column = self.column();
+            count = self.parse_number("expected a repeat count after `x`")?;
+            if count == 0 {
+                return Err(self.error_at(column, "the repeat count must be at least 1"));
+            }
+            if count > MAX_REPEAT_COUNT {
+                return Err(self.error_at(
+                    column,
+                    &format!("the repeat count must be at most {}", MAX_REPEAT_COUNT),
+                ));
+            }
+            self.skip_whitespace();
+        }
+        if steps.len() + count > MAX_STEPS {
+            return Err(self.error_at(
+                start,
+                &format!("the steps expand to more than {} steps", MAX_STEPS),
+            ));
+        }
+
+        self.expect(':', "expected `:` after the duration")?;
+        self.skip_whitespace();
+        let dx = self.parse_number("expected a horizontal movement")?;
+        self.skip_whitespace();
+        self.expect(
+            ',',
+            "expected `,` between the horizontal and vertical movement",
+        )?;
+        self.skip_whitespace();
+        let dy = self.parse_number("expected a vertical movement")?;
+
+        for _ in 0..count {
+            steps.push(Step {
+                duration,
+                dx,
+                dy,
+                adjusted_dx: 0,
+                adjusted_dy: 0,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Parses an integer with an optional sign
+    fn parse_number<T: std::str::FromStr>(&mut self, expected: &str) -> Result<T, StepsParseError> {
+        let column = self.column();
+        let start = self.position;
+        if matches!(self.peek(), Some('-') | Some('+')) {
+            self.position += 1;
+        }
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.position += 1;
+        }
+
+        let text: String = self.chars[start..self.position].iter().collect();
+        if !text.ends_with(|c: char| c.is_ascii_digit()) {
+            self.position = start;
+            return Err(self.error_at(column, expected));
+        }
+
+        text.parse()
+            .map_err(|_| self.error_at(column, &format!("`{}` is out of range", text)))
+    }
+
+    /// Consumes the given character or fails with the message
+    fn expect(&mut self, expected: char, message: &str) -> Result<(), StepsParseError> {
+        if self.peek() == Some(expected) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.error_at(self.column(), message))
+        }
+    }
+
+    /// Skips spaces, tabs and line breaks
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    /// Returns the next character without consuming it
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    /// Returns whether all characters were read
+    fn at_end(&self) -> bool {
+        self.position >= self.chars.len()
+    }
+
+    /// Returns the column of the next character, starting at 1
+    fn column(&self) -> usize {
+        self.position + 1
+    }
+
+    /// Creates an error that points at the given column
+    fn error_at(&self, column: usize, message: &str) -> StepsParseError {
+        StepsParseError {
+            column,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Step list that is read from either a JSON array or the compact notation
+struct StepList(Steps);
+
+impl<'de> Deserialize<'de> for StepList {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct StepListVisitor;
+
+        impl<'de> Visitor<'de> for StepListVisitor {
+            type Value = StepList;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a list of steps or a string in the step notation")
+            }
+
+            fn visit_str<E: de::Error>(self, text: &str) -> Result<StepList, E> {
+                parse_steps(text).map(StepList).map_err(E::custom)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<StepList, A::Error> {
+                let mut steps = Vec::new();
+                while let Some(step) = seq.next_element()? {
+                    steps.push(step);
+                }
+                Ok(StepList(steps))
+            }
+        }
+
+        deserializer.deserialize_any(StepListVisitor)
+    }
+}
+
+/// Deserializes a step list from a JSON array or the compact notation
+pub(crate) fn deserialize_steps<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Steps, D::Error> {
+    StepList::deserialize(deserializer).map(|list| list.0)
+}
+
+/// Deserializes named step lists, each from a JSON array or the compact notation
+pub(crate) fn deserialize_step_map<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<String, Steps>, D::Error> {
+    let lists = HashMap::<String, StepList>::deserialize(deserializer)?;
+    Ok(lists
+        .into_iter()
+        .map(|(name, list)| (name, list.0))
+        .collect())
+}
@@ -0,0 +1,424 @@
+use crate::patterns::collection::PatternCollection;
+use crate::patterns::types::{Step, Steps};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Summary of the simplification of a list of steps
+#[derive(Debug, Clone, Serialize)]
+pub struct SimplifyReport {
+    /// Number of steps before the simplification
+    pub original_steps: usize,
+    /// Number of steps after the simplification
+    pub steps: usize,
+    /// Largest distance in pixels between the original and the simplified path at the same time
+    pub max_error: f32,
+    /// Distance in pixels between the end points of the original and the simplified path
+    pub end_error: f32,
+}
+
+/// Point of the cumulative path of a pattern
+#[derive(Debug, Clone, Copy)]
+struct PathPoint {
+    /// Iterations of the controller since the start of the pattern
+    time: f64,
+    /// Horizontal position in pixels
+    x: f64,
+    /// Vertical position in pixels
+    y: f64,
+}
+
+/// Movement the controller sends for a number of consecutive iterations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Run {
+    /// Number of iterations the movement is sent for
+    ticks: u64,
+    /// Horizontal movement per iteration in pixels
+    dx: i32,
+    /// Vertical movement per iteration in pixels
+    dy: i32,
+}
+
+/// Shortest part a segment is split into when it alternates between two rates
+const MIN_PART_TICKS: u64 = 2;
+
+/// Reduces a list of steps to fewer steps that follow the same path within a tolerance
+///
+/// The steps are turned into the cumulative path the controller sends, where
+/// each step is applied once per iteration until its duration has elapsed.
+/// The Douglas–Peucker algorithm keeps only the corners of the path that are
+/// further than `tolerance` from the path without them, measured at the same
+/// iteration. Each remaining segment is sent with whole-pixel rates and
+/// neighbouring steps with the same rate are merged. Corners are added back
+/// while the whole-pixel rates leave the path further than the tolerance. The
+/// last step is kept unchanged because the controller keeps applying it once
+/// the pattern has ended. The steps are returned unchanged when the tolerance
+/// is zero or when the simplified steps would not be fewer.
+///
+/// # Arguments
+/// * `steps` - The steps to simplify
+/// * `tick` - Time between two iterations of the controller
+/// * `tolerance` - Largest allowed distance in pixels from the original path
+///
+/// # Returns
+/// The simplified steps together with the error they introduced
+pub fn simplify_steps(steps: &[Step], tick: Duration, tolerance: f32) -> (Steps, SimplifyReport) {
+    // The controller never runs faster than once per millisecond
+    let tick = tick.max(Duration::from_millis(1));
+    let tolerance = tolerance.max(0.0) as f64;
+
+    // The last step keeps being applied after its duration, so it is kept as it is,
+    // and nothing may change without any tolerance
+    let Some((last, body)) = steps.split_last().filter(|_| tolerance > 0.0) else {
+        let report = SimplifyReport {
+            original_steps: steps.len(),
+            steps: steps.len(),
+            max_error: 0.0,
+            end_error: 0.0,
+        };
+        return (steps.to_vec(), report);
+    };
+    let path = cumulative_path(&runs_of(body, tick));
+
+    // The start and the end of the path are always kept
+    let mut keep = vec![false; path.len()];
+    keep[0] = true;
+    keep[path.len() - 1] = true;
+    douglas_peucker(&path, 0, path.len() - 1, tolerance, &mut keep);
+
+    let runs = loop {
+        let runs = runs_through(&path, &keep, tolerance);
+        let result = cumulative_path(&runs);
+
+        let (time, error) = furthest_point(&path, &result);
+        if error <= tolerance {
+            break runs;
+        }
+
+        // Keep the corner the whole-pixel steps miss by the most, or the last corner
+        // before the furthest point when every corner is missed by less than the tolerance
+        let missed = |index: &usize| distance(path[*index], position_at(&result, path[*index].time));
+        let corner = (1..path.len())
+            .filter(|index| !keep[*index])
+            .max_by(|a, b| missed(a).total_cmp(&missed(b)))
+            .filter(|index| missed(index) > tolerance)
+            .or_else(|| {
+                (1..path.len())
+                    .rev()
+                    .find(|index| !keep[*index] && path[*index].time < time)
+            });
+
+        // Once every corner is kept the path is followed exactly
+        match corner {
+            Some(index) => keep[index] = true,
+            None => break runs,
+        }
+    };
+
+    let mut simplified = steps_of(&runs, tick);
+    match simplified.last_mut() {
+        Some(previous) if previous.dx == last.dx && previous.dy == last.dy => {
+            previous.duration += last.duration
+        }
+        _ => simplified.push(last.clone()),
+    }
+
+    // Alternating rates may need more steps than they replace
+    if simplified.len() >= steps.len() {
+        return simplify_steps(steps, tick, 0.0);
+    }
+
+    let original = cumulative_path(&runs_of(steps, tick));
+    let result = cumulative_path(&runs_of(&simplified, tick));
+    let report = SimplifyReport {
+        original_steps: steps.len(),
+        steps: simplified.len(),
+        max_error: max_distance(&original, &result) as f32,
+        end_error: distance(original[original.len() - 1], result[result.len() - 1]) as f32,
+    };
+
+    (simplified, report)
+}
+
+impl PatternCollection {
+    /// Simplifies the steps of all patterns of the collection
+    ///
+    /// Only the duration-based steps are simplified; fire-rate offsets, semi-automatic
+    /// impulses and modifier variants are left unchanged. Patterns with a generator
+    /// are skipped, since their steps are generated again when loaded.
+    ///
+    /// # Arguments
+    /// * `tick` - Time between two iterations of the controller
+    /// * `tolerance` - Largest allowed distance in pixels from the original path
+    ///
+    /// # Returns
+    /// The simplified collection and a report for every pattern with steps
+    pub fn simplify(
+        &self,
+        tick: Duration,
+        tolerance: f32,
+    ) -> (PatternCollection, BTreeMap<String, SimplifyReport>) {
+        let mut simplified = self.clone();
+        let mut reports = BTreeMap::new();
+
+        for (name, pattern) in &mut simplified.patterns {
+            if pattern.config.is_empty() || pattern.generator.is_some() {
+                continue;
+            }
+
+            let (steps, report) = simplify_steps(&pattern.config, tick, tolerance);
+            pattern.config = steps;
+            reports.insert(name.clone(), report);
+        }
+
+        (simplified, reports)
+    }
+}
+
+/// Returns the movements the controller sends for the steps
+///
+/// A step is sent on every iteration until its duration has elapsed, including
+/// the iteration on which it elapses. The controller then starts measuring the
+/// next step from that iteration, so the first step is sent one more time than
+/// the following ones and a step without duration is still sent once.
+fn runs_of(steps: &[Step], tick: Duration) -> Vec<Run> {
+    let tick_ns = tick.as_nanos();
+
+    steps
+        .iter()
+        .enumerate()
+        .map(|(index, step)| {
+            let duration_ns = step.duration as u128 * 1_000_000;
+            let ticks = duration_ns.div_ceil(tick_ns) as u64;
+            Run {
+                ticks: if index == 0 { ticks + 1 } else { ticks.max(1) },
+                dx: step.dx,
+                dy: step.dy,
+            }
+        })
+        .collect()
+}
+
+/// Returns the steps the controller sends as the given movements
+///
+/// This is the inverse of `runs_of` for runs of at least one iteration.
+fn steps_of(runs: &[Run], tick: Duration) -> Steps {
+    let tick_ns = tick.as_nanos();
+
+    runs.iter()
+        .enumerate()
+        .map(|(index, run)| {
+            let ticks = if index == 0 { run.ticks - 1 } else { run.ticks };
+            Step {
+                duration: (ticks as u128 * tick_ns / 1_000_000) as u64,
+                dx: run.dx,
+                dy: run.dy,
+                adjusted_dx: 0,
+                adjusted_dy: 0,
+            }
+        })
+        .collect()
+}
+
+/// Returns the corners of the path the movements follow, starting at the origin
+fn cumulative_path(runs: &[Run]) -> Vec<PathPoint> {
+    let mut point = PathPoint {
+        time: 0.0,
+        x: 0.0,
+        y: 0.0,
+    };
+    let mut path = vec![point];
+
+    for run in runs {
+        let ticks = run.ticks as f64;
+        point.time += ticks;
+        point.x += run.dx as f64 * ticks;
+        point.y += run.dy as f64 * ticks;
+        path.push(point);
+    }
+
+    path
+}
+
+/// Returns the whole-pixel movements that pass through the kept corners of a path
+fn runs_through(path: &[PathPoint], keep: &[bool], tolerance: f64) -> Vec<Run> {
+    let corners: Vec<PathPoint> = path
+        .iter()
+        .zip(keep)
+        .filter(|(_, keep)| **keep)
+        .map(|(point, _)| *point)
+        .collect();
+
+    // Each axis may drift by its share of the tolerance, but by at least half a pixel
+    let axis_tolerance = (tolerance / std::f64::consts::SQRT_2).max(0.5);
+
+    // Rates are calculated from the emitted position, so rounding does not drift
+    let mut runs: Vec<Run> = Vec::new();
+    let (mut x, mut y) = (0.0, 0.0);
+    for segment in corners.windows(2) {
+        let ticks = (segment[1].time - segment[0].time).round() as u64;
+        if ticks == 0 {
+            continue;
+        }
+
+        let movement = (segment[1].x - x, segment[1].y - y);
+        for part in dither(movement, ticks, axis_tolerance) {
+            x += part.dx as f64 * part.ticks as f64;
+            y += part.dy as f64 * part.ticks as f64;
+
+            match runs.last_mut() {
+                Some(last) if last.dx == part.dx && last.dy == part.dy => last.ticks += part.ticks,
+                _ => runs.push(part),
+            }
+        }
+    }
+
+    runs
+}
+
+/// Spreads a movement over a number of iterations using the whole-pixel rates around its rate
+///
+/// Each axis alternates between the next lower and the next higher rate,
+/// switching whenever it would drift further than the tolerance from the exact
+/// movement. Switches are only made on iterations that leave every part at
+/// least `MIN_PART_TICKS` long, unless the movement itself is shorter.
+fn dither(movement: (f64, f64), ticks: u64, tolerance: f64) -> Vec<Run> {
+    let rate = (movement.0 / ticks as f64, movement.1 / ticks as f64);
+
+    // Whole-pixel rates never drift
+    let low = |rate: f64| {
+        if (rate - rate.round()).abs() < 1e-6 {
+            rate.round()
+        } else {
+            rate.floor()
+        }
+    };
+    let low = (low(rate.0), low(rate.1));
+    let mut current = low;
+
+    let mut parts = vec![Run {
+        ticks: 0,
+        dx: current.0 as i32,
+        dy: current.1 as i32,
+    }];
+    let mut drift = (0.0, 0.0);
+    for elapsed in 0..ticks {
+        let started = parts[parts.len() - 1].ticks >= MIN_PART_TICKS;
+        if started && ticks - elapsed >= MIN_PART_TICKS {
+            // Switch to the rate that brings the drift back towards the exact movement
+            let next = |current: f64, low: f64, rate: f64, drift: f64| {
+                let drift = drift + current - rate;
+                if drift.abs() <= tolerance {
+                    current
+                } else if drift > 0.0 {
+                    low
+                } else {
+                    low + 1.0
+                }
+            };
+            let next = (
+                next(current.0, low.0, rate.0, drift.0),
+                next(current.1, low.1, rate.1, drift.1),
+            );
+
+            if next != current {
+                current = next;
+                parts.push(Run {
+                    ticks: 0,
+                    dx: current.0 as i32,
+                    dy: current.1 as i32,
+                });
+            }
+        }
+
+        let last = parts.len() - 1;
+        parts[last].ticks += 1;
+        drift.0 += current.0 - rate.0;
+        drift.1 += current.1 - rate.1;
+    }
+
+    parts
+}
+
+/// Marks the corners between `first` and `last` that are needed to stay within the tolerance
+fn douglas_peucker(
+    path: &[PathPoint],
+    first: usize,
+    last: usize,
+    tolerance: f64,
+    keep: &mut [bool],
+) {
+    if last <= first + 1 {
+        return;
+    }
+
+    // Find the corner furthest from the segment at the same point in time
+    let mut furthest = first;
+    let mut max_distance = 0.0;
+    for (index, point) in path.iter().enumerate().take(last).skip(first + 1) {
+        let distance = distance(*point, interpolate(path[first], path[last], point.time));
+        if distance > max_distance {
+            furthest = index;
+            max_distance = distance;
+        }
+    }
+
+    if max_distance > tolerance {
+        keep[furthest] = true;
+        douglas_peucker(path, first, furthest, tolerance, keep);
+        douglas_peucker(path, furthest, last, tolerance, keep);
+    }
+}
+
+/// Returns the position on the segment from `start` to `end` at the given time
+fn interpolate(start: PathPoint, end: PathPoint, time: f64) -> PathPoint {
+    let span = end.time - start.time;
+    let progress = if span > 0.0 {
+        ((time - start.time) / span).clamp(0.0, 1.0)
+    } else {
+        1.0
+    };
+
+    PathPoint {
+        time,
+        x: start.x + (end.x - start.x) * progress,
+        y: start.y + (end.y - start.y) * progress,
+    }
+}
+
+/// Returns the position on a path at the given time
+fn position_at(path: &[PathPoint], time: f64) -> PathPoint {
+    let segment = path
+        .windows(2)
+        .find(|segment| time <= segment[1].time)
+        .unwrap_or(&path[path.len().saturating_sub(2)..]);
+
+    match segment {
+        [start, end] => interpolate(*start, *end, time),
+        _ => path[0],
+    }
+}
+
+/// Returns the largest distance between two paths at the same point in time
+fn max_distance(a: &[PathPoint], b: &[PathPoint]) -> f64 {
+    furthest_point(a, b).1
+}
+
+/// Returns the point in time at which two paths are the furthest apart, and their distance
+///
+/// Both paths are linear between their corners, so the largest distance is
+/// always found at a corner of one of them.
+fn furthest_point(a: &[PathPoint], b: &[PathPoint]) -> (f64, f64) {
+    a.iter()
+        .chain(b)
+        .map(|point| {
+            let distance = distance(position_at(a, point.time), position_at(b, point.time));
+            (point.time, distance)
+        })
+        .fold((0.0, 0.0), |furthest, point| if point.1 > furthest.1 { point } else { furthest })
+}
+
+/// Returns the distance between two points
+fn distance(a: PathPoint, b: PathPoint) -> f64 {
+    (a.x - b.x).hypot(a.y - b.y)
+}
@@ -1,4 +1,9 @@
+use crate::keyboard_listener::{keys, Key};
+use crate::patterns::generator::Generator;
+use crate::patterns::notation::{deserialize_step_map, deserialize_steps};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
 
 /// Represents a single step in a mouse movement pattern.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,18 +35,328 @@ fn default_to_dy() -> i32 {
 /// Represents sensitivity settings for mouse movements.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Sensitivity {
-    /// Horizontal sensitivity (1.0 is lowest, higher values reduce movement)
+    /// Horizontal sensitivity (higher values reduce movement, values below 1.0 increase it)
     pub x: f32,
-    /// Vertical sensitivity (1.0 is lowest, higher values reduce movement)
+    /// Vertical sensitivity (higher values reduce movement, values below 1.0 increase it)
     pub y: f32,
 }
 
+/// Represents the mouse and in-game settings of a player.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameSettings {
+    /// Mouse resolution in counts per inch
+    ///
+    /// Injected movements are sent as counts, so the resolution does not change
+    /// how far they turn the view; it is kept to describe the physical setup.
+    pub dpi: f32,
+    /// In-game sensitivity
+    pub sensitivity: f32,
+    /// In-game multiplier applied to the sensitivity while aiming down sights
+    #[serde(default = "default_ads_multiplier")]
+    pub ads_multiplier: f32,
+    /// Field of view in degrees, for games that scale the sensitivity with it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fov: Option<f32>,
+}
+
+// Default function for ads_multiplier that leaves the sensitivity unchanged
+fn default_ads_multiplier() -> f32 {
+    1.0
+}
+
+impl GameSettings {
+    /// Returns how far one count turns the view compared to the given settings
+    ///
+    /// The field of view is only taken into account when both settings define it.
+    ///
+    /// # Arguments
+    /// * `other` - The settings to compare with
+    pub fn turn_ratio(&self, other: &GameSettings) -> f32 {
+        let fov_ratio = match (self.fov, other.fov) {
+            (Some(fov), Some(other_fov)) => {
+                (fov.to_radians() / 2.0).tan() / (other_fov.to_radians() / 2.0).tan()
+            }
+            _ => 1.0,
+        };
+
+        (self.sensitivity * self.ads_multiplier) / (other.sensitivity * other.ads_multiplier)
+            * fov_ratio
+    }
+}
+
+/// Represents the settings patterns were tuned at and the settings of the current player.
+///
+/// Patterns are stored in counts at the reference settings, and the sensitivity
+/// divisor is scaled automatically so they turn the view just as far for the
+/// current player.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameSensitivity {
+    /// Settings the patterns were tuned at
+    pub reference: GameSettings,
+    /// Settings of the current player
+    pub current: GameSettings,
+}
+
+impl GameSensitivity {
+    /// Returns the factor applied to the sensitivity divisor for the current player
+    ///
+    /// A player whose view turns further per count needs fewer counts, so the
+    /// divisor grows by the same ratio.
+    ///
+    /// # Arguments
+    /// * `baseline` - Settings the current pattern was tuned at, replacing the reference when set
+    pub fn divisor_scale(&self, baseline: Option<&GameSettings>) -> f32 {
+        let scale = self
+            .current
+            .turn_ratio(baseline.unwrap_or(&self.reference));
+        if scale.is_finite() && scale > 0.0 {
+            scale
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Represents the transforms applied to every movement after sensitivity.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TransformSettings {
+    /// Global strength of the compensation in percent
+    pub strength: f32,
+    /// Horizontal movement multiplier
+    pub scale_x: f32,
+    /// Vertical movement multiplier
+    pub scale_y: f32,
+    /// Whether the horizontal movement is inverted
+    pub invert_x: bool,
+    /// Whether the vertical movement is inverted
+    pub invert_y: bool,
+    /// Maximum horizontal movement per tick in pixels, unlimited when not set
+    pub max_dx: Option<i32>,
+    /// Maximum vertical movement per tick in pixels, unlimited when not set
+    pub max_dy: Option<i32>,
+}
+
+impl Default for TransformSettings {
+    /// Leaves the movement unchanged
+    fn default() -> Self {
+        Self {
+            strength: 100.0,
+            scale_x: 1.0,
+            scale_y: 1.0,
+            invert_x: false,
+            invert_y: false,
+            max_dx: None,
+            max_dy: None,
+        }
+    }
+}
+
+/// Represents the condition under which recoil compensation is active.
+///
+/// Rules can be nested to express any combination of keys and mouse buttons, e.g.
+/// `{"all": [{"key": "LButton"}, {"toggle": "RButton"}]}` for toggle-ADS users.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivationRule {
+    /// Active while the key or mouse button is held down
+    Key(Key),
+    /// Active after an odd number of presses of the key (e.g. toggle ADS)
+    Toggle(Key),
+    /// Active when all nested rules are active
+    All(Vec<ActivationRule>),
+    /// Active when at least one nested rule is active
+    Any(Vec<ActivationRule>),
+    /// Active when the nested rule is not active
+    Not(Box<ActivationRule>),
+}
+
+impl Default for ActivationRule {
+    /// Both left and right mouse buttons must be held down
+    fn default() -> Self {
+        ActivationRule::All(vec![
+            ActivationRule::Key(keys::LBUTTON),
+            ActivationRule::Key(keys::RBUTTON),
+        ])
+    }
+}
+
+/// Represents a key that changes the compensation while held, e.g. crouch or prone.
+///
+/// While the modifier is active, patterns that define a variant with the same
+/// name switch to that variant, and the multiplier is applied to the movement
+/// either way. When several modifiers are active, the first one in the list wins.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Modifier {
+    /// Name of the modifier, matched against the variants of a pattern
+    pub name: String,
+    /// Condition under which the modifier is active
+    pub when: ActivationRule,
+    /// Multiplier applied to the movement while the modifier is active
+    #[serde(default = "default_multiplier")]
+    pub multiplier: f32,
+}
+
+// Default function for multiplier that leaves the movement unchanged
+fn default_multiplier() -> f32 {
+    1.0
+}
+
+/// Represents the recoil compensation applied for a single bullet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Offset {
+    /// Horizontal movement in pixels applied when the bullet is fired
+    pub dx: i32,
+    /// Vertical movement in pixels applied when the bullet is fired
+    pub dy: i32,
+    /// Pre-calculated adjusted horizontal movement based on sensitivity
+    #[serde(skip)]
+    pub adjusted_dx: i32,
+    /// Pre-calculated adjusted vertical movement based on sensitivity
+    #[serde(skip)]
+    pub adjusted_dy: i32,
+}
+
+/// Represents a fire-rate based pattern that compensates recoil bullet by bullet.
+///
+/// Unlike duration-based steps, each offset is applied once at the moment its
+/// bullet is fired, and compensation ends when the magazine is empty.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FireRate {
+    /// Weapon fire rate in rounds per minute
+    pub rpm: u32,
+    /// Number of bullets in a magazine
+    pub magazine: u32,
+    /// Per-bullet offsets; the last offset is reused when the table is shorter than the magazine
+    pub offsets: Vec<Offset>,
+}
+
+impl FireRate {
+    /// Returns the time between two consecutive bullets
+    pub fn bullet_interval(&self) -> Duration {
+        Duration::from_micros(60_000_000 / u64::from(self.rpm.max(1)))
+    }
+
+    /// Returns the offset applied for the bullet with the given index
+    pub fn offset_for(&self, bullet: u32) -> Option<&Offset> {
+        let index = (bullet as usize).min(self.offsets.len().checked_sub(1)?);
+        self.offsets.get(index)
+    }
+}
+
+/// Represents a semi-automatic pattern that compensates recoil shot by shot.
+///
+/// Each press of the fire button triggers one impulse, so the pattern fits
+/// weapons that fire once per click. The shot index advances with every press
+/// and starts over after the weapon has been idle for `reset_ms`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SemiAuto {
+    /// Per-shot impulses; the last impulse is reused when more shots are fired
+    pub shots: Vec<Offset>,
+    /// Time in milliseconds over which each impulse is spread, applied at once when zero
+    #[serde(default)]
+    pub pull_ms: u64,
+    /// Idle time in milliseconds after which the next shot uses the first impulse again
+    #[serde(default = "default_reset_ms")]
+    pub reset_ms: u64,
+}
+
+// Default function for reset_ms
+fn default_reset_ms() -> u64 {
+    500
+}
+
+impl SemiAuto {
+    /// Returns the time over which each impulse is spread
+    pub fn pull_duration(&self) -> Duration {
+        Duration::from_millis(self.pull_ms)
+    }
+
+    /// Returns the idle time after which the shot index starts over
+    pub fn reset_after(&self) -> Duration {
+        Duration::from_millis(self.reset_ms)
+    }
+
+    /// Returns the impulse applied for the shot with the given index
+    pub fn offset_for(&self, shot: u32) -> Option<&Offset> {
+        let index = (shot as usize).min(self.shots.len().checked_sub(1)?);
+        self.shots.get(index)
+    }
+}
+
+/// Represents a script that computes the movement of every tick of a pattern.
+///
+/// The script is written in Rhai and sees the time since activation, the
+/// number of bullets or shots fired and the active modifier. It returns the
+/// movement as `[dx, dy]`, or `()` to keep the movement of the pattern. When it
+/// fails or runs longer than its budget, the pattern is applied without it for
+/// the rest of the spray.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Script {
+    /// Source code of the script
+    pub source: String,
+    /// Longest time in microseconds a single evaluation may take
+    #[serde(default = "default_budget_us")]
+    pub budget_us: u64,
+}
+
+// Default function for budget_us
+fn default_budget_us() -> u64 {
+    1000
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Pattern {
-    /// A sequence of mouse movement steps that form a pattern
+    /// A sequence of mouse movement steps that form a pattern, given as a list or in the step notation
+    #[serde(default, deserialize_with = "deserialize_steps")]
     pub config: Steps,
+    /// Optional fire-rate based per-bullet pattern, used instead of `config` when present
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fire_rate: Option<FireRate>,
+    /// Optional parameters the `config` steps are generated from when the configuration is loaded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generator: Option<Generator>,
+    /// Optional script that computes the movement of every tick, falling back to the rest of the pattern
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub script: Option<Script>,
+    /// Optional per-click pattern, used instead of `fire_rate` and `config` when present
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub semi_auto: Option<SemiAuto>,
+    /// Optional activation rule that overrides the global one while this pattern is active
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub activation: Option<ActivationRule>,
+    /// Optional sensitivity that overrides the global one while this pattern is active
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sensitivity: Option<Sensitivity>,
+    /// Named scope multipliers applied to the sensitivity while the scope is selected;
+    /// values above 1.0 reduce movement
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub scopes: HashMap<String, f32>,
+    /// Time in milliseconds between activation and the start of the compensation;
+    /// not used by semi-automatic patterns
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub start_delay_ms: u64,
+    /// Time in milliseconds over which the compensation is scaled up to full strength;
+    /// not used by semi-automatic patterns
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub ramp_in_ms: u64,
+    /// Alternative steps by modifier name, used instead of the pattern while the modifier is active
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        deserialize_with = "deserialize_step_map"
+    )]
+    pub variants: HashMap<String, Steps>,
+    /// Game settings this pattern was tuned at, overriding the reference of the configuration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<GameSettings>,
     /// Images associated with this pattern
     pub images: Vec<String>,
 }
 
+// Skips optional durations that are not set
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
 pub type Steps = Vec<Step>;
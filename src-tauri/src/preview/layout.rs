@@ -0,0 +1,138 @@
+use crate::mouse_controller::{Trajectory, TrajectoryMove};
+use crate::preview::series::PreviewSeries;
+
+/// Colors of the series as RGB, reused in order when there are more series
+pub const PALETTE: [(u8, u8, u8); 4] = [
+    (220, 50, 47),
+    (38, 139, 210),
+    (133, 153, 0),
+    (211, 54, 130),
+];
+
+/// Background color of the image as RGB
+pub const BACKGROUND: (u8, u8, u8) = (255, 255, 255);
+
+/// Color of the axes through the starting point as RGB
+pub const AXES: (u8, u8, u8) = (200, 200, 200);
+
+/// Radius of the markers at step boundaries in pixels
+pub const MARKER_RADIUS: i32 = 3;
+
+/// Largest width and height of a rendered preview in pixels
+pub const MAX_PREVIEW_SIZE: u32 = 4096;
+
+/// Size of a rendered preview
+#[derive(Debug, Clone, Copy)]
+pub struct PreviewOptions {
+    /// Width of the image in pixels
+    pub width: u32,
+    /// Height of the image in pixels
+    pub height: u32,
+    /// Empty space around the paths in pixels
+    pub margin: u32,
+}
+
+impl Default for PreviewOptions {
+    fn default() -> Self {
+        Self {
+            width: 640,
+            height: 640,
+            margin: 40,
+        }
+    }
+}
+
+impl PreviewOptions {
+    /// Checks that the image is at least one and at most `MAX_PREVIEW_SIZE` pixels wide and high
+    ///
+    /// # Returns
+    /// `Ok(())` if the size is valid, or an error naming the invalid dimension
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, value) in [("width", self.width), ("height", self.height)] {
+            if value == 0 || value > MAX_PREVIEW_SIZE {
+                return Err(format!(
+                    "The preview {} must be between 1 and {} pixels, got {}",
+                    name, MAX_PREVIEW_SIZE, value
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Maps positions of the simulated paths to pixels of the image
+///
+/// All series share the same scale, so overlaid paths can be compared, and
+/// both axes use the same scale, so the shape of a path is not distorted.
+/// Positions are drawn in screen coordinates: downward compensation goes down.
+#[derive(Debug, Clone, Copy)]
+pub struct Projection {
+    /// Smallest horizontal position of all paths
+    min_x: i32,
+    /// Smallest vertical position of all paths
+    min_y: i32,
+    /// Pixels per unit of movement
+    scale: f32,
+    /// Horizontal pixel of the smallest position
+    offset_x: f32,
+    /// Vertical pixel of the smallest position
+    offset_y: f32,
+}
+
+impl Projection {
+    /// Fits all series into the image
+    pub fn fit(series: &[PreviewSeries], options: &PreviewOptions) -> Self {
+        // The starting point is always part of the drawing
+        let (mut min_x, mut max_x, mut min_y, mut max_y) = (0, 0, 0, 0);
+        for step in series.iter().flat_map(|series| &series.trajectory.moves) {
+            min_x = min_x.min(step.x);
+            max_x = max_x.max(step.x);
+            min_y = min_y.min(step.y);
+            max_y = max_y.max(step.y);
+        }
+
+        let span_x = (max_x - min_x).max(1) as f32;
+        let span_y = (max_y - min_y).max(1) as f32;
+        let available_x = options.width.saturating_sub(2 * options.margin).max(1) as f32;
+        let available_y = options.height.saturating_sub(2 * options.margin).max(1) as f32;
+        let scale = (available_x / span_x).min(available_y / span_y);
+
+        // Center the drawing in the image
+        Self {
+            min_x,
+            min_y,
+            scale,
+            offset_x: (options.width as f32 - span_x * scale) / 2.0,
+            offset_y: (options.height as f32 - span_y * scale) / 2.0,
+        }
+    }
+
+    /// Returns the pixel of a position
+    pub fn project(&self, x: i32, y: i32) -> (f32, f32) {
+        (
+            self.offset_x + (x - self.min_x) as f32 * self.scale,
+            self.offset_y + (y - self.min_y) as f32 * self.scale,
+        )
+    }
+
+    /// Returns the pixel of the starting point
+    pub fn origin(&self) -> (f32, f32) {
+        self.project(0, 0)
+    }
+}
+
+/// Returns the color of the series with the given index
+pub fn color(index: usize) -> (u8, u8, u8) {
+    PALETTE[index % PALETTE.len()]
+}
+
+/// Returns the movements that start a new step
+pub fn step_boundaries(trajectory: &Trajectory) -> impl Iterator<Item = &TrajectoryMove> {
+    let mut previous = None;
+    trajectory.moves.iter().filter(move |step| {
+        let boundary = previous != Some(step.step);
+        previous = Some(step.step);
+        boundary
+    })
+}
@@ -0,0 +1,79 @@
+/// Preview module for rendering simulated patterns as images.
+///
+/// This module draws the cumulative path the controller would move the mouse
+/// along, so step lists can be checked visually while tuning them.
+///
+/// # Structure
+///
+/// The module is organized into several components:
+/// - `series`: Simulated paths of patterns and sensitivities to compare
+/// - `layout`: Sizes and the projection shared by all renderers
+/// - `svg`: Renders the paths as an SVG document
+/// - `png`: Renders the paths as a PNG image with OpenCV
+///
+/// # Usage
+///
+/// The main entry point is the `render` function, which renders one or more
+/// simulated paths on top of each other in the requested format.
+mod layout;
+mod png;
+mod series;
+mod svg;
+
+use std::str::FromStr;
+
+pub use layout::{PreviewOptions, MAX_PREVIEW_SIZE};
+pub use series::{collect_series, PreviewSeries};
+pub use png::render_png;
+pub use svg::render_svg;
+
+/// Image format of a rendered preview
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewFormat {
+    /// Scalable vector graphics document
+    Svg,
+    /// Portable network graphics image
+    Png,
+}
+
+impl PreviewFormat {
+    /// Returns the format matching the extension of a file path
+    pub fn from_path(path: &str) -> Option<Self> {
+        let (_, extension) = path.rsplit_once('.')?;
+        extension.parse().ok()
+    }
+}
+
+impl FromStr for PreviewFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "svg" => Ok(PreviewFormat::Svg),
+            "png" => Ok(PreviewFormat::Png),
+            _ => Err(format!("Unknown preview format: {}", value)),
+        }
+    }
+}
+
+/// Renders simulated paths on top of each other
+///
+/// # Arguments
+/// * `series` - Paths to draw, each with its own color and legend entry
+/// * `options` - Size of the image, at most `MAX_PREVIEW_SIZE` pixels wide and high
+/// * `format` - Image format to render
+///
+/// # Returns
+/// The encoded image, or an error if the size is invalid or rendering fails
+pub fn render(
+    series: &[PreviewSeries],
+    options: &PreviewOptions,
+    format: PreviewFormat,
+) -> Result<Vec<u8>, String> {
+    options.validate()?;
+
+    match format {
+        PreviewFormat::Svg => Ok(render_svg(series, options).into_bytes()),
+        PreviewFormat::Png => render_png(series, options),
+    }
+}
@@ -0,0 +1,94 @@
+use opencv::core::{Mat, Point, Scalar, Vector, CV_8UC3};
+use opencv::{imgcodecs, imgproc};
+
+use crate::preview::layout::{
+    color, step_boundaries, PreviewOptions, Projection, AXES, BACKGROUND, MARKER_RADIUS,
+};
+use crate::preview::series::PreviewSeries;
+
+/// Renders the simulated paths as a PNG image
+///
+/// # Arguments
+/// * `series` - Paths to draw on top of each other
+/// * `options` - Size of the image
+///
+/// # Returns
+/// The encoded PNG image, or an error if drawing or encoding fails
+pub fn render_png(series: &[PreviewSeries], options: &PreviewOptions) -> Result<Vec<u8>, String> {
+    draw(series, options).map_err(|e| format!("Failed to render preview: {}", e))
+}
+
+/// Draws the paths with OpenCV and encodes the result
+fn draw(series: &[PreviewSeries], options: &PreviewOptions) -> Result<Vec<u8>, opencv::Error> {
+    let projection = Projection::fit(series, options);
+    let width = options.width as i32;
+    let height = options.height as i32;
+    let mut image = Mat::new_rows_cols_with_default(height, width, CV_8UC3, bgr(BACKGROUND))?;
+
+    // Axes through the starting point
+    let origin = point(projection.origin());
+    imgproc::line(
+        &mut image,
+        Point::new(0, origin.y),
+        Point::new(width, origin.y),
+        bgr(AXES),
+        1,
+        imgproc::LINE_8,
+        0,
+    )?;
+    imgproc::line(
+        &mut image,
+        Point::new(origin.x, 0),
+        Point::new(origin.x, height),
+        bgr(AXES),
+        1,
+        imgproc::LINE_8,
+        0,
+    )?;
+
+    for (index, series) in series.iter().enumerate() {
+        let color = bgr(color(index));
+
+        // Cumulative path, starting at the origin
+        let mut previous = origin;
+        for step in &series.trajectory.moves {
+            let current = point(projection.project(step.x, step.y));
+            imgproc::line(&mut image, previous, current, color, 2, imgproc::LINE_AA, 0)?;
+            previous = current;
+        }
+
+        // Markers at the first movement of every step
+        for step in step_boundaries(&series.trajectory) {
+            let center = point(projection.project(step.x, step.y));
+            imgproc::circle(&mut image, center, MARKER_RADIUS, color, -1, imgproc::LINE_AA, 0)?;
+        }
+
+        // Legend entry
+        imgproc::put_text(
+            &mut image,
+            &series.label,
+            Point::new(10, 20 + 18 * index as i32),
+            imgproc::FONT_HERSHEY_SIMPLEX,
+            0.5,
+            color,
+            1,
+            imgproc::LINE_AA,
+            false,
+        )?;
+    }
+
+    let mut buffer = Vector::<u8>::new();
+    imgcodecs::imencode(".png", &image, &mut buffer, &Vector::new())?;
+
+    Ok(buffer.to_vec())
+}
+
+/// Converts an RGB color to an OpenCV scalar, which is ordered BGR
+fn bgr((r, g, b): (u8, u8, u8)) -> Scalar {
+    Scalar::new(f64::from(b), f64::from(g), f64::from(r), 0.0)
+}
+
+/// Rounds a projected position to a pixel
+fn point((x, y): (f32, f32)) -> Point {
+    Point::new(x.round() as i32, y.round() as i32)
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+
+use crate::mouse_controller::Trajectory;
+use crate::patterns::{Pattern, Sensitivity};
+
+/// Simulated path drawn in a preview
+#[derive(Debug, Clone)]
+pub struct PreviewSeries {
+    /// Label shown in the legend
+    pub label: String,
+    /// Movements of the simulated path
+    pub trajectory: Trajectory,
+}
+
+/// Simulates every combination of the named patterns and sensitivities
+///
+/// When sensitivities are given, each one replaces the pattern's own
+/// sensitivity, so two sensitivities can be compared for the same pattern.
+///
+/// # Arguments
+/// * `patterns` - Map of pattern names to patterns
+/// * `names` - Names of the patterns to simulate
+/// * `sensitivities` - Sensitivities to compare, or empty to use the configured one
+/// * `simulate` - Simulates a single pattern
+///
+/// # Returns
+/// One series per combination, or an error if a pattern does not exist
+pub fn collect_series(
+    patterns: &HashMap<String, Pattern>,
+    names: &[String],
+    sensitivities: &[Sensitivity],
+    simulate: impl Fn(&Pattern) -> Trajectory,
+) -> Result<Vec<PreviewSeries>, String> {
+    let mut series = Vec::new();
+
+    for name in names {
+        let pattern = patterns
+            .get(name)
+            .ok_or_else(|| format!("Pattern not found: {}", name))?;
+
+        if sensitivities.is_empty() {
+            series.push(PreviewSeries {
+                label: name.clone(),
+                trajectory: simulate(pattern),
+            });
+            continue;
+        }
+
+        for sensitivity in sensitivities {
+            let mut pattern = pattern.clone();
+            pattern.sensitivity = Some(sensitivity.clone());
+
+            series.push(PreviewSeries {
+                label: format!("{} @ {}/{}", name, sensitivity.x, sensitivity.y),
+                trajectory: simulate(&pattern),
+            });
+        }
+    }
+
+    Ok(series)
+}
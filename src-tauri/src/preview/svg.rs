@@ -0,0 +1,95 @@
+use std::fmt::Write;
+
+use crate::preview::layout::{
+    color, step_boundaries, PreviewOptions, Projection, AXES, BACKGROUND, MARKER_RADIUS,
+};
+use crate::preview::series::PreviewSeries;
+
+/// Renders the simulated paths as an SVG document
+///
+/// # Arguments
+/// * `series` - Paths to draw on top of each other
+/// * `options` - Size of the image
+///
+/// # Returns
+/// The SVG document
+pub fn render_svg(series: &[PreviewSeries], options: &PreviewOptions) -> String {
+    let projection = Projection::fit(series, options);
+    let (origin_x, origin_y) = projection.origin();
+    let mut svg = String::new();
+
+    // Writing to a String never fails
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = options.width,
+        h = options.height
+    );
+    let _ = writeln!(
+        svg,
+        r#"<rect width="100%" height="100%" fill="{}"/>"#,
+        rgb(BACKGROUND)
+    );
+
+    // Axes through the starting point
+    let _ = writeln!(
+        svg,
+        r#"<line x1="0" y1="{y:.1}" x2="{w}" y2="{y:.1}" stroke="{c}"/><line x1="{x:.1}" y1="0" x2="{x:.1}" y2="{h}" stroke="{c}"/>"#,
+        x = origin_x,
+        y = origin_y,
+        w = options.width,
+        h = options.height,
+        c = rgb(AXES)
+    );
+
+    for (index, series) in series.iter().enumerate() {
+        let color = rgb(color(index));
+
+        // Cumulative path, starting at the origin
+        let mut points = format!("{:.1},{:.1}", origin_x, origin_y);
+        for step in &series.trajectory.moves {
+            let (x, y) = projection.project(step.x, step.y);
+            let _ = write!(points, " {:.1},{:.1}", x, y);
+        }
+        let _ = writeln!(
+            svg,
+            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="2"/>"#,
+            points, color
+        );
+
+        // Markers at the first movement of every step
+        for step in step_boundaries(&series.trajectory) {
+            let (x, y) = projection.project(step.x, step.y);
+            let _ = writeln!(
+                svg,
+                r#"<circle cx="{:.1}" cy="{:.1}" r="{}" fill="{}"><title>step {} at {} ms</title></circle>"#,
+                x, y, MARKER_RADIUS, color, step.step, step.time_ms
+            );
+        }
+
+        // Legend entry
+        let _ = writeln!(
+            svg,
+            r#"<text x="10" y="{}" font-family="sans-serif" font-size="14" fill="{}">{}</text>"#,
+            20 + 18 * index,
+            color,
+            escape(&series.label)
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Formats a color for SVG attributes
+fn rgb((r, g, b): (u8, u8, u8)) -> String {
+    format!("rgb({},{},{})", r, g, b)
+}
+
+/// Escapes text for use in SVG content
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
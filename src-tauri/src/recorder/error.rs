@@ -0,0 +1,26 @@
+use std::fmt;
+
+/// Error type for pattern recording operations
+#[derive(Debug)]
+pub enum RecorderError {
+    /// The activation rule was not satisfied before the start timeout
+    Timeout,
+    /// The recording did not contain any mouse movement
+    NoMovement,
+    /// The input source could not be started
+    SourceFailed(String),
+}
+
+impl fmt::Display for RecorderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecorderError::Timeout => write!(f, "Timed out waiting for the fire buttons"),
+            RecorderError::NoMovement => write!(f, "No mouse movement was recorded"),
+            RecorderError::SourceFailed(reason) => {
+                write!(f, "Failed to start the input source: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RecorderError {}
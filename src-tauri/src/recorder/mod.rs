@@ -0,0 +1,41 @@
+/// Recorder module for capturing patterns from live mouse movement.
+///
+/// This module records the raw mouse movement made while the fire buttons are
+/// held and turns it into the steps that compensate it.
+///
+/// # Structure
+///
+/// The module is organized into several components:
+/// - `session`: Records the movement and buckets it into steps
+/// - `source`: Input source abstraction, with a synthetic implementation
+/// - `raw_input`: Input source that reads raw mouse movement from the system, on Windows
+/// - `raw_input_unsupported`: Stand-in for the raw input source on other platforms
+/// - `error`: Contains error types for recording operations
+///
+/// # Usage
+///
+/// The main entry point is the `record` function, which reads from any
+/// `InputSource` until the activation rule is released.
+mod error;
+#[cfg(windows)]
+mod raw_input;
+#[cfg(not(windows))]
+mod raw_input_unsupported;
+mod session;
+mod source;
+
+/// Public exports from the recorder module
+///
+/// These are the primary types that users of this module will interact with:
+/// - `record`, `RecorderSettings`: Records movement while the activation rule is satisfied
+/// - `Recording`, `Sample`: Captured movement that can be turned into steps
+/// - `InputSource`, `SyntheticSource`: Input abstraction and its synthetic implementation
+/// - `RawInputSource`: Raw mouse movement of physical devices, only available on Windows
+/// - `RecorderError`: Errors of a recording
+pub use error::RecorderError;
+#[cfg(windows)]
+pub use raw_input::RawInputSource;
+#[cfg(not(windows))]
+pub use raw_input_unsupported::RawInputSource;
+pub use session::{record, RecorderSettings, Recording, Sample, DEFAULT_BUCKET_MS};
+pub use source::{InputSource, SyntheticSource};
@@ -0,0 +1,242 @@
+use std::mem;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+
+use log::{error, info};
+use winapi::shared::minwindef::{DWORD, LPARAM, LRESULT, UINT, WPARAM};
+use winapi::shared::windef::HWND;
+use winapi::um::libloaderapi::GetModuleHandleW;
+use winapi::um::processthreadsapi::GetCurrentThreadId;
+use winapi::um::winuser::{
+    CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW, GetMessageW, GetRawInputData,
+    PostThreadMessageW, RegisterClassW, RegisterRawInputDevices, TranslateMessage, HRAWINPUT,
+    HWND_MESSAGE, MOUSE_MOVE_ABSOLUTE, MSG, RAWINPUT, RAWINPUTDEVICE, RAWINPUTHEADER,
+    RIDEV_INPUTSINK, RIDEV_REMOVE, RID_INPUT, RIM_TYPEMOUSE, WM_INPUT, WM_QUIT, WNDCLASSW,
+};
+
+use crate::mouse_controller::is_key_pressed;
+use crate::recorder::error::RecorderError;
+use crate::recorder::source::InputSource;
+
+/// Horizontal movement accumulated since the last read
+static DELTA_X: AtomicI32 = AtomicI32::new(0);
+/// Vertical movement accumulated since the last read
+static DELTA_Y: AtomicI32 = AtomicI32::new(0);
+/// Whether a raw input source is currently running
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Input source that reads raw mouse movement from the system
+///
+/// A background thread owns a message-only window registered for raw mouse
+/// input and accumulates the relative movement of physical devices. Movement
+/// injected with `SendInput`, such as the controller's own compensation, is
+/// ignored. Key states are read with `GetAsyncKeyState`.
+///
+/// # Thread Safety
+///
+/// Only one source can run at a time because the window procedure writes to
+/// process-wide counters. When the source is dropped, the registration for raw
+/// input is removed and the thread is stopped and joined.
+pub struct RawInputSource {
+    /// Identifier of the thread that owns the window
+    thread_id: DWORD,
+    /// Handle of the thread that owns the window
+    thread_handle: Option<JoinHandle<()>>,
+}
+
+impl RawInputSource {
+    /// Starts reading raw mouse input
+    ///
+    /// # Returns
+    /// The running source, or an error if another source is running or the
+    /// window could not be registered for raw input
+    pub fn start() -> Result<Self, RecorderError> {
+        if RUNNING.swap(true, Ordering::SeqCst) {
+            return Err(RecorderError::SourceFailed(
+                "A recording is already running".to_string(),
+            ));
+        }
+
+        DELTA_X.store(0, Ordering::SeqCst);
+        DELTA_Y.store(0, Ordering::SeqCst);
+
+        let (sender, receiver) = mpsc::channel();
+        let thread_handle = thread::spawn(move || unsafe {
+            let window = match create_input_window() {
+                Ok(window) => window,
+                Err(e) => {
+                    let _ = sender.send(Err(e));
+                    return;
+                }
+            };
+            let _ = sender.send(Ok(GetCurrentThreadId()));
+
+            // Pump messages until the source is dropped
+            let mut message: MSG = mem::zeroed();
+            while GetMessageW(&mut message, ptr::null_mut(), 0, 0) > 0 {
+                TranslateMessage(&message);
+                DispatchMessageW(&message);
+            }
+
+            DestroyWindow(window);
+        });
+
+        match receiver.recv() {
+            Ok(Ok(thread_id)) => {
+                info!("Raw mouse input started");
+                Ok(Self {
+                    thread_id,
+                    thread_handle: Some(thread_handle),
+                })
+            }
+            Ok(Err(e)) => {
+                let _ = thread_handle.join();
+                RUNNING.store(false, Ordering::SeqCst);
+                Err(e)
+            }
+            Err(e) => {
+                let _ = thread_handle.join();
+                RUNNING.store(false, Ordering::SeqCst);
+                Err(RecorderError::SourceFailed(e.to_string()))
+            }
+        }
+    }
+}
+
+impl InputSource for RawInputSource {
+    fn is_key_pressed(&self, code: i32) -> bool {
+        is_key_pressed(code)
+    }
+
+    fn take_mouse_delta(&mut self) -> (i32, i32) {
+        (
+            DELTA_X.swap(0, Ordering::SeqCst),
+            DELTA_Y.swap(0, Ordering::SeqCst),
+        )
+    }
+}
+
+impl Drop for RawInputSource {
+    fn drop(&mut self) {
+        unsafe {
+            // Stop delivering raw input before the window goes away
+            let device = mouse_device(RIDEV_REMOVE, ptr::null_mut());
+            if RegisterRawInputDevices(&device, 1, mem::size_of::<RAWINPUTDEVICE>() as UINT) == 0 {
+                error!("Failed to unregister from raw mouse input");
+            }
+
+            PostThreadMessageW(self.thread_id, WM_QUIT, 0, 0);
+        }
+
+        if let Some(handle) = self.thread_handle.take() {
+            if handle.join().is_err() {
+                error!("Failed to join raw input thread");
+            }
+        }
+
+        RUNNING.store(false, Ordering::SeqCst);
+        info!("Raw mouse input stopped");
+    }
+}
+
+/// Creates a message-only window that receives raw mouse input even without focus
+unsafe fn create_input_window() -> Result<HWND, RecorderError> {
+    let class_name: Vec<u16> = "RecoilHelperRawInput\0".encode_utf16().collect();
+    let instance = GetModuleHandleW(ptr::null());
+
+    let class = WNDCLASSW {
+        style: 0,
+        lpfnWndProc: Some(window_proc),
+        cbClsExtra: 0,
+        cbWndExtra: 0,
+        hInstance: instance,
+        hIcon: ptr::null_mut(),
+        hCursor: ptr::null_mut(),
+        hbrBackground: ptr::null_mut(),
+        lpszMenuName: ptr::null(),
+        lpszClassName: class_name.as_ptr(),
+    };
+    // Registering fails harmlessly when the class exists from an earlier recording
+    RegisterClassW(&class);
+
+    let window = CreateWindowExW(
+        0,
+        class_name.as_ptr(),
+        ptr::null(),
+        0,
+        0,
+        0,
+        0,
+        0,
+        HWND_MESSAGE,
+        ptr::null_mut(),
+        instance,
+        ptr::null_mut(),
+    );
+    if window.is_null() {
+        return Err(RecorderError::SourceFailed(
+            "Failed to create raw input window".to_string(),
+        ));
+    }
+
+    let device = mouse_device(RIDEV_INPUTSINK, window);
+    if RegisterRawInputDevices(&device, 1, mem::size_of::<RAWINPUTDEVICE>() as UINT) == 0 {
+        DestroyWindow(window);
+        return Err(RecorderError::SourceFailed(
+            "Failed to register for raw mouse input".to_string(),
+        ));
+    }
+
+    Ok(window)
+}
+
+/// Describes the registration of a window for raw mouse input
+///
+/// # Arguments
+/// * `flags` - Registration flags, `RIDEV_REMOVE` to unregister
+/// * `window` - Window that receives the input, null when unregistering
+fn mouse_device(flags: DWORD, window: HWND) -> RAWINPUTDEVICE {
+    // Generic desktop page, mouse usage
+    RAWINPUTDEVICE {
+        usUsagePage: 0x01,
+        usUsage: 0x02,
+        dwFlags: flags,
+        hwndTarget: window,
+    }
+}
+
+/// Accumulates the relative movement of physical mice
+unsafe extern "system" fn window_proc(
+    window: HWND,
+    message: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    if message == WM_INPUT {
+        let mut input: RAWINPUT = mem::zeroed();
+        let mut size = mem::size_of::<RAWINPUT>() as UINT;
+        let read = GetRawInputData(
+            lparam as HRAWINPUT,
+            RID_INPUT,
+            &mut input as *mut RAWINPUT as *mut _,
+            &mut size,
+            mem::size_of::<RAWINPUTHEADER>() as UINT,
+        );
+
+        // Injected input has no device handle and absolute movement is not a delta
+        if read != UINT::MAX
+            && input.header.dwType == RIM_TYPEMOUSE
+            && !input.header.hDevice.is_null()
+        {
+            let mouse = input.data.mouse();
+            if mouse.usFlags & MOUSE_MOVE_ABSOLUTE == 0 {
+                DELTA_X.fetch_add(mouse.lLastX, Ordering::SeqCst);
+                DELTA_Y.fetch_add(mouse.lLastY, Ordering::SeqCst);
+            }
+        }
+    }
+
+    DefWindowProcW(window, message, wparam, lparam)
+}
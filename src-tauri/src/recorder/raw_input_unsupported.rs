@@ -0,0 +1,31 @@
+use crate::recorder::error::RecorderError;
+use crate::recorder::source::InputSource;
+
+/// Input source that reads raw mouse movement from the system
+///
+/// Raw mouse input is only available on Windows, so on other platforms the
+/// source can never be started.
+#[derive(Debug)]
+pub enum RawInputSource {}
+
+impl RawInputSource {
+    /// Raw mouse input can only be read on Windows
+    ///
+    /// # Returns
+    /// Always an error
+    pub fn start() -> Result<Self, RecorderError> {
+        Err(RecorderError::SourceFailed(
+            "Raw mouse input is only available on Windows".to_string(),
+        ))
+    }
+}
+
+impl InputSource for RawInputSource {
+    fn is_key_pressed(&self, _code: i32) -> bool {
+        match *self {}
+    }
+
+    fn take_mouse_delta(&mut self) -> (i32, i32) {
+        match *self {}
+    }
+}
@@ -0,0 +1,199 @@
+use std::time::Duration;
+
+use crate::mouse_controller::{ActivationEngine, Clock, DEFAULT_THREAD_SLEEP_DURATION_MS};
+use crate::patterns::{ActivationRule, Sensitivity, Step, Steps};
+use crate::recorder::error::RecorderError;
+use crate::recorder::source::InputSource;
+
+/// Default length of the time slices the recording is bucketed into, in milliseconds
+pub const DEFAULT_BUCKET_MS: u64 = 100;
+
+/// Settings of a single recording
+#[derive(Debug, Clone)]
+pub struct RecorderSettings {
+    /// Condition under which the movement is recorded, usually the fire buttons
+    pub activation: ActivationRule,
+    /// Time between two samples; should match the controller tick
+    pub tick: Duration,
+    /// How long to wait for the activation rule before giving up
+    pub start_timeout: Duration,
+    /// Longest time that is recorded, even if the activation rule stays satisfied
+    pub max_duration: Duration,
+}
+
+impl Default for RecorderSettings {
+    fn default() -> Self {
+        Self {
+            activation: ActivationRule::default(),
+            tick: Duration::from_millis(DEFAULT_THREAD_SLEEP_DURATION_MS),
+            start_timeout: Duration::from_secs(10),
+            max_duration: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Raw mouse movement read during a single tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sample {
+    /// Time between the activation and the end of the tick
+    pub time: Duration,
+    /// Horizontal movement in counts
+    pub dx: i32,
+    /// Vertical movement in counts
+    pub dy: i32,
+}
+
+/// Raw mouse movement captured while the activation rule was satisfied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recording {
+    /// Time between two samples
+    pub tick: Duration,
+    /// Samples in the order they were read
+    pub samples: Vec<Sample>,
+}
+
+impl Recording {
+    /// Returns the recorded time
+    pub fn duration(&self) -> Duration {
+        self.tick * self.samples.len() as u32
+    }
+
+    /// Converts the recording into the steps that compensate it
+    ///
+    /// Samples are grouped into slices of `bucket` and each slice becomes a step
+    /// that moves by its average movement per tick, inverted because the player
+    /// was pulling against the recoil. The values are multiplied by the
+    /// sensitivity, so the controller sends the recorded movement again after
+    /// dividing by it. Neighbouring steps with the same movement are merged and
+    /// still steps at the end are dropped.
+    ///
+    /// # Arguments
+    /// * `bucket` - Length of the time slices the samples are grouped into
+    /// * `sensitivity` - Sensitivity settings the controller divides the steps by
+    ///
+    /// # Returns
+    /// The steps of the compensation pattern
+    pub fn to_steps(&self, bucket: Duration, sensitivity: &Sensitivity) -> Steps {
+        let tick_ms = self.tick.as_millis().max(1) as u64;
+        let bucket_ms = bucket.as_millis().max(1) as u64;
+        let mut steps: Steps = Vec::new();
+        let mut index = 0;
+
+        while index < self.samples.len() {
+            // Collect all samples of the current time slice
+            let slice = self.slice_of(index, bucket_ms);
+            let mut end = index;
+            let (mut sum_x, mut sum_y) = (0i64, 0i64);
+            while end < self.samples.len() && self.slice_of(end, bucket_ms) == slice {
+                sum_x += self.samples[end].dx as i64;
+                sum_y += self.samples[end].dy as i64;
+                end += 1;
+            }
+
+            let count = (end - index) as u64;
+            let dx = -compensate(sum_x as f32 / count as f32, sensitivity.x);
+            let dy = -compensate(sum_y as f32 / count as f32, sensitivity.y);
+            let duration = count * tick_ms;
+
+            match steps.last_mut() {
+                Some(last) if last.dx == dx && last.dy == dy => last.duration += duration,
+                _ => steps.push(Step {
+                    duration,
+                    dx,
+                    dy,
+                    adjusted_dx: 0,
+                    adjusted_dy: 0,
+                }),
+            }
+
+            index = end;
+        }
+
+        // Movement stops with the release, so trailing still steps add nothing
+        while steps.last().is_some_and(|step| step.dx == 0 && step.dy == 0) {
+            steps.pop();
+        }
+
+        steps
+    }
+
+    /// Returns the index of the time slice the tick of a sample started in
+    fn slice_of(&self, index: usize, bucket_ms: u64) -> u64 {
+        self.samples[index]
+            .time
+            .saturating_sub(self.tick)
+            .as_millis() as u64
+            / bucket_ms
+    }
+}
+
+/// Records raw mouse movement while the activation rule is satisfied
+///
+/// The recording starts once the activation rule is satisfied and ends when it
+/// is released or the maximum duration is reached. Movement read while waiting
+/// for the activation is discarded.
+///
+/// # Arguments
+/// * `source` - Source of the key states and the raw mouse movement
+/// * `clock` - Time source used to wait between samples
+/// * `settings` - Activation rule, tick and limits of the recording
+///
+/// # Returns
+/// The samples read while the activation rule was satisfied, or an error if it
+/// was never satisfied or no movement was read
+pub fn record(
+    source: &mut dyn InputSource,
+    clock: &dyn Clock,
+    settings: &RecorderSettings,
+) -> Result<Recording, RecorderError> {
+    // A zero tick would never advance the time
+    let tick = settings.tick.max(Duration::from_millis(1));
+    let mut activation = ActivationEngine::new();
+
+    // Wait for the activation rule, dropping any movement made before it
+    let waiting_since = clock.now();
+    loop {
+        source.take_mouse_delta();
+        if activation.evaluate(&settings.activation, &|code| source.is_key_pressed(code)) {
+            break;
+        }
+        if clock.now().saturating_duration_since(waiting_since) >= settings.start_timeout {
+            return Err(RecorderError::Timeout);
+        }
+        clock.sleep(tick);
+    }
+
+    let started = clock.now();
+    let mut samples = Vec::new();
+    loop {
+        clock.sleep(tick);
+
+        // Movement made during the tick the buttons were released still counts
+        let (dx, dy) = source.take_mouse_delta();
+        let time = clock.now().saturating_duration_since(started);
+        samples.push(Sample { time, dx, dy });
+
+        let active = activation.evaluate(&settings.activation, &|code| source.is_key_pressed(code));
+        if !active || time >= settings.max_duration {
+            break;
+        }
+    }
+
+    if samples
+        .iter()
+        .all(|sample| sample.dx == 0 && sample.dy == 0)
+    {
+        return Err(RecorderError::NoMovement);
+    }
+
+    Ok(Recording { tick, samples })
+}
+
+/// Turns the average movement per tick into a step value
+fn compensate(movement: f32, sensitivity: f32) -> i32 {
+    if sensitivity > 0.0 {
+        (movement * sensitivity).round() as i32
+    } else {
+        movement.round() as i32
+    }
+}
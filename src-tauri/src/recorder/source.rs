@@ -0,0 +1,84 @@
+use std::collections::VecDeque;
+
+/// Source of the key states and raw mouse movement read by the recorder
+///
+/// The recorder only reads input through this trait, so a recording can be
+/// driven by synthetic movement instead of the real mouse.
+pub trait InputSource {
+    /// Returns whether the key or mouse button with the given virtual key code is held down
+    fn is_key_pressed(&self, code: i32) -> bool;
+
+    /// Returns the raw mouse movement accumulated since the previous call
+    fn take_mouse_delta(&mut self) -> (i32, i32);
+}
+
+/// Single tick of synthetic input
+#[derive(Debug, Clone, Default)]
+struct SyntheticFrame {
+    /// Virtual key codes held down during the tick
+    pressed: Vec<i32>,
+    /// Horizontal movement during the tick
+    dx: i32,
+    /// Vertical movement during the tick
+    dy: i32,
+}
+
+/// Input source that replays queued frames of key states and movement
+///
+/// Every call to `take_mouse_delta` moves to the next frame and returns its
+/// movement; key states are read from the current frame. Once all frames were
+/// replayed, no key is held down and the mouse stays still.
+#[derive(Debug, Default)]
+pub struct SyntheticSource {
+    /// Frames that were not replayed yet
+    frames: VecDeque<SyntheticFrame>,
+    /// Frame that is currently replayed
+    current: SyntheticFrame,
+}
+
+impl SyntheticSource {
+    /// Creates a new source without any frames
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a frame of input
+    ///
+    /// # Arguments
+    /// * `pressed` - Virtual key codes held down during the frame
+    /// * `dx` - Horizontal movement during the frame
+    /// * `dy` - Vertical movement during the frame
+    pub fn push(&mut self, pressed: &[i32], dx: i32, dy: i32) -> &mut Self {
+        self.frames.push_back(SyntheticFrame {
+            pressed: pressed.to_vec(),
+            dx,
+            dy,
+        });
+        self
+    }
+
+    /// Queues the same frame of input several times
+    ///
+    /// # Arguments
+    /// * `count` - Number of frames to queue
+    /// * `pressed` - Virtual key codes held down during each frame
+    /// * `dx` - Horizontal movement during each frame
+    /// * `dy` - Vertical movement during each frame
+    pub fn repeat(&mut self, count: usize, pressed: &[i32], dx: i32, dy: i32) -> &mut Self {
+        for _ in 0..count {
+            self.push(pressed, dx, dy);
+        }
+        self
+    }
+}
+
+impl InputSource for SyntheticSource {
+    fn is_key_pressed(&self, code: i32) -> bool {
+        self.current.pressed.contains(&code)
+    }
+
+    fn take_mouse_delta(&mut self) -> (i32, i32) {
+        self.current = self.frames.pop_front().unwrap_or_default();
+        (self.current.dx, self.current.dy)
+    }
+}
@@ -0,0 +1,34 @@
+use std::fmt;
+use std::time::Duration;
+
+/// Error type for pattern script operations
+#[derive(Debug)]
+pub enum ScriptError {
+    /// The script could not be compiled
+    CompileFailed(String),
+    /// The script failed while it was evaluated
+    EvaluationFailed(String),
+    /// The evaluation took longer than the budget
+    BudgetExceeded(Duration),
+    /// The script returned something other than a movement
+    InvalidResult(String),
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptError::CompileFailed(reason) => write!(f, "Failed to compile script: {}", reason),
+            ScriptError::EvaluationFailed(reason) => {
+                write!(f, "Failed to evaluate script: {}", reason)
+            }
+            ScriptError::BudgetExceeded(budget) => {
+                write!(f, "Script ran longer than its budget of {:?}", budget)
+            }
+            ScriptError::InvalidResult(result) => {
+                write!(f, "Script returned {} instead of [dx, dy] or ()", result)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScriptError {}
@@ -0,0 +1,28 @@
+/// Scripting module for patterns whose movement depends on the situation.
+///
+/// This module compiles the Rhai scripts of patterns and evaluates them on
+/// every tick of the controller. Scripts run in a sandbox without access to
+/// files, modules or output, and every evaluation has a time budget.
+///
+/// # Structure
+///
+/// The module is organized into several components:
+/// - `script`: Compiles and evaluates a pattern script
+/// - `error`: Contains error types for script operations
+///
+/// # Usage
+///
+/// The main entry point is `PatternScript::compile`, which turns the script
+/// settings of a pattern into a script the controller can evaluate.
+mod error;
+mod script;
+
+/// Public exports from the scripting module
+///
+/// These are the primary types that users of this module will interact with:
+/// - `PatternScript`: Compiled script with its engine and budget
+/// - `ScriptInput`: Situation of the tick the script is evaluated for
+/// - `ScriptScope`: Variables of a script, reused between evaluations
+/// - `ScriptError`: Errors of compiling or evaluating a script
+pub use error::ScriptError;
+pub use script::{PatternScript, ScriptInput, ScriptScope};
@@ -0,0 +1,267 @@
+use std::cell::Cell;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, ImmutableString, Map, Scope, AST};
+
+use crate::patterns::Script;
+use crate::scripting::error::ScriptError;
+
+/// Number of operations between two checks of the time budget
+const BUDGET_CHECK_INTERVAL: u64 = 64;
+
+/// Variables every script sees, in the order they are pushed to its scope
+const VARIABLES: [&str; 7] = [
+    "time_ms", "tick", "shots", "step", "step_dx", "step_dy", "modifier",
+];
+
+thread_local! {
+    /// Time at which the evaluation running on this thread is terminated
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Situation of the tick a script is evaluated for
+#[derive(Debug, Clone, Copy)]
+pub struct ScriptInput<'a> {
+    /// Time since the compensation started in milliseconds
+    pub time_ms: u64,
+    /// Number of ticks since the compensation started
+    pub tick: u64,
+    /// Number of bullets or shots fired so far, always 0 for duration-based steps
+    pub shots: u32,
+    /// Index of the current step, bullet or shot of the pattern
+    pub step: usize,
+    /// Horizontal movement of the current step, bullet or shot
+    pub step_dx: i32,
+    /// Vertical movement of the current step, bullet or shot
+    pub step_dy: i32,
+    /// Name of the active modifier, if any
+    pub modifier: Option<&'a str>,
+}
+
+/// Variables of a pattern script, reused for every evaluation
+///
+/// The variables are created once and only their values are replaced before
+/// each evaluation, so evaluating a script does not allocate a new scope or
+/// copy the name of the modifier on every tick.
+#[derive(Debug, Clone)]
+pub struct ScriptScope {
+    /// Variables of the script, followed by those the last evaluation declared
+    scope: Scope<'static>,
+    /// Name of the active modifier, shared with the `modifier` variable
+    modifier: ImmutableString,
+}
+
+impl ScriptScope {
+    /// Creates the variables of a script
+    pub fn new() -> Self {
+        let mut scope = Scope::with_capacity(VARIABLES.len());
+        for name in &VARIABLES[..VARIABLES.len() - 1] {
+            scope.push(*name, 0_i64);
+        }
+        scope.push("modifier", ImmutableString::new());
+
+        Self {
+            scope,
+            modifier: ImmutableString::new(),
+        }
+    }
+
+    /// Replaces the values of the variables with the situation of a tick
+    ///
+    /// Variables declared by the previous evaluation are removed, and the
+    /// modifier name is only copied when a different modifier becomes active.
+    fn update(&mut self, input: &ScriptInput<'_>) {
+        self.scope.rewind(VARIABLES.len());
+        self.scope
+            .set_value("time_ms", input.time_ms as i64)
+            .set_value("tick", input.tick as i64)
+            .set_value("shots", input.shots as i64)
+            .set_value("step", input.step as i64)
+            .set_value("step_dx", input.step_dx as i64)
+            .set_value("step_dy", input.step_dy as i64);
+
+        let modifier = input.modifier.unwrap_or("");
+        if self.modifier.as_str() != modifier {
+            self.modifier = modifier.into();
+        }
+        self.scope.set_value("modifier", self.modifier.clone());
+    }
+}
+
+impl Default for ScriptScope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Compiled pattern script together with the engine that evaluates it
+///
+/// The engine cannot read files, load modules, print or call `eval`, and
+/// limits the size of strings, arrays and maps as well as the call depth.
+///
+/// # Thread Safety
+///
+/// The script can be shared between threads. Each evaluation keeps its
+/// deadline in a thread-local, so concurrent evaluations do not interfere.
+pub struct PatternScript {
+    /// Sandboxed engine that evaluates the script
+    engine: Engine,
+    /// Compiled source of the script
+    ast: AST,
+    /// Longest time a single evaluation may take
+    budget: Duration,
+}
+
+impl PatternScript {
+    /// Compiles the script of a pattern
+    ///
+    /// # Arguments
+    /// * `script` - Source and budget of the script
+    ///
+    /// # Returns
+    /// The compiled script, or an error if the source is invalid
+    pub fn compile(script: &Script) -> Result<Self, ScriptError> {
+        let engine = sandboxed_engine();
+        let ast = engine
+            .compile(&script.source)
+            .map_err(|e| ScriptError::CompileFailed(e.to_string()))?;
+
+        Ok(Self {
+            engine,
+            ast,
+            budget: Duration::from_micros(script.budget_us),
+        })
+    }
+
+    /// Evaluates the script for a single tick
+    ///
+    /// The script sees `time_ms`, `tick`, `shots`, `step`, `step_dx`, `step_dy`
+    /// and `modifier` (empty when no modifier is active). Fractional movement
+    /// is rounded to whole pixels.
+    ///
+    /// # Arguments
+    /// * `scope` - Variables of the script, reused between evaluations
+    /// * `input` - Situation of the tick
+    ///
+    /// # Returns
+    /// The movement returned by the script, `None` to keep the movement of the
+    /// pattern, or an error if the script failed or ran out of time
+    pub fn evaluate(
+        &self,
+        scope: &mut ScriptScope,
+        input: &ScriptInput<'_>,
+    ) -> Result<Option<(i32, i32)>, ScriptError> {
+        scope.update(input);
+
+        DEADLINE.with(|deadline| deadline.set(Some(Instant::now() + self.budget)));
+        let result = self
+            .engine
+            .eval_ast_with_scope::<Dynamic>(&mut scope.scope, &self.ast);
+        DEADLINE.with(|deadline| deadline.set(None));
+
+        match result {
+            Ok(value) => movement(value),
+            Err(e) => match *e {
+                EvalAltResult::ErrorTerminated(..) => Err(ScriptError::BudgetExceeded(self.budget)),
+                e => Err(ScriptError::EvaluationFailed(e.to_string())),
+            },
+        }
+    }
+}
+
+impl fmt::Debug for PatternScript {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PatternScript")
+            .field("budget", &self.budget)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Creates an engine without access to anything outside the script
+fn sandboxed_engine() -> Engine {
+    let mut engine = Engine::new();
+
+    engine.set_module_resolver(DummyModuleResolver::new());
+    engine.disable_symbol("eval");
+    engine.on_print(|_| {});
+    engine.on_debug(|_, _, _| {});
+
+    engine.set_max_call_levels(16);
+    engine.set_max_expr_depths(64, 32);
+    engine.set_max_string_size(1024);
+    engine.set_max_array_size(1024);
+    engine.set_max_map_size(64);
+
+    // Terminate evaluations that run past the deadline of the current thread
+    engine.on_progress(|operations| {
+        if operations % BUDGET_CHECK_INTERVAL != 0 {
+            return None;
+        }
+        let expired = DEADLINE.with(|deadline| {
+            deadline
+                .get()
+                .is_some_and(|deadline| Instant::now() >= deadline)
+        });
+        expired.then_some(Dynamic::UNIT)
+    });
+
+    engine
+}
+
+/// Turns the value returned by a script into a movement
+fn movement(value: Dynamic) -> Result<Option<(i32, i32)>, ScriptError> {
+    if value.is_unit() {
+        return Ok(None);
+    }
+
+    let type_name = value.type_name();
+    let (dx, dy) = if value.is_array() {
+        let array = value.cast::<Array>();
+        match array.as_slice() {
+            [dx, dy] => (dx.clone(), dy.clone()),
+            _ => {
+                return Err(ScriptError::InvalidResult(format!(
+                    "an array of {} values",
+                    array.len()
+                )))
+            }
+        }
+    } else if value.is_map() {
+        let map = value.cast::<Map>();
+        let axis = |name: &str| map.get(name).cloned().unwrap_or(Dynamic::from(0_i64));
+        (axis("dx"), axis("dy"))
+    } else {
+        return Err(ScriptError::InvalidResult(format!(
+            "a value of type {}",
+            type_name
+        )));
+    };
+
+    Ok(Some((pixels(dx)?, pixels(dy)?)))
+}
+
+/// Turns a number returned by a script into whole pixels
+fn pixels(value: Dynamic) -> Result<i32, ScriptError> {
+    let exact = if let Ok(int) = value.as_int() {
+        int as f64
+    } else if let Ok(float) = value.as_float() {
+        float
+    } else {
+        return Err(ScriptError::InvalidResult(format!(
+            "a movement of type {}",
+            value.type_name()
+        )));
+    };
+
+    let rounded = exact.round();
+    if rounded.is_finite() && rounded.abs() <= i32::MAX as f64 {
+        Ok(rounded as i32)
+    } else {
+        Err(ScriptError::InvalidResult(format!(
+            "a movement of {}",
+            exact
+        )))
+    }
+}
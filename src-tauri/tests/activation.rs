@@ -0,0 +1,188 @@
+//! Evaluates activation rules over sequences of key states, including toggles and rule changes.
+
+mod common;
+
+use app_lib::mouse_controller::ActivationEngine;
+use app_lib::patterns::ActivationRule;
+use common::{pattern, VirtualController};
+use serde_json::json;
+
+/// Virtual key code of the left mouse button
+const LBUTTON: i32 = 0x01;
+/// Virtual key code of the right mouse button
+const RBUTTON: i32 = 0x02;
+/// Virtual key code of the shift key
+const SHIFT: i32 = 0x10;
+
+/// Reads an activation rule from JSON
+fn rule(value: serde_json::Value) -> ActivationRule {
+    serde_json::from_value(value).expect("invalid activation rule")
+}
+
+/// Evaluates the rule once per set of held keys and returns every result
+fn evaluate(engine: &mut ActivationEngine, rule: &ActivationRule, held: &[&[i32]]) -> Vec<bool> {
+    held.iter()
+        .map(|keys| engine.evaluate(rule, &|code| keys.contains(&code)))
+        .collect()
+}
+
+#[test]
+fn toggle_changes_on_each_press_and_not_while_held() {
+    let mut engine = ActivationEngine::new();
+    let toggle = rule(json!({ "toggle": "RButton" }));
+
+    let results = evaluate(
+        &mut engine,
+        &toggle,
+        &[
+            &[],
+            &[RBUTTON],
+            &[RBUTTON],
+            &[RBUTTON],
+            &[],
+            &[],
+            &[RBUTTON],
+            &[],
+            &[RBUTTON],
+        ],
+    );
+
+    assert_eq!(
+        results,
+        vec![false, true, true, true, true, true, false, false, true]
+    );
+}
+
+#[test]
+fn toggle_is_tracked_while_the_rest_of_the_rule_is_inactive() {
+    let mut engine = ActivationEngine::new();
+    let toggle_ads = rule(json!({ "all": [{ "key": "LButton" }, { "toggle": "RButton" }] }));
+
+    // The toggle is turned on without firing, then firing activates the rule
+    let results = evaluate(
+        &mut engine,
+        &toggle_ads,
+        &[
+            &[RBUTTON],
+            &[],
+            &[LBUTTON],
+            &[],
+            &[LBUTTON, RBUTTON],
+            &[LBUTTON],
+        ],
+    );
+
+    assert_eq!(results, vec![false, false, true, false, false, false]);
+}
+
+#[test]
+fn toggle_used_twice_in_a_rule_changes_once_per_press() {
+    let mut engine = ActivationEngine::new();
+    let twice = rule(json!({ "all": [{ "toggle": "RButton" }, { "toggle": "RButton" }] }));
+
+    let results = evaluate(&mut engine, &twice, &[&[RBUTTON], &[], &[RBUTTON]]);
+
+    assert_eq!(results, vec![true, true, false]);
+}
+
+#[test]
+fn any_and_not_compose_nested_rules() {
+    let mut engine = ActivationEngine::new();
+    let hip_fire_or_ads = rule(json!({
+        "any": [
+            { "all": [{ "key": "LButton" }, { "not": { "key": "Shift" } }] },
+            { "all": [{ "key": "LButton" }, { "key": "RButton" }] }
+        ]
+    }));
+
+    let results = evaluate(
+        &mut engine,
+        &hip_fire_or_ads,
+        &[
+            &[],
+            &[LBUTTON],
+            &[LBUTTON, SHIFT],
+            &[LBUTTON, SHIFT, RBUTTON],
+            &[RBUTTON],
+            &[SHIFT],
+        ],
+    );
+
+    assert_eq!(results, vec![false, true, false, true, false, false]);
+}
+
+#[test]
+fn reset_turns_every_toggle_off() {
+    let mut engine = ActivationEngine::new();
+    let toggle = rule(json!({ "toggle": "RButton" }));
+    assert_eq!(
+        evaluate(&mut engine, &toggle, &[&[RBUTTON], &[]]),
+        vec![true, true]
+    );
+
+    engine.reset();
+
+    // A key still held after the reset counts as a new press
+    assert_eq!(
+        evaluate(&mut engine, &toggle, &[&[], &[RBUTTON], &[RBUTTON]]),
+        vec![false, true, true]
+    );
+}
+
+#[test]
+fn changing_the_rule_turns_the_toggles_off() {
+    let mut virtual_controller = VirtualController::new();
+    virtual_controller
+        .controller
+        .update_activation(rule(json!({ "toggle": "RButton" })));
+    virtual_controller
+        .controller
+        .update_pattern(pattern(json!({ "config": "24ms x100: 0,1", "images": [] })));
+    virtual_controller.tick();
+
+    // A single press turns the compensation on
+    virtual_controller.keys.set(true);
+    virtual_controller.tick();
+    virtual_controller.keys.set(false);
+    virtual_controller.run(3);
+    let sent = virtual_controller.backend.moves().len();
+    assert!(sent > 0);
+
+    // The new rule uses the same toggle, but it starts off
+    virtual_controller
+        .controller
+        .update_activation(rule(json!({ "any": [{ "toggle": "RButton" }] })));
+    virtual_controller.run(3);
+    assert_eq!(virtual_controller.backend.moves().len(), sent);
+
+    virtual_controller.keys.set(true);
+    virtual_controller.tick();
+    virtual_controller.keys.set(false);
+    virtual_controller.run(2);
+    assert!(virtual_controller.backend.moves().len() > sent);
+}
+
+#[test]
+fn publishing_the_same_rule_keeps_the_toggles() {
+    let mut virtual_controller = VirtualController::new();
+    let toggle = rule(json!({ "toggle": "RButton" }));
+    virtual_controller
+        .controller
+        .update_activation(toggle.clone());
+    virtual_controller
+        .controller
+        .update_pattern(pattern(json!({ "config": "24ms x100: 0,1", "images": [] })));
+    virtual_controller.tick();
+
+    virtual_controller.keys.set(true);
+    virtual_controller.tick();
+    virtual_controller.keys.set(false);
+    virtual_controller.tick();
+
+    // Other changes publish a new snapshot with the same rule
+    virtual_controller.controller.update_activation(toggle);
+    virtual_controller.tick();
+    let sent = virtual_controller.backend.moves().len();
+    virtual_controller.run(2);
+    assert_eq!(virtual_controller.backend.moves().len(), sent + 2);
+}
@@ -0,0 +1,88 @@
+//! Checks the start delay and ramp-in of patterns on a virtual clock.
+
+mod common;
+
+use app_lib::mouse_controller::ControllerPhase;
+use common::{pattern, VirtualController};
+use serde_json::json;
+
+/// Holds the activation keys for the given number of iterations
+///
+/// # Returns
+/// The vertical movement sent at each iteration, 0 when nothing was sent
+fn spray(virtual_controller: &mut VirtualController, ticks: usize) -> Vec<i32> {
+    virtual_controller.keys.set(true);
+    let mut sent = Vec::new();
+    for _ in 0..ticks {
+        let before = virtual_controller.backend.moves().len();
+        virtual_controller.tick();
+        let moves = virtual_controller.backend.moves();
+        sent.push(if moves.len() > before { moves[before].1 } else { 0 });
+    }
+    sent
+}
+
+#[test]
+fn start_delay_holds_off_compensation_and_ramp_in_scales_it_up() {
+    let mut virtual_controller = VirtualController::new();
+    let transitions = virtual_controller.controller.take_transitions().unwrap();
+
+    virtual_controller.controller.update_pattern(pattern(json!({
+        "config": [{ "duration": 1000, "dx": 0, "dy": 10 }],
+        "start_delay_ms": 100,
+        "ramp_in_ms": 96,
+        "images": []
+    })));
+    virtual_controller.tick();
+
+    // Iterations run every 24 ms; compensation starts 100 ms after the activation
+    // and reaches full strength 96 ms later
+    let sent = spray(&mut virtual_controller, 11);
+    assert_eq!(sent, vec![0, 0, 0, 0, 0, 2, 5, 7, 10, 10, 10]);
+
+    let phases: Vec<ControllerPhase> = transitions.try_iter().map(|t| t.to).collect();
+    assert_eq!(
+        phases,
+        vec![
+            ControllerPhase::Armed,
+            ControllerPhase::Delayed,
+            ControllerPhase::Compensating { step: 0 },
+        ]
+    );
+}
+
+#[test]
+fn ramp_in_without_delay_starts_from_zero() {
+    let mut virtual_controller = VirtualController::new();
+
+    virtual_controller.controller.update_pattern(pattern(json!({
+        "config": [{ "duration": 1000, "dx": 0, "dy": 8 }],
+        "ramp_in_ms": 96,
+        "images": []
+    })));
+    virtual_controller.tick();
+
+    let sent = spray(&mut virtual_controller, 6);
+    assert_eq!(sent, vec![0, 2, 4, 6, 8, 8]);
+}
+
+#[test]
+fn releasing_during_the_start_delay_sends_nothing() {
+    let mut virtual_controller = VirtualController::new();
+
+    virtual_controller.controller.update_pattern(pattern(json!({
+        "config": [{ "duration": 1000, "dx": 0, "dy": 10 }],
+        "start_delay_ms": 100,
+        "images": []
+    })));
+    virtual_controller.tick();
+
+    assert_eq!(spray(&mut virtual_controller, 3), vec![0, 0, 0]);
+    virtual_controller.keys.set(false);
+    virtual_controller.run(10);
+    assert!(virtual_controller.backend.moves().is_empty());
+
+    // The delay starts over on the next activation
+    let sent = spray(&mut virtual_controller, 6);
+    assert_eq!(sent, vec![0, 0, 0, 0, 0, 10]);
+}
@@ -0,0 +1,82 @@
+//! Calibrates against a synthetic scene that pans by a known amount per count.
+
+mod common;
+
+use app_lib::calibration::{calibrate, CalibrationError, CalibrationSettings, SyntheticScene};
+use common::SteppingClock;
+use opencv::core::{Mat, Point, Scalar, CV_8UC3};
+use opencv::imgproc;
+
+/// Creates a textured image of overlapping discs in shades of gray
+fn texture(width: i32, height: i32) -> Mat {
+    let mut image =
+        Mat::new_rows_cols_with_default(height, width, CV_8UC3, Scalar::all(128.0)).unwrap();
+
+    // Small linear congruential generator, so the image is the same on every run
+    let mut seed: u32 = 12345;
+    let mut next = |bound: i32| {
+        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+        ((seed >> 16) % bound as u32) as i32
+    };
+
+    for _ in 0..600 {
+        let center = Point::new(next(width), next(height));
+        let radius = 3 + next(10);
+        let shade = Scalar::all(next(256) as f64);
+        imgproc::circle(&mut image, center, radius, shade, -1, imgproc::LINE_8, 0).unwrap();
+    }
+
+    image
+}
+
+#[test]
+fn calibration_measures_the_pixels_per_count_of_each_axis() {
+    let mut scene = SyntheticScene::new(texture(400, 300), 160, 120, 0.25, 0.2);
+    let mut backend = scene.backend();
+    let settings = CalibrationSettings::default();
+
+    let calibration =
+        calibrate(&mut backend, &mut scene, &SteppingClock::default(), &settings).unwrap();
+
+    assert!((calibration.pixels_per_count_x - 0.25).abs() < 0.01);
+    assert!((calibration.pixels_per_count_y - 0.2).abs() < 0.01);
+    assert!((calibration.sensitivity.x - 0.25).abs() < 0.01);
+    assert!((calibration.sensitivity.y - 0.2).abs() < 0.01);
+
+    // Every move of 20 counts shifts the scene the other way
+    assert_eq!(calibration.samples.len(), 2 * settings.moves as usize);
+    for sample in &calibration.samples {
+        assert!((sample.shift_x + sample.dx as f64 * 0.25).abs() < 0.5);
+        assert!((sample.shift_y + sample.dy as f64 * 0.2).abs() < 0.5);
+    }
+}
+
+#[test]
+fn calibration_fails_when_the_view_does_not_turn() {
+    let mut scene = SyntheticScene::new(texture(400, 300), 160, 120, 0.0, 0.2);
+    let mut backend = scene.backend();
+
+    let result = calibrate(
+        &mut backend,
+        &mut scene,
+        &SteppingClock::default(),
+        &CalibrationSettings::default(),
+    );
+
+    assert!(matches!(result, Err(CalibrationError::NoMotion("horizontal"))));
+}
+
+#[test]
+fn calibration_fails_when_the_view_leaves_the_scene() {
+    let mut scene = SyntheticScene::new(texture(200, 150), 160, 120, 1.0, 1.0);
+    let mut backend = scene.backend();
+
+    let result = calibrate(
+        &mut backend,
+        &mut scene,
+        &SteppingClock::default(),
+        &CalibrationSettings::default(),
+    );
+
+    assert!(matches!(result, Err(CalibrationError::CaptureFailed(_))));
+}
@@ -0,0 +1,141 @@
+//! Synthetic inputs shared by the integration tests.
+
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use app_lib::mouse_controller::{
+    Clock, InputBackend, KeyState, ManualClock, MouseController, MouseInputError,
+};
+use app_lib::patterns::{ActivationRule, Pattern, Sensitivity, Step};
+
+/// Duration of a single controller iteration
+pub const TICK: Duration = Duration::from_millis(24);
+
+/// Key state where every key is held down while the shared flag is set, unless set on its own
+#[derive(Debug, Clone, Default)]
+pub struct HeldKeys {
+    held: Arc<AtomicBool>,
+    keys: Arc<Mutex<HashMap<i32, bool>>>,
+}
+
+impl HeldKeys {
+    /// Holds or releases every key that is not set on its own
+    pub fn set(&self, held: bool) {
+        self.held.store(held, Ordering::SeqCst);
+    }
+
+    /// Holds or releases a single key, regardless of the shared flag
+    pub fn set_key(&self, key_code: i32, held: bool) {
+        self.keys.lock().unwrap().insert(key_code, held);
+    }
+}
+
+impl KeyState for HeldKeys {
+    fn is_pressed(&self, key_code: i32) -> bool {
+        match self.keys.lock().unwrap().get(&key_code) {
+            Some(&held) => held,
+            None => self.held.load(Ordering::SeqCst),
+        }
+    }
+}
+
+/// Backend that records every movement instead of sending it
+#[derive(Debug, Clone, Default)]
+pub struct RecordingBackend(pub Arc<Mutex<Vec<(i32, i32)>>>);
+
+impl RecordingBackend {
+    /// Returns the movements sent so far
+    pub fn moves(&self) -> Vec<(i32, i32)> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl InputBackend for RecordingBackend {
+    fn send_move(&mut self, dx: i32, dy: i32) -> Result<(), MouseInputError> {
+        self.0.lock().unwrap().push((dx, dy));
+        Ok(())
+    }
+}
+
+/// Clock whose sleeps return at once after advancing the virtual time, for single-threaded tools
+#[derive(Debug, Default)]
+pub struct SteppingClock(pub ManualClock);
+
+impl Clock for SteppingClock {
+    fn now(&self) -> std::time::Instant {
+        self.0.now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.0.advance(duration);
+    }
+}
+
+/// Creates a step with the given duration and movement
+pub fn step(duration: u64, dx: i32, dy: i32) -> Step {
+    Step {
+        duration,
+        dx,
+        dy,
+        adjusted_dx: dx,
+        adjusted_dy: dy,
+    }
+}
+
+/// Parses a pattern from its configuration JSON
+pub fn pattern(json: serde_json::Value) -> Pattern {
+    serde_json::from_value(json).expect("invalid pattern")
+}
+
+/// Controller running on a virtual clock with synthetic keys and a recording backend
+pub struct VirtualController {
+    pub controller: MouseController,
+    pub clock: Arc<ManualClock>,
+    pub keys: HeldKeys,
+    pub backend: RecordingBackend,
+    /// Number of iterations the controller thread has finished
+    pub ticks: u64,
+}
+
+impl VirtualController {
+    /// Starts a controller and waits until its first iteration is finished
+    pub fn new() -> Self {
+        let clock = Arc::new(ManualClock::new());
+        let keys = HeldKeys::default();
+        let backend = RecordingBackend::default();
+        let controller = MouseController::with_input(
+            Sensitivity { x: 1.0, y: 1.0 },
+            ActivationRule::default(),
+            Arc::clone(&clock) as _,
+            Box::new(keys.clone()),
+            Box::new(backend.clone()),
+        );
+        clock.wait_for_sleeps(1);
+
+        Self {
+            controller,
+            clock,
+            keys,
+            backend,
+            ticks: 1,
+        }
+    }
+
+    /// Advances the virtual time by one tick and waits until the next iteration is finished
+    pub fn tick(&mut self) {
+        self.clock.advance(TICK);
+        self.ticks += 1;
+        self.clock.wait_for_sleeps(self.ticks);
+    }
+
+    /// Runs the given number of iterations
+    pub fn run(&mut self, ticks: u64) {
+        for _ in 0..ticks {
+            self.tick();
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Drives the controller thread on a virtual clock with synthetic keys and backend.
+
+mod common;
+
+use std::time::Duration;
+
+use app_lib::mouse_controller::{ControllerPhase, ControllerTransition};
+use common::{step, VirtualController, TICK};
+
+/// Returns the step the controller applies `elapsed` after activation
+///
+/// A step is left on the first iteration at which its duration has elapsed,
+/// and the next step starts at that iteration.
+fn expected_step(elapsed: Duration) -> usize {
+    let ms = elapsed.as_millis();
+    if ms <= 1008 {
+        0
+    } else if ms <= 2016 {
+        1
+    } else {
+        2
+    }
+}
+
+#[test]
+fn three_second_spray_applies_each_step_at_its_tick() {
+    let mut virtual_controller = VirtualController::new();
+    let transitions = virtual_controller.controller.take_transitions().unwrap();
+
+    // Set the pattern between two iterations, so the next one arms the controller
+    virtual_controller
+        .controller
+        .update_steps(vec![step(1000, 1, 10), step(1000, 2, 20), step(1000, 3, 30)]);
+    virtual_controller.tick();
+    virtual_controller.keys.set(true);
+
+    let spray_ticks = (Duration::from_secs(3).as_millis() / TICK.as_millis()) as u32;
+    for tick in 0..spray_ticks {
+        virtual_controller.tick();
+
+        let moves = virtual_controller.backend.moves();
+        assert_eq!(moves.len(), tick as usize + 1, "one movement per iteration");
+        let step = expected_step(TICK * tick);
+        let expected = (step as i32 + 1, (step as i32 + 1) * 10);
+        assert_eq!(moves[tick as usize], expected, "step at {:?}", TICK * tick);
+    }
+
+    // Releasing the keys stops the compensation on the next iteration
+    virtual_controller.keys.set(false);
+    virtual_controller.run(3);
+    assert_eq!(virtual_controller.backend.moves().len(), spray_ticks as usize);
+
+    let phases: Vec<ControllerTransition> = transitions.try_iter().collect();
+    let to: Vec<ControllerPhase> = phases.iter().map(|transition| transition.to).collect();
+    assert_eq!(
+        to,
+        vec![
+            ControllerPhase::Armed,
+            ControllerPhase::Compensating { step: 0 },
+            ControllerPhase::Compensating { step: 1 },
+            ControllerPhase::Compensating { step: 2 },
+            ControllerPhase::Armed,
+        ]
+    );
+}
+
+#[test]
+fn resume_restores_the_status_saved_by_suspend() {
+    let mut virtual_controller = VirtualController::new();
+    let controller = &mut virtual_controller.controller;
+
+    let suspended = controller.suspend();
+    assert!(!controller.status().enabled);
+
+    let status = controller.resume(suspended);
+    assert!(status.enabled);
+    assert!(status.armed);
+}
+
+#[test]
+fn resume_keeps_a_panic_made_while_suspended() {
+    let mut virtual_controller = VirtualController::new();
+    let controller = &mut virtual_controller.controller;
+
+    let suspended = controller.suspend();
+    controller.panic();
+
+    let status = controller.resume(suspended);
+    assert!(!status.enabled);
+    assert!(!status.armed);
+}
+
+#[test]
+fn resume_keeps_a_disabled_controller_disabled() {
+    let mut virtual_controller = VirtualController::new();
+    let controller = &mut virtual_controller.controller;
+
+    controller.set_enabled(false);
+    let suspended = controller.suspend();
+
+    let status = controller.resume(suspended);
+    assert!(!status.enabled);
+}
@@ -0,0 +1,130 @@
+//! Converts patterns between game settings and checks what is rescaled and reported.
+
+use app_lib::patterns::{conversion_factor, convert_pattern, GameSettings, Pattern};
+use serde_json::json;
+
+/// Creates game settings without a field of view
+fn settings(sensitivity: f32, ads_multiplier: f32) -> GameSettings {
+    GameSettings {
+        dpi: 800.0,
+        sensitivity,
+        ads_multiplier,
+        fov: None,
+    }
+}
+
+/// Reads a pattern from JSON
+fn pattern(value: serde_json::Value) -> Pattern {
+    serde_json::from_value(value).expect("invalid pattern")
+}
+
+#[test]
+fn turn_ratio_compares_sensitivity_and_field_of_view() {
+    let target = settings(2.0, 1.0);
+
+    // The resolution does not change how far a count turns the view
+    let mut baseline = settings(3.0, 0.5);
+    baseline.dpi = 1600.0;
+    assert!((baseline.turn_ratio(&target) - 0.75).abs() < 1e-6);
+
+    // The field of view only counts when both settings define it
+    baseline.fov = Some(90.0);
+    assert!((baseline.turn_ratio(&target) - 0.75).abs() < 1e-6);
+
+    let target = GameSettings {
+        fov: Some(60.0),
+        ..target
+    };
+    let fov_ratio = 1.0 / 30f32.to_radians().tan();
+    assert!((baseline.turn_ratio(&target) - 0.75 * fov_ratio).abs() < 1e-5);
+}
+
+#[test]
+fn invalid_settings_have_no_conversion_factor() {
+    assert_eq!(
+        conversion_factor(&settings(2.0, 1.0), &settings(4.0, 1.0)),
+        Ok(0.5)
+    );
+    assert!(conversion_factor(&settings(2.0, 1.0), &settings(0.0, 1.0)).is_err());
+    assert!(conversion_factor(&settings(0.0, 1.0), &settings(2.0, 1.0)).is_err());
+}
+
+#[test]
+fn every_movement_is_rescaled_and_rounding_is_reported() {
+    let original = pattern(json!({
+        "config": "50ms: 4,6; 50ms: 3,5",
+        "fire_rate": {
+            "rpm": 600,
+            "magazine": 30,
+            "offsets": [{ "dx": 2, "dy": 8 }, { "dx": 2, "dy": 4 }]
+        },
+        "semi_auto": { "shots": [{ "dx": 0, "dy": 7 }] },
+        "variants": { "crouch": "50ms: 0,4", "prone": "50ms: 0,4; 50ms: 0,3" },
+        "images": []
+    }));
+    let target = settings(4.0, 1.0);
+
+    let (converted, report) = convert_pattern(&original, &settings(2.0, 1.0), &target).unwrap();
+
+    assert_eq!(report.factor, 0.5);
+    let moves = |steps: &[app_lib::patterns::Step]| -> Vec<(i32, i32)> {
+        steps.iter().map(|step| (step.dx, step.dy)).collect()
+    };
+    assert_eq!(moves(&converted.config), vec![(2, 3), (2, 3)]);
+    assert_eq!(converted.config[1].duration, 50);
+    let offsets = &converted.fire_rate.as_ref().unwrap().offsets;
+    assert_eq!((offsets[0].dx, offsets[0].dy), (1, 4));
+    assert_eq!((offsets[1].dx, offsets[1].dy), (1, 2));
+    let shot = &converted.semi_auto.as_ref().unwrap().shots[0];
+    assert_eq!((shot.dx, shot.dy), (0, 4));
+    assert_eq!(moves(&converted.variants["crouch"]), vec![(0, 2)]);
+    assert_eq!(moves(&converted.variants["prone"]), vec![(0, 2), (0, 2)]);
+
+    // Halves are rounded away from zero and recorded with their exact value
+    assert_eq!(report.steps.len(), 1);
+    let loss = &report.steps[0];
+    assert_eq!(loss.index, 1);
+    assert_eq!((loss.exact_dx, loss.exact_dy), (1.5, 2.5));
+    assert_eq!((loss.dx, loss.dy), (2, 3));
+    assert!(report.offsets.is_empty());
+    assert_eq!(report.shots.len(), 1);
+    assert_eq!(report.shots[0].exact_dy, 3.5);
+    assert!(!report.variants.contains_key("crouch"));
+    assert_eq!(report.variants["prone"].len(), 1);
+    assert_eq!(report.variants["prone"][0].index, 1);
+    assert!(!report.is_lossless());
+
+    // The converted pattern can be converted again from its new baseline
+    assert_eq!(converted.baseline, Some(target));
+}
+
+#[test]
+fn differences_below_the_tolerance_are_not_reported() {
+    let original = pattern(json!({ "config": "50ms: 0,3", "images": [] }));
+
+    // 3 * 1.002 = 3.006 rounds to 3 without a noticeable loss
+    let (converted, report) =
+        convert_pattern(&original, &settings(1.002, 1.0), &settings(1.0, 1.0)).unwrap();
+
+    assert_eq!(converted.config[0].dy, 3);
+    assert!(report.steps.is_empty());
+    assert!(report.is_lossless());
+}
+
+#[test]
+fn scripts_are_kept_and_reported_as_unconverted() {
+    let original = pattern(json!({
+        "config": "50ms: 0,4",
+        "script": { "source": "[0, 4]" },
+        "images": []
+    }));
+
+    let (converted, report) =
+        convert_pattern(&original, &settings(2.0, 1.0), &settings(4.0, 1.0)).unwrap();
+
+    assert_eq!(converted.config[0].dy, 2);
+    assert_eq!(converted.script.as_ref().unwrap().source, "[0, 4]");
+    assert!(report.unconverted_script);
+    assert!(report.steps.is_empty());
+    assert!(!report.is_lossless());
+}
@@ -0,0 +1,91 @@
+//! Exports patterns to CSV and merges them back into a configuration.
+
+use app_lib::patterns::{parse_csv, PatternCollection};
+use serde_json::json;
+
+/// Creates a configuration with a step pattern, a fire-rate pattern and a semi-automatic pattern
+fn collection() -> PatternCollection {
+    serde_json::from_value(json!({
+        "patterns": {
+            "rifle": {
+                "config": "100ms x2: 0,5; 100ms: 1,6",
+                "images": ["rifle.png"]
+            },
+            "smg": {
+                "fire_rate": {
+                    "rpm": 900,
+                    "magazine": 3,
+                    "offsets": [{ "dx": 0, "dy": 2 }, { "dx": 1, "dy": 3 }]
+                },
+                "images": ["smg.png"]
+            },
+            "pistol": {
+                "semi_auto": { "shots": [{ "dx": 0, "dy": 9 }] },
+                "images": ["pistol.png"]
+            }
+        },
+        "sensitivity": { "x": 1.0, "y": 1.0 }
+    }))
+    .expect("invalid configuration")
+}
+
+#[test]
+fn exported_patterns_merge_back_unchanged() {
+    let mut collection = collection();
+    let imported = parse_csv(&collection.to_csv()).unwrap();
+
+    let report = collection.merge_csv(imported, true);
+
+    assert_eq!(report.unchanged, vec!["pistol", "rifle", "smg"]);
+    assert!(report.added.is_empty());
+    assert!(report.conflicts.is_empty());
+    assert!(report.skipped.is_empty());
+
+    let patterns = collection.get_patterns();
+    assert_eq!(patterns["rifle"].config.len(), 3);
+    assert!(patterns["smg"].fire_rate.is_some());
+    assert!(patterns["pistol"].semi_auto.is_some());
+}
+
+#[test]
+fn rows_without_steps_only_replace_the_images() {
+    let mut collection = collection();
+    let imported = parse_csv(
+        "pattern,step,duration,dx,dy,images\n\
+         smg,,,,,smg-new.png\n\
+         rifle,,,,,rifle.png\n\
+         shotgun,,,,,shotgun.png\n",
+    )
+    .unwrap();
+
+    let report = collection.merge_csv(imported, true);
+
+    assert_eq!(report.unchanged, vec!["rifle"]);
+    assert_eq!(report.skipped, vec!["shotgun"]);
+    assert_eq!(report.conflicts.len(), 1);
+    assert_eq!(report.conflicts[0].pattern, "smg");
+    assert_eq!(report.conflicts[0].fields, vec!["images"]);
+
+    let patterns = collection.get_patterns();
+    assert!(!patterns.contains_key("shotgun"));
+    assert_eq!(patterns["smg"].images, vec!["smg-new.png"]);
+    assert!(patterns["smg"].fire_rate.is_some());
+    assert_eq!(patterns["rifle"].config.len(), 3);
+}
+
+#[test]
+fn steps_replace_a_fire_rate_pattern_only_when_overwriting() {
+    let csv = "pattern,step,duration,dx,dy\nsmg,0,50,0,4\n";
+
+    let mut kept = collection();
+    let report = kept.merge_csv(parse_csv(csv).unwrap(), false);
+    assert_eq!(report.conflicts[0].fields, vec!["steps"]);
+    assert!(kept.get_patterns()["smg"].fire_rate.is_some());
+
+    let mut replaced = collection();
+    replaced.merge_csv(parse_csv(csv).unwrap(), true);
+    let smg = &replaced.get_patterns()["smg"];
+    assert!(smg.fire_rate.is_none());
+    assert_eq!(smg.config.len(), 1);
+    assert_eq!(smg.images, vec!["smg.png"]);
+}
@@ -0,0 +1,113 @@
+//! Extracts the recoil of a short fixture clip with a known drift.
+//!
+//! The frames in `fixtures/spray` are cut from a textured image along a known
+//! path: the view turns up by 20 pixels and sways sideways. Frame 5 is noise,
+//! standing in for a muzzle flash that cannot be tracked.
+
+use std::time::Duration;
+
+use app_lib::extraction::{extract, open_source, ExtractionError, ExtractionSettings};
+use app_lib::patterns::Sensitivity;
+
+/// Frame rate of the fixture clip
+const FPS: f64 = 25.0;
+
+/// Compensation expected at every frame, the opposite of the view's path
+const EXPECTED: [(f64, f64); 12] = [
+    (0.0, 0.0),
+    (0.0, 3.0),
+    (-1.0, 6.0),
+    (-2.0, 9.0),
+    (-2.0, 12.0),
+    (-1.0, 14.0),
+    (0.0, 16.0),
+    (1.0, 18.0),
+    (2.0, 19.0),
+    (2.0, 20.0),
+    (2.0, 20.0),
+    (2.0, 20.0),
+];
+
+/// Path of the fixture clip
+fn clip() -> String {
+    format!("{}/tests/fixtures/spray", env!("CARGO_MANIFEST_DIR"))
+}
+
+/// Settings that only trust clear correlations
+fn settings() -> ExtractionSettings {
+    ExtractionSettings {
+        min_response: 0.2,
+        ..ExtractionSettings::default()
+    }
+}
+
+#[test]
+fn extraction_follows_the_drift_of_the_clip() {
+    let mut source = open_source(&clip(), Some(FPS)).unwrap();
+    let extraction = extract(source.as_mut(), &settings()).unwrap();
+
+    assert_eq!(extraction.frames, 12);
+    assert_eq!(extraction.lost_frames, 1);
+    assert_eq!(extraction.duration(), Duration::from_millis(440));
+
+    // The flash frame is placed between its neighbours instead of freezing the position
+    assert_eq!(extraction.samples.len(), EXPECTED.len());
+    for (sample, (x, y)) in extraction.samples.iter().zip(EXPECTED) {
+        assert!(
+            (sample.x - x).abs() < 0.25 && (sample.y - y).abs() < 0.25,
+            "({}, {}) at {:?}, expected ({}, {})",
+            sample.x,
+            sample.y,
+            sample.time,
+            x,
+            y
+        );
+    }
+}
+
+#[test]
+fn extracted_steps_reach_the_final_compensation() {
+    let mut source = open_source(&clip(), Some(FPS)).unwrap();
+    let extraction = extract(source.as_mut(), &settings()).unwrap();
+
+    let tick = Duration::from_millis(40);
+    let steps = extraction.to_steps(
+        tick,
+        Duration::from_millis(120),
+        &Sensitivity { x: 1.0, y: 1.0 },
+    );
+    let (x, y) = steps.iter().fold((0, 0), |(x, y), step| {
+        let ticks = (step.duration / 40) as i32;
+        (x + step.dx * ticks, y + step.dy * ticks)
+    });
+
+    assert!((x - 2).abs() <= 1, "horizontal compensation {}", x);
+    assert!((y - 20).abs() <= 1, "vertical compensation {}", y);
+}
+
+#[test]
+fn extraction_fails_when_most_frames_cannot_be_tracked() {
+    let mut source = open_source(&clip(), Some(FPS)).unwrap();
+    let settings = ExtractionSettings {
+        min_response: 1.5,
+        ..ExtractionSettings::default()
+    };
+
+    let result = extract(source.as_mut(), &settings);
+
+    assert!(matches!(
+        result,
+        Err(ExtractionError::TooManyLostFrames {
+            lost: 11,
+            frames: 12
+        })
+    ));
+}
+
+#[test]
+fn extraction_requires_a_frame_rate_for_a_directory() {
+    assert!(matches!(
+        open_source(&clip(), None),
+        Err(ExtractionError::OpenFailed(_))
+    ));
+}
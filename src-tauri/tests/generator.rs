@@ -0,0 +1,169 @@
+//! Generates steps from curves and checks how generated patterns are saved.
+
+use app_lib::patterns::{Generator, PatternCollection, Step, MAX_STEPS};
+use serde_json::json;
+
+/// Reads generator parameters from JSON
+fn generator(value: serde_json::Value) -> Generator {
+    serde_json::from_value(value).expect("invalid generator")
+}
+
+/// Returns the duration and movement of every generated step
+fn generate(value: serde_json::Value) -> Vec<(u64, i32, i32)> {
+    generator(value)
+        .generate()
+        .unwrap()
+        .iter()
+        .map(|step: &Step| (step.duration, step.dx, step.dy))
+        .collect()
+}
+
+#[test]
+fn constant_curve_carries_fractions_over() {
+    let steps = generate(json!({
+        "step_ms": 50,
+        "kind": "constant",
+        "duration_ms": 200,
+        "dx": 1.5,
+        "dy": 4.0
+    }));
+
+    assert_eq!(steps, vec![(50, 2, 4), (50, 1, 4), (50, 2, 4), (50, 1, 4)]);
+}
+
+#[test]
+fn linear_curve_follows_the_rate_at_the_middle_of_each_slice() {
+    let steps = generate(json!({
+        "step_ms": 50,
+        "kind": "linear",
+        "duration_ms": 100,
+        "from": { "dx": 0.0, "dy": 0.0 },
+        "to": { "dx": 0.0, "dy": 10.0 }
+    }));
+
+    // 2.5 and 7.5 per tick, the first half pixel is carried to the second slice
+    assert_eq!(steps, vec![(50, 0, 3), (50, 0, 7)]);
+}
+
+#[test]
+fn exponential_curve_settles_towards_its_target() {
+    let steps = generate(json!({
+        "step_ms": 100,
+        "kind": "exponential",
+        "duration_ms": 300,
+        "from": { "dx": 0.0, "dy": 0.0 },
+        "to": { "dx": 0.0, "dy": 10.0 },
+        "time_constant_ms": 100.0
+    }));
+
+    assert_eq!(steps, vec![(100, 0, 4), (100, 0, 8), (100, 0, 9)]);
+
+    let error = generator(json!({
+        "kind": "exponential",
+        "duration_ms": 300,
+        "from": { "dx": 0.0, "dy": 0.0 },
+        "to": { "dx": 0.0, "dy": 10.0 },
+        "time_constant_ms": 0.0
+    }))
+    .generate()
+    .unwrap_err();
+    assert_eq!(error, "the time constant must be positive, got 0");
+}
+
+#[test]
+fn piecewise_curve_interpolates_between_control_points() {
+    let steps = generate(json!({
+        "step_ms": 50,
+        "kind": "piecewise",
+        "points": [
+            { "time_ms": 0, "dx": 0.0, "dy": 0.0 },
+            { "time_ms": 100, "dx": 0.0, "dy": 10.0 },
+            { "time_ms": 200, "dx": 0.0, "dy": 10.0 }
+        ]
+    }));
+
+    // The constant part after the second point is merged into a single step
+    assert_eq!(steps, vec![(50, 0, 3), (50, 0, 7), (100, 0, 10)]);
+
+    let error = generator(json!({
+        "kind": "piecewise",
+        "points": [
+            { "time_ms": 100, "dx": 0.0, "dy": 1.0 },
+            { "time_ms": 50, "dx": 0.0, "dy": 2.0 }
+        ]
+    }))
+    .generate()
+    .unwrap_err();
+    assert_eq!(
+        error,
+        "control points must be in order of time, 50 ms follows 100 ms"
+    );
+}
+
+#[test]
+fn curves_cut_into_too_many_steps_are_rejected() {
+    let curve = |duration_ms: u64| {
+        generator(json!({
+            "step_ms": 50,
+            "kind": "constant",
+            "duration_ms": duration_ms,
+            "dx": 0.0,
+            "dy": 1.0
+        }))
+    };
+
+    assert!(curve(50 * MAX_STEPS as u64).generate().is_ok());
+
+    let error = curve(50 * MAX_STEPS as u64 + 1).generate().unwrap_err();
+    assert_eq!(
+        error,
+        format!(
+            "the curve is cut into {} steps, at most {} are allowed",
+            MAX_STEPS + 1,
+            MAX_STEPS
+        )
+    );
+}
+
+#[test]
+fn generated_steps_are_not_saved() {
+    let mut collection: PatternCollection = serde_json::from_value(json!({
+        "patterns": {
+            "generated": {
+                "generator": {
+                    "step_ms": 50,
+                    "kind": "constant",
+                    "duration_ms": 100,
+                    "dx": 0.0,
+                    "dy": 3.0
+                },
+                "images": []
+            },
+            "manual": {
+                "config": "50ms: 0,2",
+                "images": []
+            }
+        },
+        "sensitivity": { "x": 1.0, "y": 1.0 }
+    }))
+    .expect("invalid configuration");
+    collection.generate_steps().unwrap();
+    assert_eq!(collection.get_patterns()["generated"].config.len(), 1);
+
+    let saved: serde_json::Value = serde_json::from_str(&collection.to_json().unwrap()).unwrap();
+
+    assert!(saved["patterns"]["generated"]["generator"].is_object());
+    assert_eq!(saved["patterns"]["generated"]["config"], json!([]));
+    assert_eq!(
+        saved["patterns"]["manual"]["config"]
+            .as_array()
+            .unwrap()
+            .len(),
+        1
+    );
+
+    // Loading the saved configuration generates the same steps again
+    let mut reloaded: PatternCollection = serde_json::from_value(saved).unwrap();
+    reloaded.generate_steps().unwrap();
+    assert_eq!(reloaded.get_patterns()["generated"].config[0].duration, 100);
+}
@@ -0,0 +1,39 @@
+//! Creates and destroys controllers repeatedly to check that their threads are joined.
+
+mod common;
+
+use std::sync::Arc;
+
+use app_lib::mouse_controller::{MouseController, SystemClock};
+use app_lib::patterns::{ActivationRule, Sensitivity};
+use common::{HeldKeys, RecordingBackend, VirtualController};
+
+#[test]
+fn dropping_a_controller_joins_its_thread() {
+    for _ in 0..100 {
+        let backend = RecordingBackend::default();
+        let controller = MouseController::with_input(
+            Sensitivity { x: 1.0, y: 1.0 },
+            ActivationRule::default(),
+            Arc::new(SystemClock),
+            Box::new(HeldKeys::default()),
+            Box::new(backend.clone()),
+        );
+        drop(controller);
+
+        // The backend is only dropped once the thread has exited
+        assert_eq!(Arc::strong_count(&backend.0), 1);
+    }
+}
+
+#[test]
+fn shutdown_joins_a_thread_sleeping_on_a_virtual_clock() {
+    for _ in 0..100 {
+        let mut virtual_controller = VirtualController::new();
+        virtual_controller.controller.shutdown();
+        assert_eq!(Arc::strong_count(&virtual_controller.backend.0), 1);
+
+        // Shutting down again, and dropping afterwards, has no effect
+        virtual_controller.controller.shutdown();
+    }
+}
@@ -0,0 +1,79 @@
+//! Selects the stance of the held modifiers and checks how variants and multipliers change the movement.
+
+mod common;
+
+use app_lib::patterns::PatternCollection;
+use common::{pattern, VirtualController};
+use serde_json::json;
+
+/// Virtual key code of the shift key
+const SHIFT: i32 = 0x10;
+/// Virtual key code of the control key
+const CTRL: i32 = 0x11;
+
+/// Creates a configuration with the given modifiers
+fn collection(modifiers: serde_json::Value) -> PatternCollection {
+    serde_json::from_value(json!({
+        "patterns": {},
+        "sensitivity": { "x": 1.0, "y": 1.0 },
+        "modifiers": modifiers
+    }))
+    .expect("invalid configuration")
+}
+
+/// Modifiers for crouching on Ctrl and going prone on Shift, in that order of priority
+fn crouch_and_prone() -> serde_json::Value {
+    json!([
+        { "name": "crouch", "when": { "key": "Ctrl" }, "multiplier": 0.5 },
+        { "name": "prone", "when": { "key": "Shift" }, "multiplier": 0.25 }
+    ])
+}
+
+/// Returns the movement sent in the first iteration while the given modifier keys are held
+fn first_move(collection: &PatternCollection, ctrl: bool, shift: bool) -> (i32, i32) {
+    let mut virtual_controller = VirtualController::new();
+    virtual_controller.controller.update_settings(collection);
+    virtual_controller.controller.update_pattern(pattern(json!({
+        "config": "1000ms: 0,40",
+        "variants": { "prone": "1000ms: 8,0" },
+        "images": []
+    })));
+    virtual_controller.keys.set_key(CTRL, ctrl);
+    virtual_controller.keys.set_key(SHIFT, shift);
+    virtual_controller.tick();
+
+    virtual_controller.keys.set(true);
+    virtual_controller.tick();
+    virtual_controller.backend.moves()[0]
+}
+
+#[test]
+fn multiplier_scales_the_pattern_without_a_variant() {
+    let modifiers = collection(crouch_and_prone());
+
+    assert_eq!(first_move(&modifiers, false, false), (0, 40));
+    assert_eq!(first_move(&modifiers, true, false), (0, 20));
+}
+
+#[test]
+fn variant_replaces_the_steps_and_is_scaled_by_the_multiplier() {
+    let modifiers = collection(crouch_and_prone());
+    assert_eq!(first_move(&modifiers, false, true), (2, 0));
+
+    let unscaled = collection(json!([{ "name": "prone", "when": { "key": "Shift" } }]));
+    assert_eq!(first_move(&unscaled, false, true), (8, 0));
+}
+
+#[test]
+fn first_modifier_in_the_list_wins_when_several_are_held() {
+    assert_eq!(
+        first_move(&collection(crouch_and_prone()), true, true),
+        (0, 20)
+    );
+
+    let prone_first = collection(json!([
+        { "name": "prone", "when": { "key": "Shift" }, "multiplier": 0.25 },
+        { "name": "crouch", "when": { "key": "Ctrl" }, "multiplier": 0.5 }
+    ]));
+    assert_eq!(first_move(&prone_first, true, true), (2, 0));
+}
@@ -0,0 +1,121 @@
+//! Writes step lists in the compact notation and reads them back.
+
+mod common;
+
+use app_lib::patterns::{format_steps, parse_steps, Step, MAX_REPEAT_COUNT, MAX_STEPS};
+
+/// Returns the duration and movement of every step
+fn moves(steps: &[Step]) -> Vec<(u64, i32, i32)> {
+    steps
+        .iter()
+        .map(|step| (step.duration, step.dx, step.dy))
+        .collect()
+}
+
+/// Creates a step from its duration and movement
+fn step(duration: u64, dx: i32, dy: i32) -> Step {
+    Step {
+        duration,
+        dx,
+        dy,
+        adjusted_dx: 0,
+        adjusted_dy: 0,
+    }
+}
+
+#[test]
+fn formatted_steps_parse_back_to_the_same_steps() {
+    let steps = vec![
+        step(1000, -5, 122),
+        step(700, 0, 200),
+        step(700, 0, 200),
+        step(700, 0, 200),
+        step(24, 3, -1),
+        step(24, 3, -2),
+        step(0, 0, 0),
+    ];
+
+    let text = format_steps(&steps);
+    assert_eq!(
+        text,
+        "1000ms: -5,122; 700ms x3: 0,200; 24ms: 3,-1; 24ms: 3,-2; 0ms: 0,0"
+    );
+    assert_eq!(moves(&parse_steps(&text).unwrap()), moves(&steps));
+}
+
+#[test]
+fn runs_longer_than_the_repeat_cap_are_split() {
+    let steps = vec![step(10, 1, 2); MAX_REPEAT_COUNT + 5];
+
+    let text = format_steps(&steps);
+    assert_eq!(
+        text,
+        format!("10ms x{}: 1,2; 10ms x5: 1,2", MAX_REPEAT_COUNT)
+    );
+    assert_eq!(moves(&parse_steps(&text).unwrap()), moves(&steps));
+}
+
+#[test]
+fn whitespace_and_a_trailing_separator_are_allowed() {
+    let steps = parse_steps("  24ms x2 : 1 , -3 ;\n 30ms: +4,0; ").unwrap();
+
+    assert_eq!(moves(&steps), vec![(24, 1, -3), (24, 1, -3), (30, 4, 0)]);
+}
+
+#[test]
+fn errors_point_at_the_offending_column() {
+    let error = parse_steps("24ms: 1,2; 30ms 5,6").unwrap_err();
+    assert_eq!(error.column, 17);
+    assert_eq!(error.message, "expected `:` after the duration");
+
+    let error = parse_steps("24ms x0: 1,2").unwrap_err();
+    assert_eq!(error.column, 7);
+    assert_eq!(error.message, "the repeat count must be at least 1");
+
+    let error = parse_steps("24ms: 1,99999999999").unwrap_err();
+    assert_eq!(error.column, 9);
+}
+
+#[test]
+fn repeat_counts_above_the_cap_are_rejected() {
+    let text = format!("24ms: 0,1; 24ms x{}: 0,1", MAX_REPEAT_COUNT + 1);
+
+    let error = parse_steps(&text).unwrap_err();
+    assert_eq!(error.column, 18);
+    assert_eq!(
+        error.message,
+        format!("the repeat count must be at most {}", MAX_REPEAT_COUNT)
+    );
+}
+
+#[test]
+fn step_lists_expanding_beyond_the_cap_are_rejected() {
+    let full = format!("24ms x{}: 0,1; ", MAX_REPEAT_COUNT).repeat(MAX_STEPS / MAX_REPEAT_COUNT);
+    assert_eq!(parse_steps(&full).unwrap().len(), MAX_STEPS);
+
+    let text = format!("{}30ms: 0,1", full);
+    let error = parse_steps(&text).unwrap_err();
+    assert_eq!(error.column, full.chars().count() + 1);
+    assert_eq!(
+        error.message,
+        format!("the steps expand to more than {} steps", MAX_STEPS)
+    );
+}
+
+#[test]
+fn patterns_accept_steps_in_the_compact_notation() {
+    let pattern = common::pattern(serde_json::json!({
+        "images": [],
+        "config": "24ms x2: 0,5; 24ms: 1,6",
+        "variants": { "burst": "30ms x3: 2,4" }
+    }));
+
+    assert_eq!(
+        moves(&pattern.config),
+        vec![(24, 0, 5), (24, 0, 5), (24, 1, 6)]
+    );
+    assert_eq!(
+        moves(&pattern.variants["burst"]),
+        vec![(30, 2, 4), (30, 2, 4), (30, 2, 4)]
+    );
+}
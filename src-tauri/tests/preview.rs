@@ -0,0 +1,117 @@
+//! Renders simulated paths and checks where they are drawn in the image.
+
+mod common;
+
+use app_lib::mouse_controller::simulate;
+use app_lib::patterns::PatternCollection;
+use app_lib::preview::{render, PreviewFormat, PreviewOptions, PreviewSeries, MAX_PREVIEW_SIZE};
+use common::{pattern, TICK};
+use serde_json::json;
+
+/// Simulates a pattern of ten steps that all move by the same amount
+fn series(label: &str, dx: i32, dy: i32) -> PreviewSeries {
+    let collection: PatternCollection = serde_json::from_value(json!({
+        "patterns": {},
+        "sensitivity": { "x": 1.0, "y": 1.0 }
+    }))
+    .expect("invalid configuration");
+    let pattern = pattern(json!({
+        "images": [],
+        "config": format!("24ms x10: {},{}", dx, dy)
+    }));
+
+    PreviewSeries {
+        label: label.to_string(),
+        trajectory: simulate(&pattern, &collection, TICK, TICK * 10),
+    }
+}
+
+/// Returns the points of every path drawn in an SVG preview
+fn polylines(svg: &str) -> Vec<Vec<(f32, f32)>> {
+    svg.split(r#"<polyline points=""#)
+        .skip(1)
+        .map(|rest| {
+            rest[..rest.find('"').unwrap()]
+                .split(' ')
+                .map(|point| {
+                    let (x, y) = point.split_once(',').unwrap();
+                    (x.parse().unwrap(), y.parse().unwrap())
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Renders the series as an SVG document
+fn render_svg(series: &[PreviewSeries], options: &PreviewOptions) -> String {
+    String::from_utf8(render(series, options, PreviewFormat::Svg).unwrap()).unwrap()
+}
+
+#[test]
+fn path_fills_the_image_inside_the_margin() {
+    let options = PreviewOptions::default();
+
+    let svg = render_svg(&[series("rifle", 2, 5)], &options);
+
+    let path = &polylines(&svg)[0];
+    assert_eq!(path.len(), 11);
+    let margin = options.margin as f32;
+    for &(x, y) in path {
+        assert!(x >= margin && x <= options.width as f32 - margin, "x = {x}");
+        assert!(
+            y >= margin && y <= options.height as f32 - margin,
+            "y = {y}"
+        );
+    }
+
+    // The longer axis spans the image between the margins, downward movement goes down
+    let (start, end) = (path[0], path[path.len() - 1]);
+    assert!((start.1 - margin).abs() < 0.1);
+    assert!((end.1 - (options.height as f32 - margin)).abs() < 0.1);
+
+    // Both axes use the same scale
+    let scale = (end.1 - start.1) / 50.0;
+    assert!((end.0 - start.0 - 20.0 * scale).abs() < 0.1);
+}
+
+#[test]
+fn overlaid_paths_share_the_same_scale() {
+    let options = PreviewOptions {
+        width: 300,
+        height: 500,
+        margin: 0,
+    };
+
+    let svg = render_svg(&[series("full", 0, 4), series("half", 0, 2)], &options);
+
+    let paths = polylines(&svg);
+    let length = |path: &Vec<(f32, f32)>| path[path.len() - 1].1 - path[0].1;
+    assert!((length(&paths[0]) - 500.0).abs() < 0.1);
+    assert!((length(&paths[1]) - 250.0).abs() < 0.1);
+    assert_eq!(paths[0][0], paths[1][0]);
+    assert!(svg.contains(r#"width="300" height="500""#));
+}
+
+#[test]
+fn images_outside_the_size_limits_are_rejected() {
+    let series = [series("rifle", 2, 5)];
+
+    for (width, height) in [(0, 640), (640, 0), (MAX_PREVIEW_SIZE + 1, 640)] {
+        let options = PreviewOptions {
+            width,
+            height,
+            ..PreviewOptions::default()
+        };
+
+        for format in [PreviewFormat::Svg, PreviewFormat::Png] {
+            assert!(render(&series, &options, format).is_err());
+        }
+    }
+
+    let largest = PreviewOptions {
+        width: MAX_PREVIEW_SIZE,
+        height: MAX_PREVIEW_SIZE,
+        ..PreviewOptions::default()
+    };
+    assert!(render(&series, &largest, PreviewFormat::Svg).is_ok());
+}
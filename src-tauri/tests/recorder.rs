@@ -0,0 +1,84 @@
+//! Records synthetic mouse movement and turns it into steps.
+
+mod common;
+
+use std::time::Duration;
+
+use app_lib::patterns::Sensitivity;
+use app_lib::recorder::{record, RecorderError, RecorderSettings, SyntheticSource};
+use common::SteppingClock;
+
+/// Virtual key codes of the left and right mouse buttons, held by the default activation rule
+const FIRE: &[i32] = &[0x01, 0x02];
+
+/// Settings with a tick that divides the 100 ms buckets evenly
+fn settings() -> RecorderSettings {
+    RecorderSettings {
+        tick: Duration::from_millis(25),
+        start_timeout: Duration::from_millis(100),
+        ..RecorderSettings::default()
+    }
+}
+
+#[test]
+fn recorded_movement_becomes_inverted_steps() {
+    let mut source = SyntheticSource::new();
+    source
+        // Movement before the activation is discarded, including its first tick
+        .repeat(2, &[], 5, 5)
+        .push(FIRE, 9, 9)
+        // Two buckets pulling left and down, then one pulling down less
+        .repeat(8, FIRE, 2, -4)
+        .repeat(3, FIRE, 0, -2)
+        // Movement during the tick the buttons are released still counts
+        .push(&[], 0, -2)
+        .repeat(4, &[], 7, 7);
+
+    let recording = record(&mut source, &SteppingClock::default(), &settings()).unwrap();
+
+    assert_eq!(recording.samples.len(), 12);
+    assert_eq!(recording.duration(), Duration::from_millis(300));
+    assert_eq!(recording.samples[0].time, Duration::from_millis(25));
+
+    // Steps are multiplied by the sensitivity the controller divides them by
+    let steps = recording.to_steps(Duration::from_millis(100), &Sensitivity { x: 2.0, y: 2.0 });
+    let steps: Vec<(u64, i32, i32)> = steps
+        .iter()
+        .map(|step| (step.duration, step.dx, step.dy))
+        .collect();
+    assert_eq!(steps, vec![(200, -4, 8), (100, 0, 4)]);
+}
+
+#[test]
+fn recording_stops_at_the_maximum_duration() {
+    let mut source = SyntheticSource::new();
+    source.push(FIRE, 0, 0).repeat(100, FIRE, 0, -1);
+
+    let settings = RecorderSettings {
+        max_duration: Duration::from_millis(200),
+        ..settings()
+    };
+    let recording = record(&mut source, &SteppingClock::default(), &settings).unwrap();
+
+    assert_eq!(recording.duration(), Duration::from_millis(200));
+}
+
+#[test]
+fn recording_times_out_without_activation() {
+    let mut source = SyntheticSource::new();
+    source.repeat(10, &[0x01], 1, 1);
+
+    let result = record(&mut source, &SteppingClock::default(), &settings());
+
+    assert!(matches!(result, Err(RecorderError::Timeout)));
+}
+
+#[test]
+fn recording_without_movement_fails() {
+    let mut source = SyntheticSource::new();
+    source.repeat(5, FIRE, 0, 0);
+
+    let result = record(&mut source, &SteppingClock::default(), &settings());
+
+    assert!(matches!(result, Err(RecorderError::NoMovement)));
+}
@@ -0,0 +1,109 @@
+//! Evaluates pattern scripts on every kind of pattern and checks the counters they see.
+
+mod common;
+
+use app_lib::mouse_controller::simulate;
+use app_lib::patterns::PatternCollection;
+use common::{pattern, VirtualController, TICK};
+use serde_json::json;
+
+/// Creates a configuration that leaves the movement unchanged
+fn collection() -> PatternCollection {
+    serde_json::from_value(json!({
+        "patterns": {},
+        "sensitivity": { "x": 1.0, "y": 1.0 }
+    }))
+    .expect("invalid configuration")
+}
+
+#[test]
+fn script_without_steps_compensates() {
+    let script_only = pattern(json!({
+        "script": { "source": "[0, tick + 1]" },
+        "images": []
+    }));
+
+    let trajectory = simulate(&script_only, &collection(), TICK, TICK * 4);
+
+    let dy: Vec<i32> = trajectory.moves.iter().map(|step| step.dy).collect();
+    assert_eq!(dy, vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn fire_rate_script_sees_the_bullets_fired() {
+    // One bullet every 100 ms, the script replaces every offset
+    let smg = pattern(json!({
+        "fire_rate": {
+            "rpm": 600,
+            "magazine": 3,
+            "offsets": [{ "dx": 0, "dy": 50 }]
+        },
+        "script": { "source": "[0, shots]" },
+        "images": []
+    }));
+
+    let trajectory = simulate(&smg, &collection(), TICK, TICK * 12);
+
+    // Bullets are fired at 0, 100 and 200 ms; the script stops once the magazine is empty
+    let moves: Vec<(u64, i32)> = trajectory
+        .moves
+        .iter()
+        .map(|step| (step.time_ms, step.dy))
+        .collect();
+    assert_eq!(
+        moves,
+        vec![
+            (0, 1),
+            (24, 1),
+            (48, 1),
+            (72, 1),
+            (96, 1),
+            (120, 2),
+            (144, 2),
+            (168, 2),
+            (192, 2),
+            (216, 3),
+        ]
+    );
+}
+
+#[test]
+fn semi_auto_script_sees_the_shots_fired() {
+    let mut virtual_controller = VirtualController::new();
+    virtual_controller.controller.update_pattern(pattern(json!({
+        "semi_auto": { "shots": [{ "dx": 0, "dy": 5 }] },
+        "script": { "source": "[step_dy, shots * 10]" },
+        "images": []
+    })));
+    virtual_controller.tick();
+
+    // Every press fires a shot whose impulse is applied at once
+    for _ in 0..3 {
+        virtual_controller.keys.set(true);
+        virtual_controller.tick();
+        virtual_controller.keys.set(false);
+        virtual_controller.tick();
+    }
+
+    assert_eq!(
+        virtual_controller.backend.moves(),
+        vec![(5, 10), (5, 20), (5, 30)]
+    );
+}
+
+#[test]
+fn replacing_the_steps_drops_the_script() {
+    let mut collection = collection();
+    collection.patterns.insert(
+        "scripted".to_string(),
+        pattern(json!({
+            "config": "24ms: 0,1",
+            "script": { "source": "[0, 9]" },
+            "images": []
+        })),
+    );
+
+    collection.set_steps("scripted", vec![common::step(24, 0, 2)], None);
+
+    assert!(collection.get_patterns()["scripted"].script.is_none());
+}
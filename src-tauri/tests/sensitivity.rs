@@ -0,0 +1,114 @@
+//! Combines the pattern sensitivity, scope multipliers and game settings into the divisor of a movement.
+
+mod common;
+
+use app_lib::patterns::PatternCollection;
+use common::{pattern, VirtualController};
+use serde_json::json;
+
+/// Creates a configuration with the given global sensitivity and game settings
+fn collection(sensitivity: f32, game: serde_json::Value) -> PatternCollection {
+    serde_json::from_value(json!({
+        "patterns": {},
+        "sensitivity": { "x": sensitivity, "y": sensitivity },
+        "game": game
+    }))
+    .expect("invalid configuration")
+}
+
+/// Creates game settings with the given in-game sensitivity
+fn game_settings(sensitivity: f32) -> serde_json::Value {
+    json!({ "dpi": 800.0, "sensitivity": sensitivity })
+}
+
+/// Creates a pattern that moves 120 pixels down on every iteration
+fn pattern_with(settings: serde_json::Value) -> app_lib::patterns::Pattern {
+    let mut value = json!({
+        "config": "1000ms: 0,120",
+        "scopes": { "4x": 2.0, "red dot": 0.25 },
+        "images": []
+    });
+    value
+        .as_object_mut()
+        .unwrap()
+        .extend(settings.as_object().unwrap().clone());
+    pattern(value)
+}
+
+/// Returns the vertical movement sent in the first iteration of the pattern
+fn first_move(
+    collection: &PatternCollection,
+    pattern: app_lib::patterns::Pattern,
+    scope: Option<&str>,
+) -> i32 {
+    let mut virtual_controller = VirtualController::new();
+    virtual_controller.controller.update_settings(collection);
+    virtual_controller.controller.update_pattern(pattern);
+    virtual_controller
+        .controller
+        .set_scope(scope.map(str::to_string));
+    virtual_controller.tick();
+
+    virtual_controller.keys.set(true);
+    virtual_controller.tick();
+    virtual_controller.backend.moves()[0].1
+}
+
+#[test]
+fn scope_multiplies_the_sensitivity_of_the_pattern() {
+    let global = collection(2.0, serde_json::Value::Null);
+    let own = || pattern_with(json!({ "sensitivity": { "x": 3.0, "y": 3.0 } }));
+
+    // The pattern's sensitivity replaces the global one instead of multiplying it
+    assert_eq!(first_move(&global, pattern_with(json!({})), None), 60);
+    assert_eq!(first_move(&global, own(), None), 40);
+    assert_eq!(first_move(&global, own(), Some("4x")), 20);
+
+    // Scopes the pattern does not define leave the sensitivity unchanged
+    assert_eq!(first_move(&global, own(), Some("8x")), 40);
+}
+
+#[test]
+fn sensitivity_of_one_and_below_leaves_the_movement_unchanged_without_game_settings() {
+    let global = collection(2.0, serde_json::Value::Null);
+
+    // 3.0 * 0.25 = 0.75 would enlarge the movement, so it is not applied
+    let own = pattern_with(json!({ "sensitivity": { "x": 3.0, "y": 3.0 } }));
+    assert_eq!(first_move(&global, own, Some("red dot")), 120);
+}
+
+#[test]
+fn game_settings_scale_the_combined_divisor() {
+    // The current player turns twice as far per count as the reference
+    let game = collection(
+        1.0,
+        json!({ "reference": game_settings(2.0), "current": game_settings(4.0) }),
+    );
+    let own = || pattern_with(json!({ "sensitivity": { "x": 3.0, "y": 3.0 } }));
+
+    // 3.0 * 2.0 from the scope, then doubled by the game settings
+    assert_eq!(first_move(&game, own(), Some("4x")), 10);
+    assert_eq!(first_move(&game, own(), None), 20);
+
+    // Values below 1.0 are raised to 1.0 before the game settings apply
+    assert_eq!(first_move(&game, own(), Some("red dot")), 60);
+
+    // The baseline of the pattern replaces the reference
+    let tuned = pattern_with(json!({
+        "sensitivity": { "x": 3.0, "y": 3.0 },
+        "baseline": game_settings(4.0)
+    }));
+    assert_eq!(first_move(&game, tuned, Some("4x")), 20);
+}
+
+#[test]
+fn game_settings_enlarge_the_movement_for_a_slower_player() {
+    // The current player turns half as far per count as the reference
+    let game = collection(
+        1.0,
+        json!({ "reference": game_settings(2.0), "current": game_settings(1.0) }),
+    );
+
+    assert_eq!(first_move(&game, pattern_with(json!({})), None), 240);
+    assert_eq!(first_move(&game, pattern_with(json!({})), Some("4x")), 120);
+}
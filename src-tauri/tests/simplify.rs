@@ -0,0 +1,120 @@
+//! Simplifies step lists and checks the result against what the controller sends.
+
+mod common;
+
+use app_lib::mouse_controller::simulate;
+use app_lib::patterns::{simplify_steps, PatternCollection, Step};
+use common::{pattern, TICK};
+use serde_json::json;
+
+/// Creates a configuration that leaves the movement unchanged
+fn collection() -> PatternCollection {
+    serde_json::from_value(json!({
+        "patterns": {},
+        "sensitivity": { "x": 1.0, "y": 1.0 }
+    }))
+    .expect("invalid configuration")
+}
+
+/// Returns the position after every iteration while the steps are held for `ticks` iterations
+fn positions(steps: &[Step], ticks: u32) -> Vec<(i32, i32)> {
+    let mut pattern = pattern(json!({ "images": [] }));
+    pattern.config = steps.to_vec();
+    let trajectory = simulate(&pattern, &collection(), TICK, TICK * ticks);
+
+    let tick_ms = TICK.as_millis() as u64;
+    let mut positions = vec![(0, 0); ticks as usize];
+    for step in &trajectory.moves {
+        let index = (step.time_ms / tick_ms) as usize;
+        for position in positions.iter_mut().skip(index) {
+            *position = (step.x, step.y);
+        }
+    }
+    positions
+}
+
+/// Returns the duration and movement of every step
+fn moves(steps: &[Step]) -> Vec<(u64, i32, i32)> {
+    steps
+        .iter()
+        .map(|step| (step.duration, step.dx, step.dy))
+        .collect()
+}
+
+/// Creates an irregular recoil pattern with fractional slopes
+fn spray() -> Vec<Step> {
+    app_lib::patterns::parse_steps(
+        "48ms: 0,3; 72ms: 1,4; 48ms: 0,5; 96ms: 2,6; 48ms: 1,2; 72ms: 0,3; 48ms: -1,4; \
+         50ms: 0,5; 48ms: 1,5; 48ms: 0,4; 96ms: -2,2; 48ms: 0,2; 500ms: 0,1",
+    )
+    .unwrap()
+}
+
+#[test]
+fn straight_run_collapses_to_one_step() {
+    let steps = app_lib::patterns::parse_steps("24ms x10: 2,5").unwrap();
+
+    let (simplified, report) = simplify_steps(&steps, TICK, 1.0);
+
+    assert_eq!(simplified.len(), 1);
+    assert_eq!((simplified[0].dx, simplified[0].dy), (2, 5));
+    assert_eq!((report.original_steps, report.steps), (10, 1));
+    assert!(report.max_error < 1e-3);
+    assert_eq!(positions(&simplified, 40), positions(&steps, 40));
+}
+
+#[test]
+fn simulated_error_stays_within_the_tolerance() {
+    let steps = spray();
+
+    for tolerance in [0.5, 2.0, 3.0, 4.0, 6.0] {
+        let (simplified, report) = simplify_steps(&steps, TICK, tolerance);
+        assert!(simplified.len() <= steps.len());
+
+        // Every part but the first, which is sent once more, lasts at least two iterations
+        let tick_ms = TICK.as_millis() as u64;
+        assert!(simplified[1..]
+            .iter()
+            .all(|step| step.duration >= 2 * tick_ms));
+
+        let measured = positions(&steps, 80)
+            .into_iter()
+            .zip(positions(&simplified, 80))
+            .map(|(a, b)| ((a.0 - b.0) as f32).hypot((a.1 - b.1) as f32))
+            .fold(0.0, f32::max);
+        assert!(measured <= tolerance, "{measured} > {tolerance}");
+        assert!((report.max_error - measured).abs() < 1e-3);
+    }
+}
+
+#[test]
+fn last_step_and_trailing_hold_are_kept() {
+    let steps = spray();
+
+    let (simplified, report) = simplify_steps(&steps, TICK, 4.0);
+
+    assert!(simplified.len() < steps.len());
+    assert_eq!(moves(&simplified).last(), moves(&steps).last());
+    assert!(report.end_error <= 4.0);
+
+    // Holding past the end of the pattern keeps sending the last step, so the paths
+    // stay apart by the end error
+    let original = positions(&steps, 200);
+    let result = positions(&simplified, 200);
+    for (a, b) in original[150..].iter().zip(&result[150..]) {
+        let error = ((a.0 - b.0) as f32).hypot((a.1 - b.1) as f32);
+        assert!((error - report.end_error).abs() < 1e-3);
+    }
+    assert_eq!(result[199].1 - result[198].1, 1);
+}
+
+#[test]
+fn zero_tolerance_returns_the_steps_unchanged() {
+    let steps = spray();
+
+    let (simplified, report) = simplify_steps(&steps, TICK, 0.0);
+
+    assert_eq!(moves(&simplified), moves(&steps));
+    assert_eq!(report.steps, steps.len());
+    assert_eq!(report.max_error, 0.0);
+}
@@ -0,0 +1,131 @@
+//! Simulates patterns with the settings of a configuration and compares them with the live controller.
+
+mod common;
+
+use app_lib::mouse_controller::{analyze, simulate, MAX_SIMULATED_HOLD, SCRIPT_WINDOW};
+use app_lib::patterns::{Pattern, PatternCollection};
+use common::{pattern, VirtualController, TICK};
+use serde_json::json;
+
+/// Creates a configuration whose game settings, strength and inversion all change the movement
+fn collection() -> PatternCollection {
+    serde_json::from_value(json!({
+        "patterns": {},
+        "sensitivity": { "x": 1.0, "y": 1.0 },
+        "game": {
+            "reference": { "dpi": 800.0, "sensitivity": 50.0 },
+            "current": { "dpi": 800.0, "sensitivity": 25.0 }
+        },
+        "transform": { "strength": 150.0, "invert_x": true }
+    }))
+    .expect("invalid configuration")
+}
+
+/// Creates a pattern of three steps that last four iterations each
+fn spray() -> Pattern {
+    pattern(json!({
+        "images": [],
+        "config": "96ms x3: 4,20"
+    }))
+}
+
+#[test]
+fn simulation_applies_the_game_settings_and_transforms() {
+    let trajectory = simulate(&spray(), &collection(), TICK, TICK * 12);
+
+    // Half the in-game sensitivity doubles the counts, then strength and inversion apply
+    assert_eq!(trajectory.moves.len(), 12);
+    assert!(trajectory
+        .moves
+        .iter()
+        .all(|step| (step.dx, step.dy) == (-12, 60)));
+}
+
+#[test]
+fn statistics_apply_the_game_settings_and_transforms() {
+    let stats = analyze(&spray(), &collection(), TICK);
+
+    assert_eq!((stats.raw_dx, stats.raw_dy), (48, 240));
+    assert_eq!((stats.dx, stats.dy), (-144, 720));
+    assert_eq!((stats.peak_dx, stats.peak_dy), (12, 60));
+}
+
+#[test]
+fn simulation_matches_the_controller_with_the_same_configuration() {
+    let collection = collection();
+    let trajectory = simulate(&spray(), &collection, TICK, TICK * 12);
+
+    let mut virtual_controller = VirtualController::new();
+    virtual_controller.controller.update_settings(&collection);
+    virtual_controller.controller.update_pattern(spray());
+    virtual_controller.tick();
+
+    virtual_controller.keys.set(true);
+    virtual_controller.run(12);
+    virtual_controller.keys.set(false);
+    virtual_controller.run(3);
+
+    let simulated: Vec<(i32, i32)> = trajectory
+        .moves
+        .iter()
+        .map(|step| (step.dx, step.dy))
+        .collect();
+    assert_eq!(virtual_controller.backend.moves(), simulated);
+}
+
+#[test]
+fn simulation_holds_at_most_the_maximum_hold() {
+    let trajectory = simulate(&spray(), &collection(), TICK, MAX_SIMULATED_HOLD * 10);
+
+    let tick_ms = TICK.as_millis() as u64;
+    let hold_ms = MAX_SIMULATED_HOLD.as_millis() as u64;
+    let last = trajectory.moves.last().unwrap();
+    assert_eq!(last.time_ms, (hold_ms - 1) / tick_ms * tick_ms);
+    assert_eq!(trajectory.moves.len() as u64, (hold_ms - 1) / tick_ms + 1);
+}
+
+#[test]
+fn statistics_include_the_start_delay() {
+    let mut delayed = spray();
+    delayed.start_delay_ms = 48;
+
+    let stats = analyze(&delayed, &collection(), TICK);
+
+    // The whole pattern is still sent after the delay
+    assert_eq!(stats.duration_ms, Some(96 * 3 + 48));
+    assert_eq!((stats.dx, stats.dy), (-144, 720));
+}
+
+#[test]
+fn scripts_without_steps_are_measured_over_a_fixed_window() {
+    let script_only = pattern(json!({
+        "script": { "source": "[0, 1]" },
+        "images": []
+    }));
+
+    let stats = analyze(&script_only, &collection(), TICK);
+
+    let ticks = SCRIPT_WINDOW.as_millis() as i32 / TICK.as_millis() as i32;
+    assert_eq!(stats.duration_ms, None);
+    assert_eq!(stats.raw_dy, ticks);
+    assert_eq!(stats.dy, ticks * 3);
+}
+
+#[test]
+fn steps_reduced_to_zero_by_the_strength_are_collapsed() {
+    let collection: PatternCollection = serde_json::from_value(json!({
+        "patterns": {},
+        "sensitivity": { "x": 1.0, "y": 1.0 },
+        "transform": { "strength": 40.0 }
+    }))
+    .expect("invalid configuration");
+    let weak = pattern(json!({
+        "images": [],
+        "config": "24ms: 0,1; 24ms: 0,5; 24ms: 1,10"
+    }));
+
+    let stats = analyze(&weak, &collection, TICK);
+
+    // One pixel at 40% rounds to zero, vertically in the first step and horizontally in the last
+    assert_eq!(stats.collapsed_steps, 2);
+}
@@ -0,0 +1,139 @@
+//! Observes the phase transitions of the controller thread on a virtual clock.
+
+mod common;
+
+use std::sync::mpsc::Receiver;
+
+use app_lib::mouse_controller::{ControllerPhase, ControllerTransition};
+use common::{pattern, step, VirtualController};
+use serde_json::json;
+
+/// Returns the phases entered since the last call
+fn phases(transitions: &Receiver<ControllerTransition>) -> Vec<ControllerPhase> {
+    transitions.try_iter().map(|transition| transition.to).collect()
+}
+
+#[test]
+fn controller_without_pattern_stays_idle_while_active() {
+    let mut virtual_controller = VirtualController::new();
+    let transitions = virtual_controller.controller.take_transitions().unwrap();
+
+    virtual_controller.keys.set(true);
+    virtual_controller.run(5);
+
+    assert!(phases(&transitions).is_empty());
+    assert!(virtual_controller.backend.moves().is_empty());
+}
+
+#[test]
+fn clearing_the_pattern_returns_to_idle() {
+    let mut virtual_controller = VirtualController::new();
+    let transitions = virtual_controller.controller.take_transitions().unwrap();
+
+    virtual_controller.controller.update_steps(vec![step(100, 0, 1)]);
+    virtual_controller.tick();
+    virtual_controller.controller.clear_steps();
+    virtual_controller.tick();
+
+    assert_eq!(
+        phases(&transitions),
+        vec![ControllerPhase::Armed, ControllerPhase::Idle]
+    );
+}
+
+#[test]
+fn disabled_controller_ignores_the_activation_until_enabled() {
+    let mut virtual_controller = VirtualController::new();
+    let transitions = virtual_controller.controller.take_transitions().unwrap();
+
+    virtual_controller.controller.update_steps(vec![step(100, 0, 1)]);
+    virtual_controller.controller.set_enabled(false);
+    virtual_controller.keys.set(true);
+    virtual_controller.run(3);
+    assert_eq!(phases(&transitions), vec![ControllerPhase::Disabled]);
+    assert!(virtual_controller.backend.moves().is_empty());
+
+    virtual_controller.controller.set_enabled(true);
+    virtual_controller.tick();
+    assert_eq!(
+        phases(&transitions),
+        vec![ControllerPhase::Compensating { step: 0 }]
+    );
+    assert_eq!(virtual_controller.backend.moves(), vec![(0, 1)]);
+}
+
+#[test]
+fn panic_disables_a_running_spray() {
+    let mut virtual_controller = VirtualController::new();
+    let transitions = virtual_controller.controller.take_transitions().unwrap();
+
+    virtual_controller.controller.update_steps(vec![step(100, 0, 1)]);
+    virtual_controller.keys.set(true);
+    virtual_controller.run(2);
+    virtual_controller.controller.panic();
+    virtual_controller.run(2);
+
+    assert_eq!(
+        phases(&transitions),
+        vec![
+            ControllerPhase::Compensating { step: 0 },
+            ControllerPhase::Disabled
+        ]
+    );
+    assert_eq!(virtual_controller.backend.moves().len(), 2);
+}
+
+#[test]
+fn fire_rate_pattern_finishes_when_the_magazine_is_empty() {
+    let mut virtual_controller = VirtualController::new();
+    let transitions = virtual_controller.controller.take_transitions().unwrap();
+
+    // One bullet every 100 ms
+    virtual_controller.controller.update_pattern(pattern(json!({
+        "fire_rate": {
+            "rpm": 600,
+            "magazine": 5,
+            "offsets": [
+                { "dx": 0, "dy": 1 },
+                { "dx": 0, "dy": 2 },
+                { "dx": 0, "dy": 3 },
+                { "dx": 0, "dy": 4 },
+                { "dx": 0, "dy": 5 }
+            ]
+        },
+        "images": []
+    })));
+    virtual_controller.tick();
+    virtual_controller.keys.set(true);
+
+    // Bullets are fired at 0, 100, 200, 300 and 400 ms, each applied by the first iteration at or after it
+    let mut moves_at = Vec::new();
+    for tick in 0..20 {
+        virtual_controller.tick();
+        let moves = virtual_controller.backend.moves();
+        if moves.len() > moves_at.len() {
+            moves_at.push((tick, *moves.last().unwrap()));
+        }
+    }
+
+    assert_eq!(
+        moves_at,
+        vec![(0, (0, 1)), (5, (0, 2)), (9, (0, 3)), (13, (0, 4)), (17, (0, 5))]
+    );
+    assert_eq!(
+        phases(&transitions),
+        vec![
+            ControllerPhase::Armed,
+            ControllerPhase::Compensating { step: 0 },
+            ControllerPhase::Compensating { step: 1 },
+            ControllerPhase::Compensating { step: 2 },
+            ControllerPhase::Compensating { step: 3 },
+            ControllerPhase::Finished,
+        ]
+    );
+
+    // Releasing the trigger re-arms the controller for the next magazine
+    virtual_controller.keys.set(false);
+    virtual_controller.tick();
+    assert_eq!(phases(&transitions), vec![ControllerPhase::Armed]);
+}
@@ -0,0 +1,103 @@
+//! Applies the movement transforms in order and clamps the movement of every iteration.
+
+mod common;
+
+use app_lib::mouse_controller::{simulate, TransformPipeline};
+use app_lib::patterns::{PatternCollection, Sensitivity, TransformSettings};
+use common::{pattern, VirtualController, TICK};
+use serde_json::json;
+
+/// Reads transform settings from JSON, leaving the other settings unchanged
+fn settings(value: serde_json::Value) -> TransformSettings {
+    serde_json::from_value(value).expect("invalid transform settings")
+}
+
+/// Creates a configuration with the given transforms
+fn collection(transform: serde_json::Value) -> PatternCollection {
+    serde_json::from_value(json!({
+        "patterns": {},
+        "sensitivity": { "x": 1.0, "y": 1.0 },
+        "transform": transform
+    }))
+    .expect("invalid configuration")
+}
+
+#[test]
+fn sensitivity_applies_before_strength() {
+    let pipeline = TransformPipeline::new(
+        &Sensitivity { x: 4.0, y: 4.0 },
+        &settings(json!({ "strength": 300.0 })),
+    );
+
+    // One pixel keeps its one-pixel minimum before it is tripled
+    assert_eq!(pipeline.apply(1, 8), (3, 6));
+}
+
+#[test]
+fn strength_and_scale_round_after_each_stage() {
+    let pipeline = TransformPipeline::new(
+        &Sensitivity { x: 1.0, y: 1.0 },
+        &settings(json!({ "strength": 150.0, "scale_x": 1.5, "scale_y": 0.5 })),
+    );
+
+    // 1.5 rounds to 2 before the multiplier, a single factor of 2.25 would round to 2
+    assert_eq!(pipeline.apply(1, 4), (3, 3));
+}
+
+#[test]
+fn inversion_applies_before_the_clamp_of_the_iteration() {
+    let pipeline = TransformPipeline::new(
+        &Sensitivity { x: 1.0, y: 1.0 },
+        &settings(json!({ "invert_x": true, "max_dx": 2, "max_dy": 3 })),
+    );
+
+    // Single values are not clamped, only what is sent in an iteration
+    assert_eq!(pipeline.apply(5, 5), (-5, 5));
+    assert_eq!(pipeline.limit(-5, 5), (-2, 3));
+}
+
+#[test]
+fn bullets_fired_in_the_same_iteration_are_clamped_together() {
+    // A bullet every 10 ms, so up to three are fired in a single iteration
+    let smg = pattern(json!({
+        "fire_rate": {
+            "rpm": 6000,
+            "magazine": 6,
+            "offsets": [{ "dx": 0, "dy": 10 }]
+        },
+        "images": []
+    }));
+
+    let trajectory = simulate(&smg, &collection(json!({ "max_dy": 15 })), TICK, TICK * 4);
+
+    let dy: Vec<i32> = trajectory.moves.iter().map(|step| step.dy).collect();
+    assert_eq!(dy, vec![10, 15, 15, 10]);
+}
+
+#[test]
+fn flushed_impulse_is_clamped_with_the_next_one() {
+    let mut virtual_controller = VirtualController::new();
+    virtual_controller
+        .controller
+        .update_settings(&collection(json!({ "max_dy": 12 })));
+    virtual_controller.controller.update_pattern(pattern(json!({
+        "semi_auto": { "shots": [{ "dx": 0, "dy": 40 }], "pull_ms": 96 },
+        "images": []
+    })));
+    virtual_controller.tick();
+
+    // The second press comes before the first impulse is complete
+    for _ in 0..2 {
+        virtual_controller.keys.set(true);
+        virtual_controller.tick();
+        virtual_controller.keys.set(false);
+        virtual_controller.tick();
+    }
+    virtual_controller.run(3);
+
+    // The 30 pixels left of the first impulse are sent at once, but no more than 12
+    assert_eq!(
+        virtual_controller.backend.moves(),
+        vec![(0, 10), (0, 12), (0, 10), (0, 10), (0, 10), (0, 10)]
+    );
+}
@@ -3,14 +3,62 @@ import "./App.module.css";
 import { listen } from "@tauri-apps/api/event";
 import { useEffect, useState } from "react";
 
+import {
+  BackendService,
+  type ControllerPhase,
+  type ControllerStatus,
+  type ControllerTransition,
+} from "./backend/backend.service";
+
 export const App = () => {
   const [activePattern, setActivePattern] = useState<string>();
+  const [controllerStatus, setControllerStatus] = useState<ControllerStatus>();
+  const [controllerPhase, setControllerPhase] = useState<ControllerPhase>();
+  const [scope, setScope] = useState<string>();
 
   useEffect(() => {
     listen<string>("pattern-selected", (value) => {
       setActivePattern(value.payload);
     });
+    listen<ControllerStatus>("controller-state", (value) => {
+      setControllerStatus(value.payload);
+    });
+    listen<string>("scope-selected", (value) => {
+      setScope(value.payload);
+    });
+    listen<ControllerTransition>("controller-transition", (value) => {
+      setControllerPhase(value.payload.to);
+    });
+    BackendService.getControllerStatus().then(setControllerStatus);
   }, []);
 
-  return <div>active pattern: {activePattern}</div>;
+  const controllerLabel = !controllerStatus
+    ? ""
+    : !controllerStatus.armed
+      ? "stopped (panic)"
+      : controllerStatus.enabled
+        ? "live"
+        : "disabled";
+
+  const phaseLabel =
+    controllerPhase?.phase === "compensating"
+      ? `compensating (step ${controllerPhase.step + 1})`
+      : controllerPhase?.phase;
+
+  return (
+    <div>
+      <div>active pattern: {activePattern}</div>
+      <div>
+        controller: {controllerLabel}{" "}
+        <button onClick={() => BackendService.toggleController()}>
+          toggle
+        </button>
+      </div>
+      <div>phase: {phaseLabel}</div>
+      <div>
+        scope: {scope}{" "}
+        <button onClick={() => BackendService.cycleScope()}>next scope</button>
+      </div>
+    </div>
+  );
 };
@@ -1,3 +1,215 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export type ControllerStatus = {
+  enabled: boolean;
+  armed: boolean;
+};
+
+export type ControllerPhase =
+  | { phase: "idle" }
+  | { phase: "armed" }
+  | { phase: "delayed" }
+  | { phase: "compensating"; step: number }
+  | { phase: "finished" }
+  | { phase: "disabled" };
+
+export type ControllerTransition = {
+  from: ControllerPhase;
+  to: ControllerPhase;
+};
+
+export type TransformSettings = {
+  strength: number;
+  scale_x: number;
+  scale_y: number;
+  invert_x: boolean;
+  invert_y: boolean;
+  max_dx: number | null;
+  max_dy: number | null;
+};
+
+export type GameSettings = {
+  dpi: number;
+  sensitivity: number;
+  ads_multiplier: number;
+  fov?: number;
+};
+
+export type GameSensitivity = {
+  reference: GameSettings;
+  current: GameSettings;
+};
+
+export type RoundingLoss = {
+  index: number;
+  exact_dx: number;
+  exact_dy: number;
+  dx: number;
+  dy: number;
+};
+
+export type ConversionReport = {
+  factor: number;
+  steps: RoundingLoss[];
+  offsets: RoundingLoss[];
+  shots: RoundingLoss[];
+  variants: Record<string, RoundingLoss[]>;
+  unconverted_script: boolean;
+};
+
+export type ConversionResult = {
+  config: unknown;
+  reports: Record<string, ConversionReport>;
+};
+
+export type SimplifyReport = {
+  original_steps: number;
+  steps: number;
+  max_error: number;
+  end_error: number;
+};
+
+export type SimplificationResult = {
+  config: unknown;
+  reports: Record<string, SimplifyReport>;
+};
+
+export type CsvConflict = {
+  pattern: string;
+  fields: ("steps" | "images")[];
+};
+
+export type MergeReport = {
+  added: string[];
+  unchanged: string[];
+  conflicts: CsvConflict[];
+  skipped: string[];
+  overwritten: boolean;
+};
+
+export type ImportResult = {
+  config: unknown;
+  report: MergeReport;
+};
+
+export type TrajectoryMove = {
+  time_ms: number;
+  step: number;
+  dx: number;
+  dy: number;
+  x: number;
+  y: number;
+};
+
+export type Trajectory = {
+  moves: TrajectoryMove[];
+  x: number;
+  y: number;
+};
+
+export type PatternStats = {
+  steps: number;
+  duration_ms: number | null;
+  raw_dx: number;
+  raw_dy: number;
+  raw_distance: number;
+  dx: number;
+  dy: number;
+  distance: number;
+  peak_dx: number;
+  peak_dy: number;
+  collapsed_steps: number;
+};
+
+export type Step = {
+  duration: number;
+  dx: number;
+  dy: number;
+};
+
+export type CalibrationSample = {
+  dx: number;
+  dy: number;
+  shift_x: number;
+  shift_y: number;
+  response: number;
+};
+
+export type Calibration = {
+  pixels_per_count_x: number;
+  pixels_per_count_y: number;
+  sensitivity: { x: number; y: number };
+  samples: CalibrationSample[];
+};
+
 export const BackendService = {
-  
+  getControllerStatus: () => invoke<ControllerStatus>("get_controller_status"),
+  toggleController: () => invoke<ControllerStatus>("toggle_controller"),
+  getTransform: () => invoke<TransformSettings>("get_transform"),
+  setTransform: (transform: TransformSettings) =>
+    invoke<TransformSettings>("set_transform", { transform }),
+  setStrength: (strength: number) =>
+    invoke<TransformSettings>("set_strength", { strength }),
+  setScope: (scope: string | null) =>
+    invoke<string | null>("set_scope", { scope }),
+  cycleScope: () => invoke<string | null>("cycle_scope"),
+  getGameSensitivity: () =>
+    invoke<GameSensitivity | null>("get_game_sensitivity"),
+  setGameSettings: (settings: GameSettings) =>
+    invoke<GameSensitivity | null>("set_game_settings", { settings }),
+  convertPatterns: (target: GameSettings, output?: string) =>
+    invoke<ConversionResult>("convert_patterns", {
+      target,
+      output: output ?? null,
+    }),
+  simplifyPatterns: (tolerance: number, tickMs?: number, output?: string) =>
+    invoke<SimplificationResult>("simplify_patterns", {
+      tolerance,
+      tickMs: tickMs ?? null,
+      output: output ?? null,
+    }),
+  exportPatternsCsv: (output?: string) =>
+    invoke<string>("export_patterns_csv", { output: output ?? null }),
+  importPatternsCsv: (csv: string, overwrite: boolean, output?: string) =>
+    invoke<ImportResult>("import_patterns_csv", {
+      csv,
+      overwrite,
+      output: output ?? null,
+    }),
+  simulatePattern: (name: string, holdMs: number, tickMs?: number) =>
+    invoke<Trajectory>("simulate_pattern", {
+      name,
+      holdMs,
+      tickMs: tickMs ?? null,
+    }),
+  renderPatternPreview: (
+    patterns: string[],
+    holdMs: number,
+    format: "svg" | "png",
+    sensitivities?: { x: number; y: number }[],
+    tickMs?: number,
+  ) =>
+    invoke<ArrayBuffer>("render_pattern_preview", {
+      patterns,
+      holdMs,
+      format,
+      sensitivities: sensitivities ?? null,
+      tickMs: tickMs ?? null,
+    }),
+  getPatternStats: (tickMs?: number) =>
+    invoke<Record<string, PatternStats>>("get_pattern_stats", {
+      tickMs: tickMs ?? null,
+    }),
+  recordPattern: (name: string, timeoutMs?: number, bucketMs?: number) =>
+    invoke<Step[]>("record_pattern", {
+      name,
+      timeoutMs: timeoutMs ?? null,
+      bucketMs: bucketMs ?? null,
+    }),
+  calibrateSensitivity: (moves?: number, step?: number, settleMs?: number) =>
+    invoke<Calibration>("calibrate_sensitivity", {
+      moves: moves ?? null,
+      step: step ?? null,
+      settleMs: settleMs ?? null,
+    }),
 };